@@ -0,0 +1,257 @@
+use crate::stats::{BenchmarkResult, LatencyStats, Style, percentile_label};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// 与基线对比的一项 P99 指标，单位 ms
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDiff {
+    pub name: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    /// current > baseline * (1 + threshold / 100)
+    pub regressed: bool,
+}
+
+impl MetricDiff {
+    /// 相对基线的变化百分比，正数表示变慢
+    pub fn change_pct(&self) -> f64 {
+        (self.current / self.baseline - 1.0) * 100.0
+    }
+}
+
+/// 将本次结果的各项 P99 延迟与 `--output-file` 保存的基线 JSON 对比，
+/// 任一侧缺少的指标不参与对比
+pub fn compare_with_baseline(
+    baseline: &Value,
+    current: &BenchmarkResult,
+    threshold_pct: f64,
+) -> Vec<MetricDiff> {
+    let itl: Vec<Duration> = current
+        .results
+        .iter()
+        .flat_map(|r| r.itl.iter().copied())
+        .collect();
+    let metrics = [
+        (
+            "P99 TTFT",
+            "ttft",
+            &current.ttft,
+            current.results.iter().map(|r| r.ttft).collect(),
+        ),
+        (
+            "P99 End-to-End",
+            "end_to_end",
+            &current.end_to_end,
+            current.results.iter().map(|r| r.total).collect(),
+        ),
+        ("P99 ITL", "itl", &current.itl, itl),
+    ];
+    metrics
+        .into_iter()
+        .filter_map(|(name, key, stats, samples)| {
+            let baseline = baseline.get(key)?.get("p99")?.as_f64()?;
+            let current = p99(stats.as_ref(), samples)?;
+            Some(MetricDiff {
+                name,
+                baseline,
+                current,
+                regressed: current > baseline * (1.0 + threshold_pct / 100.0),
+            })
+        })
+        .collect()
+}
+
+/// 未配置 99 百分位时从原始样本重新计算
+pub(crate) fn p99(stats: Option<&LatencyStats>, samples: Vec<Duration>) -> Option<f64> {
+    let configured = stats?.percentiles.iter().find(|(perc, _)| *perc == 99.0);
+    match configured {
+        Some(&(_, value)) => Some(value),
+        None => {
+            LatencyStats::from_samples(samples.into_iter(), &[99.0]).map(|s| s.percentiles[0].1)
+        }
+    }
+}
+
+/// 以表格打印对比结果，退化的指标标红
+pub fn print_baseline_comparison(diffs: &[MetricDiff], threshold_pct: f64, colored: bool) {
+    println!(
+        "\n--- Baseline comparison (threshold {}%) ---",
+        threshold_pct
+    );
+    println!(
+        "{:<16} {:>12} {:>12} {:>9}",
+        "Metric", "Baseline", "Current", "Change"
+    );
+    for diff in diffs {
+        let line = format!(
+            "{:<16} {:>9.2} ms {:>9.2} ms {:>+8.1}%{}",
+            diff.name,
+            diff.baseline,
+            diff.current,
+            diff.change_pct(),
+            if diff.regressed { "  REGRESSED" } else { "" }
+        );
+        if colored && diff.regressed {
+            println!("{}{}\x1b[0m", Style::Red.code(), line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// 两次结果之间一项指标的变化
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricChange {
+    pub name: String,
+    pub unit: &'static str,
+    pub before: f64,
+    pub after: f64,
+    /// 吞吐类指标越大越好，延迟与错误率越小越好
+    pub higher_is_better: bool,
+    /// 变化幅度超过阈值
+    pub significant: bool,
+}
+
+impl MetricChange {
+    fn new(
+        name: String,
+        unit: &'static str,
+        before: f64,
+        after: f64,
+        higher_is_better: bool,
+    ) -> Self {
+        Self {
+            name,
+            unit,
+            before,
+            after,
+            higher_is_better,
+            significant: false,
+        }
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+
+    /// before 为 0 时无法计算百分比
+    pub fn delta_pct(&self) -> Option<f64> {
+        (self.before != 0.0).then(|| self.delta() / self.before * 100.0)
+    }
+
+    /// 超过阈值且方向变差
+    pub fn regressed(&self) -> bool {
+        self.significant && (self.delta() > 0.0) != self.higher_is_better
+    }
+
+    pub fn improved(&self) -> bool {
+        self.significant && !self.regressed()
+    }
+}
+
+/// 对比两次结果的延迟（平均值与两者共有的百分位）、吞吐、RPS 和错误率；
+/// 变化百分比的绝对值超过 threshold_pct 时标记为显著
+pub fn compare_results(
+    before: &BenchmarkResult,
+    after: &BenchmarkResult,
+    threshold_pct: f64,
+) -> Vec<MetricChange> {
+    let mut changes = Vec::new();
+    let latencies = [
+        ("TTFT", &before.ttft, &after.ttft),
+        ("End-to-End", &before.end_to_end, &after.end_to_end),
+        ("ITL", &before.itl, &after.itl),
+    ];
+    for (name, before, after) in latencies {
+        let (Some(before), Some(after)) = (before, after) else {
+            continue;
+        };
+        changes.push(MetricChange::new(
+            format!("{name} Avg"),
+            "ms",
+            before.avg,
+            after.avg,
+            false,
+        ));
+        for &(perc, value) in &before.percentiles {
+            if let Some(&(_, after_value)) = after.percentiles.iter().find(|(p, _)| *p == perc) {
+                let label = format!("{name} {}", percentile_label(perc));
+                changes.push(MetricChange::new(label, "ms", value, after_value, false));
+            }
+        }
+    }
+    if let (Some(b), Some(a)) = (&before.throughput, &after.throughput) {
+        changes.push(MetricChange::new(
+            "Throughput Avg".into(),
+            "tokens/s",
+            b.avg,
+            a.avg,
+            true,
+        ));
+    }
+    changes.push(MetricChange::new(
+        "Requests/sec".into(),
+        "",
+        before.requests_per_sec,
+        after.requests_per_sec,
+        true,
+    ));
+    let error_rate = |r: &BenchmarkResult| {
+        if r.total == 0 {
+            0.0
+        } else {
+            r.errors as f64 / r.total as f64 * 100.0
+        }
+    };
+    changes.push(MetricChange::new(
+        "Error rate".into(),
+        "%",
+        error_rate(before),
+        error_rate(after),
+        false,
+    ));
+
+    for change in &mut changes {
+        change.significant = match change.delta_pct() {
+            Some(pct) => pct.abs() > threshold_pct,
+            // 从 0 变为非 0（如开始出现错误）总是视为显著
+            None => change.after != change.before,
+        };
+    }
+    changes
+}
+
+/// 以表格打印对比结果：显著变差标红，显著改善标绿
+pub fn print_comparison(changes: &[MetricChange], colored: bool) {
+    println!(
+        "{:<18} {:>16} {:>16} {:>12} {:>9}",
+        "Metric", "Before", "After", "Delta", "Delta%"
+    );
+    for change in changes {
+        let value = |v: f64| format!("{:.2} {}", v, change.unit).trim_end().to_string();
+        let pct = change
+            .delta_pct()
+            .map_or("n/a".to_string(), |p| format!("{:+.1}%", p));
+        let line = format!(
+            "{:<18} {:>16} {:>16} {:>+12.2} {:>9}",
+            change.name,
+            value(change.before),
+            value(change.after),
+            change.delta(),
+            pct
+        );
+        let style = if change.regressed() {
+            Style::Red
+        } else if change.improved() {
+            Style::Green
+        } else {
+            Style::Plain
+        };
+        if colored && !matches!(style, Style::Plain) {
+            println!("{}{}\x1b[0m", style.code(), line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
@@ -1,192 +1,2070 @@
-use clap::Parser;
-use futures::StreamExt;
-use reqwest::Client;
-use std::io::Write;
-use std::sync::atomic::{AtomicBool, Ordering};
+use base64::prelude::*;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{
+    ArgAction, ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
+use interface_performance_test::{
+    AutoConcurrency, BenchmarkConfig, BenchmarkResult, BenchmarkRunner, BodyTemplate, BoxError,
+    Breakpoint, Cookie, CookieJar, DEFAULT_USER_AGENT, HistoryRecord, IpFamily, JsonPath,
+    JsonSchema, LatencyStats, LogTarget, MaxErrorsMode, ProxyConfig, Soak, Spike, SseFormat,
+    StepLoad, append_history, build_header_map, compare_results, compare_with_baseline,
+    load_body_variations, load_cookie_file, load_har_bodies, load_history, load_prompt_csv,
+    load_url_list, parse_cookie, parse_duration, parse_header, parse_var, parse_weighted_url,
+    print_baseline_comparison, print_comparison, print_history, print_step_table,
+    push_line_protocol, resolve_method, restore_terminal, to_line_protocol, write_atomic,
+    write_html_report,
+};
+use reqwest::header::HeaderName;
+use serde::Deserialize;
+use std::io::IsTerminal;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 提示信息：--quiet 或 --dry-run 时不输出，JSON 输出时写到 stderr，保证 stdout 只有结果
+macro_rules! info {
+    ($args:expr, $($arg:tt)*) => {
+        if !$args.quiet && !$args.dry_run {
+            if $args.output_format == OutputFormat::Json {
+                eprintln!($($arg)*)
+            } else {
+                println!($($arg)*)
+            }
+        }
+    };
+}
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about = "Concurrency tester for LLM streaming APIs with optional response printing")]
-struct Args {
+#[clap(
+    author,
+    version,
+    about = "Concurrency tester for LLM streaming APIs with optional response printing",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// 不带子命令时等同于 `run`
+    #[clap(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 执行压测（默认）
+    Run(Box<RunArgs>),
+    /// 以文本形式打印 --output-file 保存的结果
+    Report(ReportArgs),
+    /// 对比两次保存的结果，任一指标显著变差时以退出码 1 结束
+    Compare(CompareArgs),
+    /// 列出 run --history-file 记录的历史运行
+    History(HistoryArgs),
+}
+
+impl Cli {
+    fn into_run_args(self) -> RunArgs {
+        match self.command {
+            Some(Command::Run(args)) => *args,
+            _ => self.run,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct ReportArgs {
+    /// run --output-file 保存的 JSON 结果
     #[clap(short, long)]
-    url: String,
+    input: PathBuf,
+
+    /// 何时输出 ANSI 颜色
+    #[clap(long, value_enum, default_value = "auto")]
+    color: ColorChoice,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    /// 作为基准的结果文件
+    #[clap(long)]
+    before: PathBuf,
+
+    /// 对比的结果文件
+    #[clap(long)]
+    after: PathBuf,
+
+    /// 变化幅度超过该百分比才视为显著
+    #[clap(long, default_value_t = 5.0)]
+    threshold: f64,
+
+    /// 何时输出 ANSI 颜色
+    #[clap(long, value_enum, default_value = "auto")]
+    color: ColorChoice,
+}
+
+#[derive(clap::Args, Debug)]
+struct HistoryArgs {
+    /// run --history-file 写入的历史文件（JSON Lines）
+    #[clap(long)]
+    history_file: PathBuf,
+
+    /// 只列出该标签的运行
+    #[clap(long)]
+    tag: Option<String>,
 
-    /// 总请求数
-    #[clap(short = 'n', long, default_value_t = 10)]
-    requests: usize,
+    /// 列出最近的运行数
+    #[clap(long, default_value_t = 20)]
+    last: usize,
+}
+
+#[derive(Parser, Debug)]
+#[clap(group(ArgGroup::new("auth").multiple(false)))]
+struct RunArgs {
+    /// 测试目标地址；可重复指定，写成 URL:权重（如 http://canary:8081:10）时按权重分配请求，否则轮询
+    #[clap(short, long, value_parser = parse_weighted_url)]
+    url: Vec<(String, Option<u32>)>,
+
+    /// 每行一个目标 URL 的文件，请求在各 URL 间轮询分配，结果中按 URL 分组统计
+    #[clap(long, conflicts_with = "url")]
+    url_list: Option<PathBuf>,
+
+    /// 从 TOML 配置文件读取参数，命令行中显式给出的参数和环境变量优先
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// 在解析参数前从该文件加载 `KEY=VALUE` 形式的环境变量（已设置的环境变量不被覆盖），
+    /// 每个参数都可以通过 BENCH_<参数名大写> 指定，如 BENCH_URL、BENCH_AUTH_BEARER
+    #[clap(long)]
+    env_file: Option<PathBuf>,
+
+    /// 按本次运行的参数打印带注释的 TOML 配置后退出，保存后通过 --config 使用即可复现同样的运行
+    #[clap(long)]
+    generate_config: bool,
+
+    /// 总请求数，重试后仍失败的请求也计入（未指定 --duration 时默认为 10）
+    #[clap(short = 'n', long)]
+    requests: Option<usize>,
+
+    /// 测试持续时间（如 30s、5m），与 --requests 同时指定时先到者为准
+    #[clap(short = 'd', long, value_parser = parse_test_duration)]
+    duration: Option<Duration>,
+
+    /// 每个 worker 依次发起的请求数（失败也计入），总请求数为 --concurrency × --iterations，
+    /// 模拟 N 个各自连续发起请求的独立用户
+    #[clap(
+        long,
+        conflicts_with_all = ["requests", "duration", "warmup", "ramp_up", "spike", "soak", "step_load", "breakpoint"]
+    )]
+    iterations: Option<usize>,
 
     /// 并发数（最大并发连接数）
-    #[clap(short = 'c', long, default_value_t = 10)]
+    #[clap(
+        short = 'c',
+        long,
+        default_value_t = 10,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
     concurrency: usize,
 
-    /// 请求超时（秒）
+    /// 单个请求的总超时（秒），从发起连接到读完响应，包括模型生成时间
     #[clap(short = 't', long, default_value_t = 60)]
     timeout: u64,
 
+    /// 建立 TCP 连接的超时（秒），只约束连接阶段，与 --timeout 分别生效
+    #[clap(long, default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// 非 2xx 或网络错误时的最大重试次数（不超过 100），只有最终结果计入统计
+    #[clap(long, default_value_t = 0, value_parser = clap::value_parser!(u32).range(..=100))]
+    retry: u32,
+
+    /// 首次重试前的等待时间，之后每次翻倍（最长 60s）并附加 ±10% 抖动
+    #[clap(long, value_parser = parse_duration, default_value = "500ms")]
+    retry_delay: Duration,
+
+    /// 失败数达到该值时中止压测，并输出已收集的统计
+    #[clap(long)]
+    max_errors: Option<usize>,
+
+    /// --max-errors 的计数方式：consecutive 为连续失败数，total 为累计失败数
+    #[clap(long, value_enum, default_value_t = MaxErrorsMode::Consecutive, requires = "max_errors")]
+    max_errors_mode: MaxErrorsMode,
+
+    /// 启用熔断：最近 10 秒错误率超过 50% 时所有 worker 暂停 5 秒后再试
+    #[clap(long)]
+    circuit_breaker: bool,
+
+    /// 请求方法：GET、POST、PUT、PATCH、DELETE
+    #[clap(short = 'X', long, default_value = "POST")]
+    method: String,
+
+    /// 请求体（JSON），GET/DELETE 请求可省略
     #[clap(short = 'b', long)]
-    body: String,
+    body: Option<String>,
+
+    /// 从文件读取请求体，不能与 --body 同时使用
+    #[clap(long, conflicts_with = "body")]
+    body_file: Option<PathBuf>,
+
+    /// JSONL 文件，每行一个请求体，请求间轮流使用
+    #[clap(long, conflicts_with_all = ["body", "body_file"])]
+    body_variation_file: Option<PathBuf>,
+
+    /// 从 HAR 抓包文件中取 URL 匹配 --url 的 JSON POST 请求体，请求间轮流使用
+    #[clap(long, conflicts_with_all = ["body", "body_file", "body_variation_file"])]
+    har_file: Option<PathBuf>,
+
+    /// 请求体模板，每个请求替换其中的 {{NAME}}：内置 REQUEST_ID、WORKER_ID、TIMESTAMP_MS、
+    /// RANDOM_UUID、RANDOM_WORD、RANDOM_INT:MIN:MAX，以及 --var 定义的变量
+    #[clap(long, conflicts_with_all = ["body", "body_file", "body_variation_file", "har_file"])]
+    body_template: Option<String>,
+
+    /// CSV 文件（首行为表头），--body-template 中的 {{PROMPT}} 按行轮流取 --prompt-column 列的值
+    #[clap(long, requires = "body_template")]
+    prompt_csv: Option<PathBuf>,
+
+    /// --prompt-csv 中作为 {{PROMPT}} 的列，从 1 开始
+    #[clap(long, default_value_t = 1, requires = "prompt_csv")]
+    prompt_column: usize,
+
+    /// --body-template 中的自定义变量："NAME=VALUE"，值中可引用其他变量，可重复指定
+    #[clap(long = "var", value_parser = parse_var, requires = "body_template")]
+    vars: Vec<(String, String)>,
+
+    /// 用 JSON Schema 校验每个 2xx 响应（SSE 模式下为每个 data 事件），不符合时计为失败
+    #[clap(long)]
+    response_schema: Option<PathBuf>,
+
+    /// 对每个成功响应求值的 JSONPath（如 "$.model"），报告数值的均值与范围、其他值的频次，可重复指定
+    #[clap(long, value_parser = JsonPath::parse)]
+    extract: Vec<JsonPath>,
 
     /// Print the full response body of the first successful request (for debugging)
     #[clap(short = 'p', long)]
     print_response: bool,
+
+    /// 每个 chunk 折算的 token 数，用于估算生成吞吐
+    #[clap(long, default_value_t = 1)]
+    tokens_per_chunk: u32,
+
+    /// 从响应中的 usage.completion_tokens 读取 token 数，代替按 chunk 估算
+    #[clap(long)]
+    parse_usage_tokens: bool,
+
+    /// 解析 vLLM 在最后一个 chunk 中返回的 usage（需在请求体中设置
+    /// stream_options.include_usage），报告平均 prompt / completion token 数和服务端 tokens/s
+    #[clap(long)]
+    vllm_usage: bool,
+
+    /// 读取 TGI 的 x-inference-time / x-queue-time / x-prefill-time / x-decode-time 响应头，
+    /// 与客户端测得的延迟并列报告
+    #[clap(long)]
+    tgi_headers: bool,
+
+    /// 每个请求的输入 token 数估算值，报告 RPS × 该值的输入 token 吞吐，便于比较不同长度的 prompt
+    #[clap(long)]
+    input_tokens_estimate: Option<u32>,
+
+    /// 每个请求的输出 token 数估算值，报告 RPS × 该值的输出 token 吞吐
+    #[clap(long)]
+    output_tokens_estimate: Option<u32>,
+
+    /// 按 SSE `data:` 事件解析响应流，每个 JSON 事件计为一个 token 事件，遇到 [DONE] 即结束
+    #[clap(long)]
+    sse: bool,
+
+    /// 按 Anthropic 的流式格式解析（`event:` + `data:`），隐含 --sse：
+    /// 带 delta.text 的 content_block_delta 计为 token 事件，读到 message_stop 即结束
+    #[clap(long)]
+    anthropic: bool,
+
+    /// 读到 OpenAI 流式响应的 `data: [DONE]` 即结束读取，不等待 100ms 空闲超时（--sse 下总是如此）
+    #[clap(long)]
+    openai_done_sentinel: bool,
+
+    /// 结果输出格式
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// 将 JSON 结果写入文件（与 --output-format 无关），先写临时文件再重命名
+    #[clap(long)]
+    output_file: Option<PathBuf>,
+
+    /// 逐条写入每个完成请求的原始数据（JSONL）
+    #[clap(long)]
+    raw_output: Option<PathBuf>,
+
+    /// 逐条写入每个完成请求的延迟数据（CSV）
+    #[clap(long)]
+    csv_output: Option<PathBuf>,
+
+    /// 结束时将所有请求与响应（每个响应体最多 64 KB）写为 HAR 文件，可在浏览器开发者工具中查看
+    #[clap(long)]
+    record_har: Option<PathBuf>,
+
+    /// 将前 N 个请求的请求头、请求体、响应头、响应体与耗时写入 --trace-file，
+    /// 格式类似 curl -v（发送的行以 "> " 开头，收到的行以 "< " 开头），失败响应的响应体也会读取
+    #[clap(long, default_value_t = 0)]
+    trace_requests: usize,
+
+    /// --trace-requests 的输出文件
+    #[clap(long, default_value = "traces.txt", requires = "trace_requests")]
+    trace_file: PathBuf,
+
+    /// 附加请求头，格式同 curl -H："Name: Value"，可重复指定
+    #[clap(short = 'H', long = "header", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// 只通过 IPv4 连接目标
+    #[clap(long, conflicts_with = "ipv6")]
+    ipv4: bool,
+
+    /// 只通过 IPv6 连接目标
+    #[clap(long)]
+    ipv6: bool,
+
+    /// 不复用连接：每个请求新建 TCP 连接，用于测量包含建连开销的延迟
+    #[clap(long, conflicts_with_all = ["pool_max_idle_per_host", "pool_idle_timeout"])]
+    no_keepalive: bool,
+
+    /// 每个响应最多读取的字节数，达到后停止读取，请求仍计为成功并标记为截断
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_body_size: Option<u64>,
+
+    /// 关闭 TCP_NODELAY（启用 Nagle 算法），用于测量小请求被合并发送带来的额外延迟
+    #[clap(long)]
+    no_tcp_nodelay: bool,
+
+    /// 操作系统层面 TCP keepalive 探测的间隔（如 60s）
+    #[clap(long, value_parser = parse_duration, conflicts_with = "no_keepalive")]
+    tcp_keepalive: Option<Duration>,
+
+    /// 每个请求新建 HTTP 客户端，连接池与 DNS 缓存都不共享，测量完整的冷启动延迟
+    #[clap(
+        long,
+        conflicts_with_all = ["no_keepalive", "pool_max_idle_per_host", "pool_idle_timeout"]
+    )]
+    connection_per_request: bool,
+
+    /// 每个主机最多保留的空闲连接数（默认不限）
+    #[clap(long)]
+    pool_max_idle_per_host: Option<usize>,
+
+    /// 空闲连接的保留时长（如 30s，默认 90s）
+    #[clap(long, value_parser = parse_duration)]
+    pool_idle_timeout: Option<Duration>,
+
+    /// 不跟随重定向，3xx 响应计为失败；否则重定向的往返时间会计入 TTFT
+    #[clap(long, conflicts_with = "max_redirects")]
+    no_follow_redirects: bool,
+
+    /// 跟随重定向的最大次数，超过时请求失败
+    #[clap(long, default_value_t = 10)]
+    max_redirects: usize,
+
+    /// User-Agent 请求头，部分网关按其限流；--header 中的 User-Agent 优先
+    #[clap(long, default_value = DEFAULT_USER_AGENT)]
+    user_agent: String,
+
+    /// 在该时长内逐个启动 worker，并发数从 1 线性增加到 --concurrency，期间结果不计入统计；
+    /// 与 --warmup 同时使用时，预热在爬坡完成后进行
+    #[clap(long, value_parser = parse_duration)]
+    ramp_up: Option<Duration>,
+
+    /// 正式计时前先完成的预热请求数（失败的请求也计入），结果不计入统计
+    #[clap(long, default_value_t = 0)]
+    warmup: usize,
+
+    /// 压测开始前以 HEAD 请求（不支持时改用 GET）预先建立的连接数，排除建连开销
+    #[clap(
+        long,
+        default_value_t = 0,
+        conflicts_with_all = ["no_keepalive", "connection_per_request"]
+    )]
+    min_connections: usize,
+
+    /// 需要报告的百分位列表（逗号分隔，取值范围 (0, 100)）
+    #[clap(long, value_delimiter = ',', value_parser = parse_percentile, default_value = "50,95,99,99.9")]
+    percentiles: Vec<f64>,
+
+    /// 限制所有 worker 合计每秒最多发起的请求数
+    #[clap(long)]
+    rate: Option<f64>,
+
+    /// 每个 worker 完成一个请求后等待该时长再发起下一个，模拟用户思考时间
+    #[clap(long, value_parser = parse_duration)]
+    think_time: Option<Duration>,
+
+    /// 在 --think-time 基础上额外增加 [0, 该值) 的均匀随机时长，避免各 worker 同步发起请求
+    #[clap(long, value_parser = parse_duration, requires = "think_time")]
+    think_time_jitter: Option<Duration>,
+
+    /// 阶梯加压：从 --step-concurrency 开始，每 --step-duration 增加 --step-concurrency 个并发，
+    /// 直到 --step-max，每一步单独统计并在最后输出汇总表
+    #[clap(long, requires = "step_max", conflicts_with_all = ["requests", "duration", "html_report"])]
+    step_load: bool,
+
+    /// 阶梯加压的起始并发数和每步增量
+    #[clap(long, default_value_t = 5, requires = "step_load")]
+    step_concurrency: usize,
+
+    /// 阶梯加压每一步的持续时间
+    #[clap(long, value_parser = parse_duration, default_value = "20s", requires = "step_load")]
+    step_duration: Duration,
+
+    /// 阶梯加压的最大并发数
+    #[clap(long, requires = "step_load")]
+    step_max: Option<usize>,
+
+    /// 拐点搜索：并发数从 1 开始翻倍直到 P99 TTFT 超过 --latency-target，再二分查找仍达标的最大并发数
+    #[clap(
+        long,
+        requires = "latency_target",
+        conflicts_with_all = ["requests", "duration", "html_report", "step_load"]
+    )]
+    breakpoint: bool,
+
+    /// 拐点搜索中 P99 TTFT 的上限（如 500ms）
+    #[clap(long, value_parser = parse_duration, requires = "breakpoint")]
+    latency_target: Option<Duration>,
+
+    /// 拐点搜索中每个并发级别完成的请求数
+    #[clap(long, default_value_t = 100, requires = "breakpoint")]
+    breakpoint_requests: usize,
+
+    /// 拐点搜索超标后最多进行的二分次数
+    #[clap(long, default_value_t = 5, requires = "breakpoint")]
+    breakpoint_iterations: usize,
+
+    /// 自适应并发：从 1 个并发开始，每 --auto-concurrency-interval 增加 1，直到 Requests/sec 达到
+    /// --target-rps，或 P95 TTFT 超过 --auto-concurrency-max-latency，最后报告最优并发数
+    #[clap(
+        long,
+        requires = "target_rps",
+        conflicts_with_all = [
+            "requests", "duration", "iterations", "concurrency", "html_report", "step_load",
+            "breakpoint", "spike", "soak", "streaming_quantiles", "assert_p99_ttft",
+            "assert_p99_total", "assert_rps", "baseline_file", "history_file", "influx_output", "influx_host"
+        ]
+    )]
+    auto_concurrency: bool,
+
+    /// 自适应并发的目标 Requests/sec
+    #[clap(long, requires = "auto_concurrency")]
+    target_rps: Option<f64>,
+
+    /// 自适应并发中 P95 TTFT 的上限（如 500ms），超过时停止加压
+    #[clap(long, value_parser = parse_duration, requires = "auto_concurrency")]
+    auto_concurrency_max_latency: Option<Duration>,
+
+    /// 自适应并发中每个并发级别的持续时间
+    #[clap(
+        long,
+        value_parser = parse_duration,
+        default_value = "5s",
+        requires = "auto_concurrency"
+    )]
+    auto_concurrency_interval: Duration,
+
+    /// 自适应并发的并发数上限
+    #[clap(long, default_value_t = 256, requires = "auto_concurrency")]
+    auto_concurrency_max: usize,
+
+    /// 突发负载：以 --baseline-concurrency 运行 --spike-delay 后，并发突增到 --spike-concurrency
+    /// 并持续 --spike-duration，再回到基础并发运行 --spike-delay，结果按三个阶段分别统计
+    #[clap(
+        long,
+        conflicts_with_all = ["requests", "concurrency", "ramp_up", "step_load", "breakpoint", "soak"]
+    )]
+    spike: bool,
+
+    /// 突发前后的基础并发数
+    #[clap(long, default_value_t = 5, requires = "spike")]
+    baseline_concurrency: usize,
+
+    /// 突发阶段的并发数
+    #[clap(long, default_value_t = 50, requires = "spike")]
+    spike_concurrency: usize,
+
+    /// 突发阶段的持续时间
+    #[clap(long, value_parser = parse_duration, default_value = "10s", requires = "spike")]
+    spike_duration: Duration,
+
+    /// 突发开始前（以及结束后）以基础并发运行的时长
+    #[clap(long, value_parser = parse_duration, default_value = "30s", requires = "spike")]
+    spike_delay: Duration,
+
+    /// 稳定性测试：以当前并发持续运行该时长（如 1h），按 --soak-interval 记录时间序列，
+    /// 结束时比较开头与结尾 10 分钟的 P99 TTFT 和内存变化
+    #[clap(
+        long,
+        value_parser = parse_duration,
+        conflicts_with_all = ["requests", "duration", "step_load", "breakpoint"]
+    )]
+    soak: Option<Duration>,
+
+    /// 稳定性测试时间序列的输出文件（JSON Lines）
+    #[clap(long, default_value = "soak.jsonl", requires = "soak")]
+    soak_output: PathBuf,
+
+    /// 稳定性测试的采样间隔
+    #[clap(long, value_parser = parse_duration, default_value = "60s", requires = "soak")]
+    soak_interval: Duration,
+
+    /// 服务端自身的预期 TTFT（如 40ms），结果中报告平均 TTFT 超出该值的部分作为连接开销
+    #[clap(long, value_parser = parse_duration)]
+    expected_server_latency: Option<Duration>,
+
+    /// 报告 TTFT 不超过该值的请求占比（如 150ms），仅作参考，不影响退出码
+    #[clap(long, value_parser = parse_duration)]
+    slo_ttft: Option<Duration>,
+
+    /// 报告端到端延迟不超过该值的请求占比（如 2000ms）
+    #[clap(long, value_parser = parse_duration)]
+    slo_total: Option<Duration>,
+
+    /// P99 TTFT 超过该值时以退出码 1 结束，用于 CI（如 200ms）
+    #[clap(long, value_parser = parse_duration, conflicts_with_all = ["step_load", "breakpoint"])]
+    assert_p99_ttft: Option<Duration>,
+
+    /// P99 端到端延迟超过该值时以退出码 1 结束
+    #[clap(long, value_parser = parse_duration, conflicts_with_all = ["step_load", "breakpoint"])]
+    assert_p99_total: Option<Duration>,
+
+    /// 每秒请求数低于该值时以退出码 1 结束
+    #[clap(long, conflicts_with_all = ["step_load", "breakpoint"])]
+    assert_rps: Option<f64>,
+
+    /// 检查每个成功响应中该 JSONPath 的取值，与 --assert-value 按顺序一一对应，可重复指定；
+    /// 不符合时计入 Assertion failures，不计为失败，也不影响退出码
+    #[clap(long, value_parser = JsonPath::parse, requires = "assert_value")]
+    assert_field: Vec<JsonPath>,
+
+    /// --assert-field 的期望值：字符串按原文比较，数字、布尔值等按 JSON 文本比较（如 42、true）
+    #[clap(long, requires = "assert_field")]
+    assert_value: Vec<String>,
+
+    /// 成功响应须返回该状态码（如只返回 201 的接口），不符合时计入 Assertion failures
+    #[clap(long)]
+    assert_status: Option<u16>,
+
+    /// 与之前用 --output-file 保存的结果对比，任一 P99 指标退化超过 --regression-threshold 时以退出码 1 结束
+    #[clap(long, conflicts_with_all = ["step_load", "breakpoint"])]
+    baseline_file: Option<PathBuf>,
+
+    /// 允许的 P99 退化百分比：当前值 > 基线值 × (1 + 阈值/100) 视为退化
+    #[clap(long, default_value_t = 10.0, requires = "baseline_file")]
+    regression_threshold: f64,
+
+    /// 用 t-digest 近似计算百分位，内存占用与请求数无关，适合超长时间测试；
+    /// 不保留逐请求结果，因此不能与依赖原始样本的选项同时使用
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "soak", "spike", "step_load", "breakpoint", "worker_stats", "slo_ttft", "slo_total",
+            "assert_p99_ttft", "assert_p99_total", "report_interval", "time_buckets"
+        ]
+    )]
+    streaming_quantiles: bool,
+
+    /// 用 HDR 直方图（3 位有效数字）统计 TTFT 与端到端延迟的 P99.9 / P99.99，适合长时间测试
+    #[clap(long)]
+    hdr_histogram: bool,
+
+    /// 将 TTFT 的 HDR 直方图以 HdrHistogram 的 .hgrm 百分位分布格式写入该文件
+    #[clap(long, requires = "hdr_histogram")]
+    hdr_output: Option<PathBuf>,
+
+    /// 在汇总结果后按请求发出时刻每隔该时长（如 5s）分段打印 RPS 与 TTFT 百分位
+    #[clap(long, value_parser = parse_duration)]
+    time_buckets: Option<Duration>,
+
+    /// 在汇总结果后按 worker 打印请求数、平均 TTFT 和平均端到端延迟
+    #[clap(long)]
+    worker_stats: bool,
+
+    /// 运行期间显示全屏界面：进度、RPS 走势、TTFT 百分位柱状图和最近的请求；stdout 不是终端时退回进度条
+    #[clap(long, conflicts_with_all = ["quiet", "verbose", "plot", "report_interval"])]
+    tui: bool,
+
+    /// 运行期间在 stderr 上每秒刷新一行 RPS 走势图（▁▂▃▄▅▆▇█），代替进度条；非终端时自动关闭
+    #[clap(long, conflicts_with = "quiet")]
+    plot: bool,
+
+    /// 运行期间每隔该时长打印一次当前统计（如 5s）
+    #[clap(long, value_parser = parse_duration)]
+    report_interval: Option<Duration>,
+
+    /// 只输出最终结果，不打印启动信息、进度条等提示
+    #[clap(short = 'q', long)]
+    quiet: bool,
+
+    /// 以 JSON 打印解析后的请求（URL、方法、请求头、请求体前 200 个字符）和负载参数后退出，
+    /// 不建立任何连接；认证信息只显示开头几个字符
+    #[clap(long)]
+    dry_run: bool,
+
+    /// 每个请求完成后向 stderr 打印一行耗时信息
+    #[clap(short = 'v', long, conflicts_with = "quiet")]
+    verbose: bool,
+
+    /// 统计表是否着色：auto 仅在 stdout 为终端时着色
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// 测试结束后生成单文件 HTML 报告
+    #[clap(long)]
+    html_report: Option<PathBuf>,
+
+    /// 将本次运行的摘要追加到历史文件（JSON Lines：每行一条 JSON 记录，含时间、URL、并发、
+    /// TTFT/总耗时的均值与 P99、Requests/sec 和标签），用 history 子命令查询
+    #[clap(long, conflicts_with_all = ["step_load", "breakpoint"])]
+    history_file: Option<PathBuf>,
+
+    /// 标识本次运行的标签（如 v2.1.0-candidate），写入 JSON 结果、原始数据和历史记录，
+    /// 可重复指定；history --tag 按标签筛选
+    #[clap(long = "tag")]
+    tags: Vec<String>,
+
+    /// 将逐请求结果与汇总以 InfluxDB line protocol 写入该文件，可直接用 influx write --file 导入
+    #[clap(long, conflicts_with_all = ["step_load", "breakpoint"])]
+    influx_output: Option<PathBuf>,
+
+    /// 测试结束后将同样的数据推送到该 InfluxDB 地址（如 http://localhost:8086）
+    #[clap(long, requires = "influx_bucket", conflicts_with_all = ["step_load", "breakpoint"])]
+    influx_host: Option<String>,
+
+    /// InfluxDB 的 API token
+    #[clap(long, requires = "influx_host")]
+    influx_token: Option<String>,
+
+    /// 写入的 InfluxDB bucket
+    #[clap(long, requires = "influx_host")]
+    influx_bucket: Option<String>,
+
+    /// bucket 所属的组织，token 不限定组织时必须指定
+    #[clap(long, requires = "influx_host")]
+    influx_org: Option<String>,
+
+    /// 运行期间在该端口提供 Prometheus 格式的 /metrics
+    #[clap(long)]
+    metrics_port: Option<u16>,
+
+    /// /metrics 监听的地址，默认只允许本机访问；0.0.0.0 对所有网络接口开放
+    #[clap(long, default_value = "127.0.0.1", requires = "metrics_port")]
+    metrics_addr: IpAddr,
+
+    /// OTLP/HTTP 接收端地址（如 http://localhost:4318），每个请求导出为一个 span
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    /// 为每个请求注入 W3C traceparent 请求头，便于服务端关联 trace
+    #[clap(long)]
+    trace_context: bool,
+
+    /// 随请求发送的 cookie，格式 "name=value; path=/; domain=example.com"，可重复指定；
+    /// 指定后每个 worker 还会记录响应中的 Set-Cookie 并在后续请求中发送
+    #[clap(long = "cookie", value_parser = parse_cookie)]
+    cookies: Vec<Cookie>,
+
+    /// 从 Netscape 格式的 cookie 文件（如 curl -c 导出的 cookies.txt）加载初始 cookie
+    #[clap(long)]
+    cookie_jar: Option<PathBuf>,
+
+    /// 为每个请求生成一个 UUID 放入该请求头（如 X-Request-ID），并写入 --verbose 输出和
+    /// --raw-output 记录，便于按 ID 查找服务端日志
+    #[clap(long, value_parser = parse_header_name)]
+    request_id_header: Option<HeaderName>,
+
+    /// 所有请求经由该 HTTP 代理发出，如 http://proxy.example.com:8080
+    #[clap(long)]
+    proxy: Option<String>,
+
+    /// 代理认证信息，格式 user:password
+    #[clap(long, requires = "proxy")]
+    proxy_auth: Option<String>,
+
+    /// 不走代理的主机列表，逗号分隔
+    #[clap(long, requires = "proxy")]
+    no_proxy: Option<String>,
+
+    /// 等同于 --header "Authorization: Bearer <TOKEN>"
+    #[clap(long, group = "auth")]
+    auth_bearer: Option<String>,
+
+    /// 等同于 --header "api-key: <KEY>"（Azure OpenAI 约定）
+    #[clap(long, group = "auth")]
+    auth_api_key: Option<String>,
+
+    /// HTTP Basic 认证，格式 user:password
+    #[clap(long, group = "auth")]
+    auth_basic: Option<String>,
 }
 
-#[derive(Debug, Clone)]
-struct LatencyResult {
-    ttft: Duration,
-    total: Duration,
+/// --config 文件的内容：键为参数名，值为 TOML 标量或数组，具体格式见 [`parse_toml`]
+#[derive(Debug, Deserialize)]
+struct ConfigFile(std::collections::BTreeMap<String, ConfigValue>);
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<ConfigValue>),
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+impl ConfigValue {
+    fn to_arg(&self) -> Result<String, String> {
+        match self {
+            ConfigValue::Bool(b) => Ok(b.to_string()),
+            ConfigValue::Int(n) => Ok(n.to_string()),
+            ConfigValue::Float(f) => Ok(f.to_string()),
+            ConfigValue::Str(s) => Ok(s.clone()),
+            ConfigValue::List(_) => Err("nested arrays are not supported".to_string()),
+        }
+    }
+}
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(args.timeout))
-        .build()?;
+/// 不能出现在配置文件中的参数，也不对应环境变量
+const CONFIG_EXCLUDED_ARGS: &[&str] = &["config", "env_file", "generate_config", "help", "version"];
 
-    let (result_sender, mut result_receiver) = mpsc::channel::<LatencyResult>(args.requests);
-    let printed = Arc::new(AtomicBool::new(false)); // 保证只打印一次
+/// 取值不在 --help 中显示的环境变量
+const SECRET_ARGS: &[&str] = &[
+    "auth_bearer",
+    "auth_api_key",
+    "auth_basic",
+    "proxy_auth",
+    "influx_token",
+];
 
-    println!(
-        "Starting benchmark: {} (requests={}, concurrency={})",
-        args.url, args.requests, args.concurrency
-    );
-    if args.print_response {
-        println!("ℹ️  Response of the first successful request will be printed below:\n--- RESPONSE START ---");
+/// 参数对应的环境变量名：BENCH_ 加参数名的大写形式
+fn env_name(id: &str) -> String {
+    format!("BENCH_{}", id.to_uppercase())
+}
+
+/// 为 run 的每个参数绑定 BENCH_<参数名> 环境变量（顶层与 run 子命令各一份）
+fn cli_command() -> clap::Command {
+    fn with_env(command: clap::Command) -> clap::Command {
+        command.mut_args(|arg| {
+            let id = arg.get_id().as_str();
+            if CONFIG_EXCLUDED_ARGS.contains(&id) {
+                return arg;
+            }
+            let hide = SECRET_ARGS.contains(&id);
+            let env = env_name(id);
+            arg.env(env).hide_env_values(hide)
+        })
     }
+    with_env(Cli::command()).mut_subcommand("run", with_env)
+}
 
-    let start = Instant::now();
+impl RunArgs {
+    /// 合并 --config 指定的配置文件：命令行显式给出的参数优先，其余参数取配置文件中的值，
+    /// 两者都没有时使用默认值。
+    ///
+    /// 配置项被转换成等价的命令行参数后与原始命令行一起重新解析，因此取值格式、
+    /// 取值校验以及参数间的互斥关系都与命令行完全一致。同时返回合并后的 ArgMatches，
+    /// 供 --generate-config 读取每个参数的最终取值。
+    fn merge_config(self, matches: &ArgMatches) -> Result<(Self, ArgMatches), BoxError> {
+        let Some(path) = &self.config else {
+            return Ok((self, matches.clone()));
+        };
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config {}: {e}", path.display()))?;
+        let table = parse_toml(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        let ConfigFile(config) =
+            serde_json::from_value(table.into()).map_err(|e| format!("{}: {e}", path.display()))?;
 
-    for _ in 0..args.concurrency {
-        let client = client.clone();
-        let url = args.url.clone();
-        let body = args.body.clone();
-        let sender = result_sender.clone();
-        let printed = printed.clone();
-        let print_enabled = args.print_response;
+        let command = RunArgs::command();
+        let mut extra = Vec::new();
+        for (key, value) in &config {
+            let key = key.replace('-', "_");
+            let arg = command
+                .get_arguments()
+                .filter(|a| !CONFIG_EXCLUDED_ARGS.contains(&a.get_id().as_str()))
+                .find(|a| {
+                    a.get_id() == key.as_str()
+                        || a.get_long().is_some_and(|l| l.replace('-', "_") == key)
+                })
+                .ok_or_else(|| format!("{}: unknown option {key:?}", path.display()))?;
+            if matches!(
+                matches.value_source(arg.get_id().as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            ) {
+                continue;
+            }
 
-        tokio::spawn(async move {
-            loop {
-                let req_start = Instant::now();
-
-                let res = client
-                    .post(&url)
-                    .header("Content-Type", "application/json")
-                    // 注意：不设置 Accept 头（适配 TGI/vLLM）
-                    .body(body.clone())
-                    .send()
-                    .await;
-
-                match res {
-                    Ok(resp) => {
-                        if !resp.status().is_success() {
-                            continue;
-                        }
-
-                        let mut stream = resp.bytes_stream();
-                        let mut should_print = false;
-
-                        // 检查是否需要打印（仅第一个成功请求）
-                        if print_enabled && !printed.load(Ordering::Relaxed) {
-                            should_print = true;
-                            printed.store(true, Ordering::Relaxed);
-                        }
-
-                        // 等待第一个 chunk（TTFT）
-                        let ttft = match tokio::time::timeout(
-                            Duration::from_secs(args.timeout as u64),
-                            stream.next(),
-                        )
-                        .await
-                        {
-                            Ok(Some(Ok(chunk))) => {
-                                if should_print {
-                                    // 安全地将 bytes 转为字符串（忽略非法 UTF-8）
-                                    let s = String::from_utf8_lossy(&chunk);
-                                    print!("{}", s);
-                                    std::io::stdout().flush().ok();
-                                }
-                                req_start.elapsed()
-                            }
-                            _ => continue,
-                        };
-
-                        // 读取剩余流
-                        while let Ok(Some(Ok(chunk))) = tokio::time::timeout(
-                            Duration::from_millis(100),
-                            stream.next(),
-                        )
-                        .await
-                        {
-                            if should_print {
-                                let s = String::from_utf8_lossy(&chunk);
-                                print!("{}", s);
-                                std::io::stdout().flush().ok();
-                            }
-                        }
-
-                        if should_print {
-                            println!("\n--- RESPONSE END ---\n");
-                        }
-
-                        let total = req_start.elapsed();
-                        let _ = sender.send(LatencyResult { ttft, total }).await;
+            let flag = format!("--{}", arg.get_long().unwrap_or(arg.get_id().as_str()));
+            if !arg.get_action().takes_values() {
+                match value {
+                    ConfigValue::Bool(true) => extra.push(flag),
+                    ConfigValue::Bool(false) => {}
+                    _ => {
+                        return Err(
+                            format!("{}: {key} expects true or false", path.display()).into()
+                        );
                     }
-                    Err(_) => continue,
                 }
+                continue;
             }
-        });
+            let items = match value {
+                ConfigValue::List(items) => items.iter().collect(),
+                scalar => vec![scalar],
+            };
+            let items = items
+                .into_iter()
+                .map(ConfigValue::to_arg)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("{}: {key}: {e}", path.display()))?;
+            // 与默认值相同的配置项不必传入，否则会触发 requires 等只针对显式参数的约束
+            if items == default_values(arg) {
+                continue;
+            }
+            for item in items {
+                extra.push(flag.clone());
+                extra.push(item);
+            }
+        }
+
+        let argv = std::env::args_os().chain(extra.into_iter().map(Into::into));
+        let matches = cli_command()
+            .try_get_matches_from(argv)
+            .unwrap_or_else(|e| e.exit());
+        let args = Cli::from_arg_matches(&matches)?.into_run_args();
+        let matches = matches
+            .subcommand_matches("run")
+            .unwrap_or(&matches)
+            .clone();
+        Ok((args, matches))
     }
+}
+
+/// 参数的默认值，按 value_delimiter 拆分
+fn default_values(arg: &clap::Arg) -> Vec<String> {
+    arg.get_default_values()
+        .iter()
+        .flat_map(|v| {
+            let v = v.to_string_lossy().into_owned();
+            match arg.get_value_delimiter() {
+                Some(d) => v.split(d).map(str::to_string).collect(),
+                None => vec![v],
+            }
+        })
+        .collect()
+}
 
-    // 收集结果
-    let mut results = Vec::with_capacity(args.requests);
-    for _ in 0..args.requests {
-        if let Some(res) = result_receiver.recv().await {
-            results.push(res);
+/// 按本次运行的最终取值（命令行 > 配置文件 > 默认值）生成带注释的配置文件，
+/// 未指定且没有默认值的参数以注释形式列出
+fn generate_config(matches: &ArgMatches) -> String {
+    let mut out = String::from(
+        "# concurrency_testing 配置文件，通过 --config <FILE> 使用；命令行参数优先于此处的值\n",
+    );
+    for arg in RunArgs::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        if CONFIG_EXCLUDED_ARGS.contains(&id) {
+            continue;
+        }
+        out.push('\n');
+        if let Some(help) = arg.get_help() {
+            for line in help.to_string().lines() {
+                out.push_str(&format!("# {line}\n"));
+            }
+        }
+        let possible: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .map(|v| v.get_name().to_string())
+            .collect();
+        if arg.get_action().takes_values() && !possible.is_empty() {
+            out.push_str(&format!("# 可选值：{}\n", possible.join(", ")));
+        }
+
+        let multiple = matches!(arg.get_action(), ArgAction::Append);
+        // 原始取值已按 value_delimiter 拆分，且包含未显式指定时的默认值
+        let values: Vec<serde_json::Value> = matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|v| {
+                let v = v.to_string_lossy().into_owned();
+                match serde_json::from_str::<serde_json::Number>(&v) {
+                    Ok(n) => n.into(),
+                    Err(_) => v.into(),
+                }
+            })
+            .collect();
+        // 来自环境变量的取值（通常是凭据）不写入配置文件
+        let line = if matches.value_source(id) == Some(ValueSource::EnvVariable) {
+            format!("# {id}：取自环境变量 {}", env_name(id))
+        } else if !arg.get_action().takes_values() {
+            format!("{id} = {}", matches.get_flag(id))
+        } else if multiple {
+            let commented = if values.is_empty() { "# " } else { "" };
+            format!("{commented}{id} = {}", to_toml_value(&values.into()))
         } else {
+            match values.into_iter().next() {
+                Some(v) => format!("{id} = {}", to_toml_value(&v)),
+                None => format!("# {id} = \"\""),
+            }
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+fn parse_percentile(s: &str) -> Result<f64, String> {
+    let value: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid percentile: {s}"))?;
+    if value <= 0.0 || value >= 100.0 {
+        return Err(format!("percentile must be in (0, 100): {s}"));
+    }
+    Ok(value)
+}
+
+/// --duration 为 0 时每个 worker 仍会发出一个请求，与 BenchmarkBuilder 一样直接拒绝
+fn parse_test_duration(s: &str) -> Result<Duration, String> {
+    match parse_duration(s)? {
+        Duration::ZERO => Err("--duration must be greater than 0".to_string()),
+        duration => Ok(duration),
+    }
+}
+
+fn parse_header_name(s: &str) -> Result<HeaderName, String> {
+    HeaderName::from_bytes(s.trim().as_bytes()).map_err(|_| format!("invalid header name: {s:?}"))
+}
+
+/// 将 --auth-* 快捷参数展开为普通请求头，追加在 --header 之后
+fn resolve_auth_headers(args: &RunArgs) -> Result<Vec<(String, String)>, String> {
+    let mut headers = args.headers.clone();
+    if let Some(token) = &args.auth_bearer {
+        headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+    }
+    if let Some(key) = &args.auth_api_key {
+        headers.push(("api-key".to_string(), key.clone()));
+    }
+    if let Some(credentials) = &args.auth_basic {
+        if !credentials.contains(':') {
+            return Err("invalid --auth-basic (expected user:password)".to_string());
+        }
+        headers.push((
+            "Authorization".to_string(),
+            format!("Basic {}", BASE64_STANDARD.encode(credentials)),
+        ));
+    }
+    Ok(headers)
+}
+
+/// 解析 --config 使用的 TOML 子集：顶层 `key = value`，值支持字符串（"..." 或 '...'）、
+/// 整数、浮点数、布尔值以及（可跨行的）数组；`#` 开头为注释，不支持表（`[section]`）。
+///
+/// 可用的键与命令行参数一一对应，使用参数名的下划线形式（`tokens_per_chunk`，
+/// 也接受 `tokens-per-chunk`），例如：
+///
+/// ```toml
+/// url = "http://127.0.0.1:8080/v1/chat/completions"
+/// concurrency = 20
+/// duration = "30s"            # 时长类参数使用与命令行相同的写法
+/// headers = ["Authorization: Bearer sk-xxx"]
+/// percentiles = [50, 90, 99]
+/// sse = true
+/// ```
+fn parse_toml(text: &str) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let mut table = serde_json::Map::new();
+    let mut rest = text;
+    loop {
+        rest = skip_toml_blank(rest);
+        if rest.is_empty() {
+            return Ok(table);
+        }
+        let line_no = text[..text.len() - rest.len()].lines().count() + 1;
+        if rest.starts_with('[') {
+            return Err(format!("line {line_no}: tables are not supported"));
+        }
+
+        let (key, after_key) = match rest.find('=') {
+            Some(pos) => (rest[..pos].trim(), &rest[pos + 1..]),
+            None => return Err(format!("line {line_no}: expected `key = value`")),
+        };
+        let key = key.trim_matches('"');
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(format!("line {line_no}: invalid key {key:?}"));
+        }
+
+        let (value, after_value) =
+            parse_toml_value(after_key).map_err(|e| format!("line {line_no}: {e}"))?;
+        let after_value = after_value.trim_start_matches([' ', '\t']);
+        if !(after_value.is_empty() || after_value.starts_with(['\n', '\r', '#'])) {
+            return Err(format!("line {line_no}: unexpected trailing characters"));
+        }
+        if table.insert(key.to_string(), value).is_some() {
+            return Err(format!("line {line_no}: duplicate key {key:?}"));
+        }
+        rest = after_value;
+    }
+}
+
+/// 跳过空白、换行和注释
+fn skip_toml_blank(mut s: &str) -> &str {
+    loop {
+        s = s.trim_start();
+        match s.strip_prefix('#') {
+            Some(comment) => s = comment.find('\n').map_or("", |pos| &comment[pos..]),
+            None => return s,
+        }
+    }
+}
+
+fn parse_toml_value(s: &str) -> Result<(serde_json::Value, &str), String> {
+    let s = s.trim_start_matches([' ', '\t']);
+    if let Some(body) = s.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = body.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((out.into(), &body[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape \\u{hex}"))?;
+                        out.push(c);
+                    }
+                    other => {
+                        let c = other.unwrap_or(' ');
+                        return Err(format!("invalid escape sequence \\{c}"));
+                    }
+                },
+                '\n' => return Err("unterminated string".to_string()),
+                c => out.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    } else if let Some(body) = s.strip_prefix('\'') {
+        let end = body
+            .find(['\'', '\n'])
+            .filter(|&pos| body[pos..].starts_with('\''))
+            .ok_or("unterminated string")?;
+        Ok((body[..end].into(), &body[end + 1..]))
+    } else if let Some(mut body) = s.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            body = skip_toml_blank(body);
+            if let Some(rest) = body.strip_prefix(']') {
+                return Ok((items.into(), rest));
+            }
+            let (item, rest) = parse_toml_value(body)?;
+            items.push(item);
+            body = skip_toml_blank(rest);
+            if let Some(rest) = body.strip_prefix(',') {
+                body = rest;
+            } else if !body.starts_with(']') {
+                return Err("expected `,` or `]` in array".to_string());
+            }
+        }
+    } else {
+        let end = s
+            .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == '#')
+            .unwrap_or(s.len());
+        let (token, rest) = s.split_at(end);
+        let value = match token {
+            "true" => true.into(),
+            "false" => false.into(),
+            _ => {
+                let number = token.replace('_', "");
+                if let Ok(n) = number.parse::<i64>() {
+                    n.into()
+                } else if let Ok(f) = number.parse::<f64>() {
+                    f.into()
+                } else {
+                    return Err(format!("invalid value {token:?}"));
+                }
+            }
+        };
+        Ok((value, rest))
+    }
+}
+
+/// 将 JSON 值写成 TOML 值，用于 --generate-config
+fn to_toml_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => {
+            let mut out = String::from("\"");
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    '\r' => out.push_str("\\r"),
+                    c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+        serde_json::Value::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(to_toml_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// 读取 run --output-file 保存的单次结果
+fn load_result(path: &Path) -> Result<BenchmarkResult, BoxError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    Ok(serde_json::from_str(&text)
+        .map_err(|e| format!("{} is not a saved benchmark result: {e}", path.display()))?)
+}
+
+/// report 子命令：读取保存的结果并按 run 的文本格式打印
+fn print_report(report: &ReportArgs) -> Result<(), BoxError> {
+    load_result(&report.input)?.print_text(report.color.enabled());
+    Ok(())
+}
+
+/// compare 子命令：打印对比表，有显著变差的指标时以退出码 1 结束
+fn run_compare(compare: &CompareArgs) -> Result<(), BoxError> {
+    let before = load_result(&compare.before)?;
+    let after = load_result(&compare.after)?;
+    let changes = compare_results(&before, &after, compare.threshold);
+    print_comparison(&changes, compare.color.enabled());
+
+    let regressions: Vec<&str> = changes
+        .iter()
+        .filter(|c| c.regressed())
+        .map(|c| c.name.as_str())
+        .collect();
+    if !regressions.is_empty() {
+        eprintln!(
+            "❌ {} metric(s) regressed by more than {}%: {}",
+            regressions.len(),
+            compare.threshold,
+            regressions.join(", ")
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// 值中含凭据的请求头，--dry-run 时只显示开头几个字符
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "api-key",
+    "x-api-key",
+    "cookie",
+];
+
+/// 保留认证方案（如 "Bearer "）和凭据的前 4 个字符，其余以 *** 代替
+fn redact(value: &str) -> String {
+    let (scheme, secret) = match value.split_once(' ') {
+        Some((scheme, secret)) => (format!("{scheme} "), secret),
+        None => (String::new(), value),
+    };
+    let visible: String = if secret.chars().count() > 8 {
+        secret.chars().take(4).collect()
+    } else {
+        String::new()
+    };
+    format!("{scheme}{visible}***")
+}
+
+/// --dry-run 的输出：每个请求实际发送的内容与负载参数
+fn dry_run_summary(args: &RunArgs, config: &BenchmarkConfig) -> serde_json::Value {
+    let mut headers: serde_json::Map<String, serde_json::Value> = config
+        .request_headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                redact(&value)
+            } else {
+                value.into_owned()
+            };
+            (name.to_string(), value.into())
+        })
+        .collect();
+    headers
+        .entry("user-agent")
+        .or_insert(config.user_agent.clone().into());
+    let preview = |body: &str| {
+        let mut preview: String = body.chars().take(200).collect();
+        if body.chars().count() > 200 {
+            preview.push('…');
+        }
+        preview
+    };
+    serde_json::json!({
+        "url": config.url,
+        "method": config.method.as_str(),
+        "headers": headers,
+        "body": config
+            .bodies
+            .first()
+            .map(String::as_str)
+            .or(config.body_template.as_ref().map(BodyTemplate::source))
+            .map(preview),
+        "body_variations": config.bodies.len(),
+        "proxy": args.proxy,
+        "timeout_secs": config.timeout.as_secs_f64(),
+        "connect_timeout_secs": config.connect_timeout.as_secs_f64(),
+        "concurrency": config.concurrency,
+        "requests": config.request_limit(),
+        "duration_secs": config.duration.map(|d| d.as_secs_f64()),
+        "rate": config.rate,
+        "sse": config.sse,
+    })
+}
+
+/// 命令行中 --env-file 的值，需在 clap 解析之前取得
+fn env_file_arg() -> Option<PathBuf> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
             break;
         }
+        if arg == "--env-file" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--env-file=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// 解析 .env 文件：每行 `KEY=VALUE`，可带 `export ` 前缀；`#` 开头为注释；
+/// 值可用双引号（支持 \n、\t、\"、\\ 转义）或单引号（原样）包裹，
+/// 未加引号的值中 ` #` 之后视为注释
+fn parse_env_file(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {line_no}: expected `KEY=VALUE`"));
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("line {line_no}: invalid key {key:?}"));
+        }
+
+        let value = value.trim_start();
+        let value = if let Some(body) = value.strip_prefix('"') {
+            let mut out = String::new();
+            let mut chars = body.chars();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some(c) => out.push(c),
+                        None => return Err(format!("line {line_no}: unterminated string")),
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err(format!("line {line_no}: unterminated string")),
+                }
+            }
+            out
+        } else if let Some(body) = value.strip_prefix('\'') {
+            match body.find('\'') {
+                Some(end) => body[..end].to_string(),
+                None => return Err(format!("line {line_no}: unterminated string")),
+            }
+        } else {
+            let end = value.find(" #").unwrap_or(value.len());
+            value[..end].trim_end().to_string()
+        };
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+/// 将 .env 文件中的变量加入进程环境，已存在的环境变量优先
+fn load_env_file(path: &Path) -> Result<(), BoxError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read env file {}: {e}", path.display()))?;
+    let vars = parse_env_file(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+    for (key, value) in vars {
+        if std::env::var_os(&key).is_none() {
+            // SAFETY: 只在 main 中、#[tokio::main] 的 run() 创建运行时之前调用，
+            // 此时进程中只有主线程，不会有其他线程同时读写环境变量
+            unsafe { std::env::set_var(key, value) };
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), BoxError> {
+    if let Some(path) = env_file_arg() {
+        load_env_file(&path)?;
+    }
+    run()
+}
+
+#[tokio::main]
+async fn run() -> Result<(), BoxError> {
+    let matches = cli_command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+    let (args, run_matches) = match cli.command {
+        Some(Command::Report(report)) => return print_report(&report),
+        Some(Command::Compare(compare)) => return run_compare(&compare),
+        Some(Command::History(history)) => {
+            print_history(&load_history(
+                &history.history_file,
+                history.tag.as_deref(),
+                history.last,
+            )?);
+            return Ok(());
+        }
+        Some(Command::Run(args)) => {
+            args.merge_config(matches.subcommand_matches("run").unwrap())?
+        }
+        None => cli.run.merge_config(&matches)?,
+    };
+    if args.generate_config {
+        print!("{}", generate_config(&run_matches));
+        return Ok(());
+    }
+    let weighted = args.url.iter().any(|(_, weight)| weight.is_some());
+    if weighted && args.url.iter().any(|(_, weight)| weight.is_none()) {
+        Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "either all --url values have a weight or none do",
+            )
+            .exit();
+    }
+    let (urls, url_weights) = match &args.url_list {
+        Some(path) => (load_url_list(path)?, Vec::new()),
+        None if args.url.len() > 1 || weighted => (
+            args.url.iter().map(|(url, _)| url.clone()).collect(),
+            args.url.iter().filter_map(|(_, weight)| *weight).collect(),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+    if args.url.is_empty() && urls.is_empty() {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--url or --url-list is required (on the command line or in --config)",
+            )
+            .exit();
+    }
+    if args.assert_field.len() != args.assert_value.len() {
+        Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "each --assert-field needs a matching --assert-value",
+            )
+            .exit();
+    }
+    if args.spike && args.spike_concurrency <= args.baseline_concurrency {
+        Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "--spike-concurrency must be greater than --baseline-concurrency",
+            )
+            .exit();
+    }
+
+    let proxy = match &args.proxy {
+        Some(proxy_url) => {
+            let mut proxy = ProxyConfig::new(proxy_url);
+            if let Some(auth) = &args.proxy_auth {
+                let (user, password) = auth
+                    .split_once(':')
+                    .ok_or("invalid --proxy-auth (expected user:password)")?;
+                proxy.basic_auth = Some((user.to_string(), password.to_string()));
+            }
+            proxy.no_proxy = args.no_proxy.clone();
+            proxy
+                .to_proxy()
+                .map_err(|e| format!("invalid --proxy {proxy_url:?}: {e}"))?;
+            Some(proxy)
+        }
+        None => None,
+    };
+    let headers = build_header_map(&resolve_auth_headers(&args)?)?;
+    // 请求体只在启动时读取一次
+    let body = match &args.body_file {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read --body-file {}: {e}", path.display()))?,
+        ),
+        None => args.body.clone(),
+    };
+    let url = urls
+        .first()
+        .or(args.url.first().map(|(url, _)| url))
+        .cloned()
+        .unwrap_or_default();
+    let cookie_jar = match &args.cookie_jar {
+        Some(path) => Some(load_cookie_file(path)?),
+        None if args.cookies.is_empty() => None,
+        None => Some(Vec::new()),
+    }
+    .map(|mut cookies| {
+        cookies.extend(args.cookies.iter().cloned());
+        CookieJar::new(cookies)
+    });
+    // 所有请求轮流取用的请求体，为空表示不发送请求体
+    let bodies = match (&args.body_variation_file, &args.har_file) {
+        (Some(path), _) => load_body_variations(path)?,
+        (None, Some(path)) => load_har_bodies(path, &url)?,
+        (None, None) => body.iter().cloned().collect(),
+    };
+    let body_template = match &args.body_template {
+        Some(template) => {
+            let mut template = BodyTemplate::parse(template, &args.vars)?;
+            match &args.prompt_csv {
+                Some(path) if !template.uses_prompt() => {
+                    return Err(format!(
+                        "--prompt-csv {} is set but --body-template has no {{{{PROMPT}}}}",
+                        path.display()
+                    )
+                    .into());
+                }
+                Some(path) => {
+                    let prompts = load_prompt_csv(path, args.prompt_column)?;
+                    info!(args, "Prompts ({}): {} rows", path.display(), prompts.len());
+                    template = template.with_prompts(prompts);
+                }
+                None if template.uses_prompt() => {
+                    return Err("{{PROMPT}} in --body-template requires --prompt-csv".into());
+                }
+                None => {}
+            }
+            Some(template)
+        }
+        None => None,
+    };
+    let method = resolve_method(
+        &args.method,
+        bodies
+            .first()
+            .or(args.body_template.as_ref())
+            .map(String::as_str),
+    )?;
+
+    let response_schema = match &args.response_schema {
+        Some(path) => {
+            info!(args, "Response schema: {}", path.display());
+            Some(JsonSchema::load(path)?)
+        }
+        None => None,
+    };
+    if let Some(path) = args.body_variation_file.as_ref().or(args.har_file.as_ref()) {
+        info!(
+            args,
+            "Body variations ({}): {} bodies",
+            path.display(),
+            bodies.len()
+        );
+    }
+    if let (Some(path), Some(body)) = (&args.body_file, &body) {
+        let preview: String = body
+            .chars()
+            .take(80)
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        let ellipsis = if body.chars().count() > 80 { "…" } else { "" };
+        info!(args, "Body ({}): {}{}", path.display(), preview, ellipsis);
     }
 
-    let total_time = start.elapsed();
-    let success = results.len();
+    // 第一次 Ctrl-C 停止压测并输出已收集的结果，再按一次直接退出
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("\nInterrupted, finishing up (press Ctrl-C again to exit immediately)…");
+                shutdown.store(true, Ordering::Relaxed);
+            }
+            if tokio::signal::ctrl_c().await.is_ok() {
+                restore_terminal();
+                std::process::exit(130);
+            }
+        });
+    }
 
-    println!("\n=== Results ===");
-    println!("Total: {}, Success: {}, Failed: {}", args.requests, success, args.requests - success);
-    println!("Total time: {:.2?}", total_time);
+    let config = BenchmarkConfig {
+        url,
+        urls,
+        url_weights,
+        method,
+        bodies,
+        body_template,
+        headers,
+        user_agent: args.user_agent.clone(),
+        keepalive: !args.no_keepalive,
+        connection_per_request: args.connection_per_request,
+        max_body_size: args.max_body_size,
+        tcp_nodelay: !args.no_tcp_nodelay,
+        tcp_keepalive: args.tcp_keepalive,
+        pool_max_idle_per_host: args.pool_max_idle_per_host,
+        pool_idle_timeout: args.pool_idle_timeout,
+        follow_redirects: !args.no_follow_redirects,
+        max_redirects: args.max_redirects,
+        ip_family: match (args.ipv4, args.ipv6) {
+            (true, _) => Some(IpFamily::V4),
+            (_, true) => Some(IpFamily::V6),
+            _ => None,
+        },
+        concurrency: if args.spike {
+            args.spike_concurrency
+        } else {
+            args.concurrency
+        },
+        requests: args.requests,
+        iterations: args.iterations,
+        // 突发前后的两段基础并发各持续 --spike-delay
+        duration: args.soak.or(args.duration).or(args
+            .spike
+            .then(|| args.spike_delay * 2 + args.spike_duration)),
+        timeout: Duration::from_secs(args.timeout),
+        connect_timeout: Duration::from_secs(args.connect_timeout),
+        retries: args.retry,
+        retry_delay: args.retry_delay,
+        max_errors: args.max_errors,
+        max_errors_mode: args.max_errors_mode,
+        circuit_breaker: args.circuit_breaker,
+        proxy,
+        tokens_per_chunk: args.tokens_per_chunk,
+        parse_usage_tokens: args.parse_usage_tokens,
+        vllm_usage: args.vllm_usage,
+        tgi_headers: args.tgi_headers,
+        input_tokens_estimate: args.input_tokens_estimate,
+        output_tokens_estimate: args.output_tokens_estimate,
+        response_schema,
+        extract: args.extract.clone(),
+        field_assertions: args
+            .assert_field
+            .iter()
+            .cloned()
+            .zip(args.assert_value.iter().cloned())
+            .collect(),
+        expected_status: args.assert_status,
+        sse: args.sse || args.anthropic,
+        sse_format: if args.anthropic {
+            SseFormat::Anthropic
+        } else {
+            SseFormat::OpenAi
+        },
+        done_sentinel: args.openai_done_sentinel,
+        ramp_up: args.ramp_up,
+        warmup: args.warmup,
+        min_connections: args.min_connections,
+        rate: args.rate,
+        percentiles: args.percentiles.clone(),
+        raw_output: args.raw_output.clone(),
+        csv_output: args.csv_output.clone(),
+        record_har: args.record_har.clone(),
+        trace_requests: args.trace_requests,
+        trace_file: args.trace_file.clone(),
+        metrics_port: args.metrics_port,
+        metrics_addr: args.metrics_addr,
+        otlp_endpoint: args.otlp_endpoint.clone(),
+        trace_context: args.trace_context,
+        request_id_header: args.request_id_header.clone(),
+        cookie_jar,
+        print_response: args.print_response,
+        streaming_quantiles: args.streaming_quantiles,
+        hdr_histogram: args.hdr_histogram,
+        hdr_output: args.hdr_output.clone(),
+        verbose: args.verbose,
+        think_time: args.think_time,
+        think_time_jitter: args.think_time_jitter.unwrap_or_default(),
+        expected_server_latency: args.expected_server_latency,
+        slo_ttft: args.slo_ttft,
+        slo_total: args.slo_total,
+        spike: args.spike.then_some(Spike {
+            baseline_concurrency: args.baseline_concurrency,
+            delay: args.spike_delay,
+            duration: args.spike_duration,
+        }),
+        soak: args.soak.map(|_| Soak {
+            output: Some(args.soak_output.clone()),
+            interval: args.soak_interval,
+        }),
+        tui: args.tui,
+        plot: args.plot,
+        tags: args.tags.clone(),
+        report_interval: args.report_interval,
+        shutdown: Some(shutdown.clone()),
+        log: match (args.quiet, args.output_format) {
+            (true, _) => LogTarget::Off,
+            (false, OutputFormat::Text) => LogTarget::Stdout,
+            (false, OutputFormat::Json) => LogTarget::Stderr,
+        },
+    };
+    if args.dry_run {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&dry_run_summary(&args, &config))?
+        );
+        return Ok(());
+    }
+    if args.step_load {
+        let steps = StepLoad {
+            start: args.step_concurrency,
+            step: args.step_concurrency,
+            max: args.step_max.unwrap_or(args.step_concurrency),
+            duration: args.step_duration,
+        };
+        let results = BenchmarkRunner::run_steps(config, steps, |step| {
+            if args.output_format == OutputFormat::Text {
+                println!("\n=== Step: concurrency={} ===", step.concurrency);
+                step.result.print_text(args.color.enabled());
+            }
+        })
+        .await?;
+        if let Some(path) = &args.output_file {
+            save_json(path, &results)?;
+        }
+        match args.output_format {
+            OutputFormat::Text => print_step_table("Step load", &results),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        }
+        print_saved(&args);
+        return Ok(());
+    }
 
-    if success > 0 {
-        let to_ms = |ns: u128| ns as f64 / 1_000_000.0;
-        let mut ttfts: Vec<u128> = results.iter().map(|r| r.ttft.as_nanos()).collect();
-        let mut totals: Vec<u128> = results.iter().map(|r| r.total.as_nanos()).collect();
-        ttfts.sort_unstable();
-        totals.sort_unstable();
+    if args.breakpoint {
+        let search = Breakpoint {
+            latency_target: args.latency_target.unwrap_or_default(),
+            requests: args.breakpoint_requests,
+            iterations: args.breakpoint_iterations,
+        };
+        let found = BenchmarkRunner::find_breakpoint(config, search, |step, within| {
+            if args.output_format == OutputFormat::Text {
+                println!(
+                    "concurrency={} rps={:.2} errors={} {}",
+                    step.concurrency,
+                    step.result.requests_per_sec,
+                    step.result.errors,
+                    if within {
+                        "✅ within target"
+                    } else {
+                        "❌ over target"
+                    }
+                );
+            }
+        })
+        .await?;
+        if let Some(path) = &args.output_file {
+            save_json(path, &found)?;
+        }
+        match args.output_format {
+            OutputFormat::Text => {
+                print_step_table("Breakpoint search", &found.steps);
+                match (found.max_concurrency, found.requests_per_sec) {
+                    (Some(concurrency), Some(rps)) => println!(
+                        "\nMax sustainable concurrency: {} ({:.2} requests/sec)",
+                        concurrency, rps
+                    ),
+                    _ => println!("\nP99 TTFT exceeds the target even at concurrency 1"),
+                }
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&found)?),
+        }
+        print_saved(&args);
+        return Ok(());
+    }
 
-        let p = |data: &[u128], perc: f64| -> f64 {
-            let idx = ((data.len() as f64) * perc).min(data.len() as f64 - 1.0) as usize;
-            to_ms(data[idx])
+    if args.auto_concurrency {
+        let tune = AutoConcurrency {
+            target_rps: args.target_rps.unwrap_or_default(),
+            max_latency: args.auto_concurrency_max_latency,
+            interval: args.auto_concurrency_interval,
+            max_concurrency: args.auto_concurrency_max,
         };
+        let found = BenchmarkRunner::auto_concurrency(config, tune, |step, p95| {
+            if args.output_format == OutputFormat::Text {
+                println!(
+                    "concurrency={} rps={:.2} p95_ttft={} errors={}",
+                    step.concurrency,
+                    step.result.requests_per_sec,
+                    p95.map_or("-".to_string(), |p95| format!("{p95:.2}ms")),
+                    step.result.errors
+                );
+            }
+        })
+        .await?;
+        if let Some(path) = &args.output_file {
+            save_json(path, &found)?;
+        }
+        match args.output_format {
+            OutputFormat::Text => {
+                print_step_table("Auto concurrency", &found.steps);
+                match (found.optimal_concurrency, found.requests_per_sec) {
+                    (Some(concurrency), Some(rps)) => println!(
+                        "\nOptimal concurrency: {} ({:.2} requests/sec, stopped: {})",
+                        concurrency,
+                        rps,
+                        found.stop_reason.description()
+                    ),
+                    _ => println!(
+                        "\nNo concurrency level met the latency cap (stopped: {})",
+                        found.stop_reason.description()
+                    ),
+                }
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&found)?),
+        }
+        print_saved(&args);
+        return Ok(());
+    }
 
-        println!("\n--- TTFT ---");
-        println!("Avg: {:.2} ms", ttfts.iter().map(|&x| to_ms(x)).sum::<f64>() / success as f64);
-        println!("P50: {:.2} ms", p(&ttfts, 0.5));
-        println!("P95: {:.2} ms", p(&ttfts, 0.95));
-        println!("P99: {:.2} ms", p(&ttfts, 0.99));
+    // 先读取基线，文件有误时不必等压测结束才报错
+    let baseline: Option<serde_json::Value> = match &args.baseline_file {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read baseline {}: {e}", path.display()))?;
+            Some(serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?)
+        }
+        None => None,
+    };
 
-        println!("\n--- End-to-End ---");
-        println!("Avg: {:.2} ms", totals.iter().map(|&x| to_ms(x)).sum::<f64>() / success as f64);
-        println!("P50: {:.2} ms", p(&totals, 0.5));
-        println!("P95: {:.2} ms", p(&totals, 0.95));
-        println!("P99: {:.2} ms", p(&totals, 0.99));
+    let summary = BenchmarkRunner::run(config.clone()).await?;
 
-        println!("\nRequests/sec: {:.2}", success as f64 / total_time.as_secs_f64());
+    if let Some(path) = &args.output_file {
+        save_json(path, &summary)?;
+    }
+    if let Some(path) = &args.html_report {
+        write_html_report(path, &config, &summary)?;
     }
+    if let Some(path) = &args.history_file {
+        append_history(path, &HistoryRecord::new(&config, &summary))?;
+    }
+    if args.influx_output.is_some() || args.influx_host.is_some() {
+        let lines = to_line_protocol(&config, &summary);
+        if let Some(path) = &args.influx_output {
+            std::fs::write(path, &lines)?;
+        }
+        if let (Some(host), Some(bucket)) = (&args.influx_host, &args.influx_bucket) {
+            // 推送失败不影响已完成的测试结果
+            if let Err(e) = push_line_protocol(
+                host,
+                args.influx_token.as_deref(),
+                args.influx_org.as_deref(),
+                bucket,
+                lines,
+            )
+            .await
+            {
+                eprintln!("⚠️  {e}");
+            }
+        }
+    }
+
+    match args.output_format {
+        OutputFormat::Text => {
+            summary.print_text(args.color.enabled());
+            if let Some(bucket) = args.time_buckets {
+                summary.print_time_buckets(bucket);
+            }
+            if args.worker_stats {
+                summary.print_worker_table();
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+    }
+    print_saved(&args);
 
-    let end = Instant::now();
-    println!("测试花费时间：{}", end.checked_duration_since(start).unwrap().as_secs_f64());
+    // 断言在完整报告输出之后检查，失败时报告仍然可见
+    let mut failures = check_assertions(&args, &summary);
+    if let Some(baseline) = &baseline {
+        let diffs = compare_with_baseline(baseline, &summary, args.regression_threshold);
+        if args.output_format == OutputFormat::Text {
+            print_baseline_comparison(&diffs, args.regression_threshold, args.color.enabled());
+        }
+        if diffs.is_empty() {
+            eprintln!("⚠️  No comparable P99 metrics in the baseline file");
+        }
+        for diff in diffs.iter().filter(|d| d.regressed) {
+            failures.push(format!(
+                "{} regressed by {:.1}% ({:.2} ms -> {:.2} ms)",
+                diff.name,
+                diff.change_pct(),
+                diff.baseline,
+                diff.current
+            ));
+        }
+    }
+    for failure in &failures {
+        eprintln!("❌ Assertion failed: {}", failure);
+    }
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 写入 --output-file，失败时在错误信息中带上路径
+fn save_json(path: &Path, value: &impl serde::Serialize) -> Result<(), BoxError> {
+    write_atomic(path, serde_json::to_string_pretty(value)?)
+        .map_err(|e| format!("failed to write {}: {e}", path.display()).into())
+}
+
+/// 报告结束后提示 --output-file 的路径；JSON 输出时写到 stderr，不影响 stdout 的 JSON
+fn print_saved(args: &RunArgs) {
+    let Some(path) = &args.output_file else {
+        return;
+    };
+    match args.output_format {
+        OutputFormat::Text => println!("Results saved to {}", path.display()),
+        OutputFormat::Json => eprintln!("Results saved to {}", path.display()),
+    }
+}
+
+/// 返回所有未通过的 --assert-* 检查的说明
+fn check_assertions(args: &RunArgs, summary: &BenchmarkResult) -> Vec<String> {
+    let p99 = |samples: Vec<Duration>| {
+        LatencyStats::from_samples(samples.into_iter(), &[99.0]).map(|s| s.percentiles[0].1)
+    };
+    let mut failures = Vec::new();
+    let latency_checks = [
+        (
+            "P99 TTFT",
+            args.assert_p99_ttft,
+            summary.results.iter().map(|r| r.ttft).collect(),
+        ),
+        (
+            "P99 total",
+            args.assert_p99_total,
+            summary.results.iter().map(|r| r.total).collect(),
+        ),
+    ];
+    for (name, limit, samples) in latency_checks {
+        let Some(limit) = limit else { continue };
+        let limit_ms = limit.as_secs_f64() * 1000.0;
+        match p99(samples) {
+            Some(value) if value <= limit_ms => {}
+            Some(value) => failures.push(format!("{} {:.2} ms > {:.2} ms", name, value, limit_ms)),
+            None => failures.push(format!("{} unavailable (no successful requests)", name)),
+        }
+    }
+    if let Some(min) = args.assert_rps
+        && summary.requests_per_sec < min
+    {
+        failures.push(format!(
+            "requests/sec {:.2} < {:.2}",
+            summary.requests_per_sec, min
+        ));
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_toml_strings_and_escapes() {
+        let table = parse_toml(concat!(
+            "url = \"http://127.0.0.1:8080/v1\"\n",
+            "body = \"{\\\"a\\\": \\\"tab\\there\\\"}\\n\"\n",
+            "snowman = \"\\u2603\"\n",
+            "literal = 'C:\\path\\no\\escapes'\n",
+            "\"quoted\" = \"key\"\n",
+        ))
+        .unwrap();
+        assert_eq!(table["url"], "http://127.0.0.1:8080/v1");
+        assert_eq!(table["body"], "{\"a\": \"tab\there\"}\n");
+        assert_eq!(table["snowman"], "☃");
+        assert_eq!(table["literal"], "C:\\path\\no\\escapes");
+        assert_eq!(table["quoted"], "key");
+    }
+
+    #[test]
+    fn parses_toml_scalars_arrays_and_comments() {
+        let table = parse_toml(concat!(
+            "# leading comment\n",
+            "\n",
+            "concurrency = 1_000   # inline comment\n",
+            "rate = 2.5\n",
+            "sse = true\n",
+            "keepalive = false#no space\n",
+            "percentiles = [50, 99.9]\n",
+            "headers = [\n",
+            "  \"A: 1\", # first\n",
+            "  'B: 2',\n",
+            "]\n",
+            "empty = []\n",
+        ))
+        .unwrap();
+        assert_eq!(table["concurrency"], 1000);
+        assert_eq!(table["rate"], 2.5);
+        assert_eq!(table["sse"], true);
+        assert_eq!(table["keepalive"], false);
+        assert_eq!(table["percentiles"], json!([50, 99.9]));
+        assert_eq!(table["headers"], json!(["A: 1", "B: 2"]));
+        assert_eq!(table["empty"], json!([]));
+        assert_eq!(table.len(), 7);
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        let err = |text: &str| parse_toml(text).unwrap_err();
+        assert_eq!(err("a = 1\na = 2\n"), "line 2: duplicate key \"a\"");
+        assert_eq!(err("\n\njust a line\n"), "line 3: expected `key = value`");
+        assert_eq!(err("[section]\n"), "line 1: tables are not supported");
+        assert_eq!(err("my key = 1"), "line 1: invalid key \"my key\"");
+        assert_eq!(err("a = \"open\n"), "line 1: unterminated string");
+        assert_eq!(err("a = 'open"), "line 1: unterminated string");
+        assert_eq!(err("a = \"\\q\""), "line 1: invalid escape sequence \\q");
+        assert_eq!(err("a = 1 2"), "line 1: unexpected trailing characters");
+        assert_eq!(err("a = [1 2]"), "line 1: expected `,` or `]` in array");
+        assert_eq!(err("a = yes"), "line 1: invalid value \"yes\"");
+    }
+
+    #[test]
+    fn toml_values_round_trip() {
+        let values = [
+            json!("plain"),
+            json!("quote \" backslash \\ newline \n tab \t cr \r bell \u{7}"),
+            json!(42),
+            json!(-1.5),
+            json!(true),
+            json!(["a", 1, [false]]),
+        ];
+        for value in values {
+            let text = format!("key = {}\n", to_toml_value(&value));
+            let table = parse_toml(&text).unwrap();
+            assert_eq!(table["key"], value, "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_env_file() {
+        let vars = parse_env_file(concat!(
+            "# comment\n",
+            "\n",
+            "   \n",
+            "PLAIN=value\n",
+            "export EXPORTED = spaced value  \n",
+            "DOUBLE=\"line\\nnext \\\"q\\\" # kept\"\n",
+            "SINGLE='raw \\n # kept'\n",
+            "INLINE=value # comment\n",
+            "HASH=a#b\n",
+            "EMPTY=\n",
+            "EQUALS=a=b\n",
+        ))
+        .unwrap();
+        let expected = [
+            ("PLAIN", "value"),
+            ("EXPORTED", "spaced value"),
+            ("DOUBLE", "line\nnext \"q\" # kept"),
+            ("SINGLE", "raw \\n # kept"),
+            ("INLINE", "value"),
+            ("HASH", "a#b"),
+            ("EMPTY", ""),
+            ("EQUALS", "a=b"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(vars, expected);
+    }
+
+    #[test]
+    fn rejects_malformed_env_lines() {
+        let err = |text: &str| parse_env_file(text).unwrap_err();
+        assert_eq!(err("A=1\nnot a pair\n"), "line 2: expected `KEY=VALUE`");
+        assert_eq!(err("=value"), "line 1: invalid key \"\"");
+        assert_eq!(err("MY-KEY=1"), "line 1: invalid key \"MY-KEY\"");
+        assert_eq!(err("A=\"open"), "line 1: unterminated string");
+        assert_eq!(err("A='open"), "line 1: unterminated string");
+    }
+
+    #[test]
+    fn rejects_zero_duration_flag() {
+        let parse = |args: &[&str]| cli_command().try_get_matches_from(args);
+        let err = parse(&["bench", "run", "-u", "http://h/", "-d", "0s"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("--duration must be greater than 0"),
+            "{err}"
+        );
+        let err = parse(&[
+            "bench",
+            "run",
+            "-u",
+            "http://h/",
+            "-d",
+            "99999999999999999999999h",
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("duration out of range"), "{err}");
+        assert!(parse(&["bench", "run", "-u", "http://h/", "-d", "100ms"]).is_ok());
+    }
+
+    #[test]
+    fn caps_retry_flag() {
+        let parse = |retry: &str| {
+            cli_command().try_get_matches_from([
+                "bench",
+                "run",
+                "-u",
+                "http://h/",
+                "--retry",
+                retry,
+            ])
+        };
+        assert!(parse("100").is_ok());
+        assert!(parse("101").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 统计错误率的滑动窗口
+const WINDOW: Duration = Duration::from_secs(10);
+/// 窗口内错误率超过该值时断开
+const ERROR_RATE_THRESHOLD: f64 = 0.5;
+/// 窗口内样本不足时不判断，避免开头的个别失败直接触发
+const MIN_SAMPLES: usize = 10;
+/// 断开后暂停发起请求的时长
+const OPEN_DURATION: Duration = Duration::from_secs(5);
+/// 半开状态下探测请求超过该时长仍无结果（如 worker 已退出）时，允许另一个 worker 重新探测
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// 半开状态下等待探测结果时，其他 worker 每次等待的时长
+const PROBE_WAIT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CircuitState {
+    /// 正常发起请求
+    Closed,
+    /// 暂停到指定时刻
+    Open { until: Instant },
+    /// 暂停结束，只放行一个探测请求，由它的结果决定恢复还是再次断开
+    HalfOpen,
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreakerState {
+    state: CircuitState,
+    /// (完成时刻, 是否成功)，只保留最近 WINDOW 内的结果
+    outcomes: VecDeque<(Instant, bool)>,
+    /// 半开状态下探测请求的发出时刻，None 表示还未放行探测请求
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreakerState {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            outcomes: VecDeque::new(),
+            probe_started: None,
+        }
+    }
+
+    /// 返回发起请求前需要等待的时间：Open 状态下为剩余的暂停时间，暂停结束时切换到 HalfOpen；
+    /// HalfOpen 状态下只有第一个调用者（探测请求）返回 None，其余等待探测结果
+    pub(crate) fn pause(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        match self.state {
+            CircuitState::Closed => return None,
+            CircuitState::Open { until } if now < until => return Some(until - now),
+            CircuitState::Open { .. } => self.transition(CircuitState::HalfOpen),
+            CircuitState::HalfOpen => {}
+        }
+        if self
+            .probe_started
+            .is_some_and(|started| now - started < PROBE_TIMEOUT)
+        {
+            return Some(PROBE_WAIT);
+        }
+        self.probe_started = Some(now);
+        None
+    }
+
+    pub(crate) fn record(&mut self, success: bool) {
+        let now = Instant::now();
+        match self.state {
+            // 断开前发出、在半开后才完成的请求不算探测结果
+            CircuitState::HalfOpen if self.probe_started.is_none() => {}
+            CircuitState::HalfOpen if success => {
+                self.outcomes.clear();
+                self.transition(CircuitState::Closed);
+            }
+            CircuitState::HalfOpen => self.open(now),
+            CircuitState::Closed => {
+                self.outcomes.push_back((now, success));
+                while self
+                    .outcomes
+                    .front()
+                    .is_some_and(|&(at, _)| now - at > WINDOW)
+                {
+                    self.outcomes.pop_front();
+                }
+                let errors = self.outcomes.iter().filter(|&&(_, ok)| !ok).count();
+                let rate = errors as f64 / self.outcomes.len() as f64;
+                if self.outcomes.len() >= MIN_SAMPLES && rate > ERROR_RATE_THRESHOLD {
+                    eprintln!(
+                        "\n⚠️  Circuit breaker: error rate {:.0}% over the last {}s",
+                        rate * 100.0,
+                        WINDOW.as_secs()
+                    );
+                    self.open(now);
+                }
+            }
+            // 断开前已发出的请求，结果不影响状态
+            CircuitState::Open { .. } => {}
+        }
+    }
+
+    fn open(&mut self, now: Instant) {
+        self.outcomes.clear();
+        self.transition(CircuitState::Open {
+            until: now + OPEN_DURATION,
+        });
+    }
+
+    fn transition(&mut self, next: CircuitState) {
+        let name = |state: CircuitState| match state {
+            CircuitState::Closed => "closed",
+            CircuitState::Open { .. } => "open",
+            CircuitState::HalfOpen => "half-open",
+        };
+        match next {
+            CircuitState::Open { .. } => eprintln!(
+                "⚠️  Circuit breaker {} -> {}: pausing for {}s",
+                name(self.state),
+                name(next),
+                OPEN_DURATION.as_secs()
+            ),
+            _ => eprintln!("⚠️  Circuit breaker {} -> {}", name(self.state), name(next)),
+        }
+        self.state = next;
+        self.probe_started = None;
+    }
+}
+
+/// 所有 worker 共享的熔断器
+pub(crate) type CircuitBreaker = Arc<Mutex<CircuitBreakerState>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 填满窗口使错误率超过阈值
+    fn trip(breaker: &mut CircuitBreakerState) {
+        for _ in 0..MIN_SAMPLES {
+            breaker.record(false);
+        }
+        assert!(matches!(breaker.state, CircuitState::Open { .. }));
+    }
+
+    /// 跳过 Open 状态的暂停时间
+    fn expire(breaker: &mut CircuitBreakerState) {
+        breaker.state = CircuitState::Open {
+            until: Instant::now(),
+        };
+    }
+
+    #[test]
+    fn stays_closed_below_threshold() {
+        let mut breaker = CircuitBreakerState::new();
+        // 样本不足时不断开
+        for _ in 0..MIN_SAMPLES - 1 {
+            breaker.record(false);
+        }
+        assert_eq!(breaker.state, CircuitState::Closed);
+        // 错误率恰好 50% 不断开
+        let mut breaker = CircuitBreakerState::new();
+        for i in 0..MIN_SAMPLES {
+            breaker.record(i % 2 == 0);
+        }
+        assert_eq!(breaker.state, CircuitState::Closed);
+        assert_eq!(breaker.pause(), None);
+    }
+
+    #[test]
+    fn recovers_after_successful_probe() {
+        let mut breaker = CircuitBreakerState::new();
+        trip(&mut breaker);
+        let pause = breaker.pause().unwrap();
+        assert!(pause > OPEN_DURATION - Duration::from_secs(1) && pause <= OPEN_DURATION);
+        // 断开期间完成的请求不影响状态
+        breaker.record(true);
+        assert!(matches!(breaker.state, CircuitState::Open { .. }));
+
+        expire(&mut breaker);
+        assert_eq!(breaker.pause(), None);
+        assert_eq!(breaker.state, CircuitState::HalfOpen);
+        // 探测请求未完成时其他 worker 继续等待
+        assert_eq!(breaker.pause(), Some(PROBE_WAIT));
+        assert_eq!(breaker.pause(), Some(PROBE_WAIT));
+
+        breaker.record(true);
+        assert_eq!(breaker.state, CircuitState::Closed);
+        assert_eq!(breaker.pause(), None);
+        assert!(breaker.outcomes.is_empty());
+    }
+
+    #[test]
+    fn reopens_after_failed_probe() {
+        let mut breaker = CircuitBreakerState::new();
+        trip(&mut breaker);
+        expire(&mut breaker);
+        assert_eq!(breaker.pause(), None);
+        breaker.record(false);
+        assert!(matches!(breaker.state, CircuitState::Open { .. }));
+        assert!(breaker.pause().is_some());
+    }
+
+    #[test]
+    fn ignores_results_before_probe_and_retries_stale_probe() {
+        let mut breaker = CircuitBreakerState::new();
+        trip(&mut breaker);
+        expire(&mut breaker);
+        breaker.transition(CircuitState::HalfOpen);
+        // 未放行探测请求前完成的请求不决定状态
+        breaker.record(false);
+        assert_eq!(breaker.state, CircuitState::HalfOpen);
+
+        assert_eq!(breaker.pause(), None);
+        // 探测请求迟迟没有结果时放行新的探测请求
+        breaker.probe_started = Some(Instant::now() - PROBE_TIMEOUT);
+        assert_eq!(breaker.pause(), None);
+        assert_eq!(breaker.pause(), Some(PROBE_WAIT));
+    }
+}
@@ -0,0 +1,429 @@
+use crate::config::{BenchmarkConfig, IpFamily, LogTarget, SseFormat};
+use crate::cookie::{Cookie, CookieJar};
+use crate::jsonpath::JsonPath;
+use crate::request::build_header_map;
+use crate::schema::JsonSchema;
+use crate::template::BodyTemplate;
+use reqwest::header::HeaderName;
+use reqwest::{Method, Url};
+use std::fmt;
+use std::time::Duration;
+
+/// [`BenchmarkBuilder::build`] 的校验错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum BenchmarkConfigError {
+    /// URL 无法解析
+    InvalidUrl(String),
+    /// 只支持 http（当前构建未启用 TLS 后端）
+    UnsupportedScheme(String),
+    ZeroConcurrency,
+    /// 请求数、每个 worker 的请求数或持续时间为 0
+    EmptyLimit,
+    InvalidRate(f64),
+    InvalidPercentile(f64),
+    InvalidHeader(String),
+    /// 权重为 0，或权重个数与 URL 个数不一致
+    InvalidUrlWeights,
+}
+
+impl fmt::Display for BenchmarkConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(e) => write!(f, "invalid URL: {e}"),
+            Self::UnsupportedScheme(scheme) => {
+                write!(
+                    f,
+                    "unsupported URL scheme {scheme:?} (only http is supported)"
+                )
+            }
+            Self::ZeroConcurrency => write!(f, "concurrency must be greater than 0"),
+            Self::EmptyLimit => {
+                write!(
+                    f,
+                    "requests, iterations and duration must be greater than 0"
+                )
+            }
+            Self::InvalidRate(rate) => write!(f, "rate must be a positive number: {rate}"),
+            Self::InvalidPercentile(perc) => write!(f, "percentile must be in (0, 100): {perc}"),
+            Self::InvalidHeader(e) => f.write_str(e),
+            Self::InvalidUrlWeights => {
+                write!(f, "url weights must be positive, one per url")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BenchmarkConfigError {}
+
+/// 以链式调用构造 [`BenchmarkConfig`]，在 [`build`](Self::build) 时统一校验
+///
+/// ```
+/// use interface_performance_test::BenchmarkBuilder;
+/// use std::time::Duration;
+///
+/// let config = BenchmarkBuilder::new("http://localhost:8080/v1/chat/completions")
+///     .concurrency(10)
+///     .requests(100)
+///     .body(r#"{"stream": true}"#)
+///     .timeout(Duration::from_secs(30))
+///     .build()
+///     .unwrap();
+/// assert_eq!(config.concurrency, 10);
+/// assert_eq!(config.method, reqwest::Method::POST);
+///
+/// assert!(BenchmarkBuilder::new("not a url").build().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BenchmarkBuilder {
+    config: BenchmarkConfig,
+    /// 未指定时有请求体用 POST，否则用 GET
+    method: Option<Method>,
+    headers: Vec<(String, String)>,
+}
+
+impl BenchmarkBuilder {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            config: BenchmarkConfig::new(url),
+            method: None,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn method(&mut self, method: Method) -> &mut Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// 所有请求使用同一个请求体
+    pub fn body(&mut self, body: impl Into<String>) -> &mut Self {
+        self.config.bodies = vec![body.into()];
+        self
+    }
+
+    /// 请求间轮流使用的多个请求体
+    pub fn bodies<I, S>(&mut self, bodies: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.bodies = bodies.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 每个请求按模板生成请求体，代替 [`body`](Self::body) / [`bodies`](Self::bodies)
+    pub fn body_template(&mut self, template: BodyTemplate) -> &mut Self {
+        self.config.body_template = Some(template);
+        self
+    }
+
+    /// 请求间轮询使用的多个目标 URL，`new` 传入的 URL 只用于显示
+    pub fn urls<I, S>(&mut self, urls: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.urls = urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 按权重分配请求的多个目标 URL，如金丝雀发布时 90 / 10 分流
+    pub fn weighted_urls<I, S>(&mut self, urls: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (S, u32)>,
+        S: Into<String>,
+    {
+        let (urls, weights) = urls.into_iter().map(|(url, w)| (url.into(), w)).unzip();
+        self.config.urls = urls;
+        self.config.url_weights = weights;
+        self
+    }
+
+    /// 追加一个请求头，可多次调用
+    pub fn header(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn user_agent(&mut self, user_agent: impl Into<String>) -> &mut Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    pub fn concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.config.concurrency = concurrency;
+        self
+    }
+
+    pub fn requests(&mut self, requests: usize) -> &mut Self {
+        self.config.requests = Some(requests);
+        self
+    }
+
+    pub fn duration(&mut self, duration: Duration) -> &mut Self {
+        self.config.duration = Some(duration);
+        self
+    }
+
+    /// 每个 worker 依次发起 iterations 个请求后退出
+    pub fn iterations(&mut self, iterations: usize) -> &mut Self {
+        self.config.iterations = Some(iterations);
+        self
+    }
+
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn ip_family(&mut self, ip_family: IpFamily) -> &mut Self {
+        self.config.ip_family = Some(ip_family);
+        self
+    }
+
+    /// 关闭后每个请求都新建连接，用于测量不复用连接时的延迟
+    pub fn keepalive(&mut self, keepalive: bool) -> &mut Self {
+        self.config.keepalive = keepalive;
+        self
+    }
+
+    /// 关闭后启用 Nagle 算法，用于测量其对小请求延迟的影响
+    pub fn tcp_nodelay(&mut self, enabled: bool) -> &mut Self {
+        self.config.tcp_nodelay = enabled;
+        self
+    }
+
+    pub fn tcp_keepalive(&mut self, interval: Duration) -> &mut Self {
+        self.config.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// 每个响应读到 bytes 字节即停止，避免超长响应长时间占用 worker
+    /// 添加一个初始 cookie，并开启按 Set-Cookie 更新的 cookie 处理
+    pub fn cookie(&mut self, cookie: Cookie) -> &mut Self {
+        let jar = self.config.cookie_jar.take().unwrap_or_default();
+        let mut cookies = jar.into_cookies();
+        cookies.push(cookie);
+        self.config.cookie_jar = Some(CookieJar::new(cookies));
+        self
+    }
+
+    /// 每个请求生成随机 UUID 作为该请求头的值，便于与服务端日志关联
+    pub fn request_id_header(&mut self, name: HeaderName) -> &mut Self {
+        self.config.request_id_header = Some(name);
+        self
+    }
+
+    pub fn max_body_size(&mut self, bytes: u64) -> &mut Self {
+        self.config.max_body_size = Some(bytes);
+        self
+    }
+
+    /// 每个请求使用新的客户端，模拟不实现连接池的调用方
+    pub fn connection_per_request(&mut self, enabled: bool) -> &mut Self {
+        self.config.connection_per_request = enabled;
+        self
+    }
+
+    pub fn pool_max_idle_per_host(&mut self, max: usize) -> &mut Self {
+        self.config.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    pub fn pool_idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// 关闭后不跟随重定向，避免重定向的往返时间混入 TTFT
+    pub fn follow_redirects(&mut self, follow_redirects: bool) -> &mut Self {
+        self.config.follow_redirects = follow_redirects;
+        self
+    }
+
+    pub fn max_redirects(&mut self, max_redirects: usize) -> &mut Self {
+        self.config.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut Self {
+        self.config.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// 失败请求最多重试 retries 次，首次等待 delay，之后指数退避（最长 60 秒）
+    pub fn retry(&mut self, retries: u32, delay: Duration) -> &mut Self {
+        self.config.retries = retries;
+        self.config.retry_delay = delay;
+        self
+    }
+
+    /// 每个 worker 两个请求之间的空闲时间，附加 [0, jitter) 的随机时长
+    pub fn think_time(&mut self, think_time: Duration, jitter: Duration) -> &mut Self {
+        self.config.think_time = Some(think_time);
+        self.config.think_time_jitter = jitter;
+        self
+    }
+
+    pub fn sse(&mut self, sse: bool) -> &mut Self {
+        self.config.sse = sse;
+        self
+    }
+
+    /// 用 JSON Schema 校验每个成功响应，不符合时计为失败
+    pub fn response_schema(&mut self, schema: JsonSchema) -> &mut Self {
+        self.config.response_schema = Some(schema);
+        self
+    }
+
+    /// 追加一个对每个成功响应求值的 JSONPath，可多次调用
+    pub fn extract(&mut self, path: JsonPath) -> &mut Self {
+        self.config.extract.push(path);
+        self
+    }
+
+    /// 检查每个成功响应中 path 的取值等于 expected，不符合时只计数，不计为失败
+    pub fn assert_field(&mut self, path: JsonPath, expected: impl Into<String>) -> &mut Self {
+        self.config.field_assertions.push((path, expected.into()));
+        self
+    }
+
+    /// 检查成功响应的状态码，如只接受 201 的接口
+    pub fn expected_status(&mut self, status: u16) -> &mut Self {
+        self.config.expected_status = Some(status);
+        self
+    }
+
+    /// 按指定格式解析 SSE 事件，同时开启 SSE 模式
+    pub fn sse_format(&mut self, format: SseFormat) -> &mut Self {
+        self.config.sse = true;
+        self.config.sse_format = format;
+        self
+    }
+
+    /// 读到 OpenAI 流式响应的 `data: [DONE]` 即结束读取，以其到达时刻作为结束时间
+    pub fn done_sentinel(&mut self, done_sentinel: bool) -> &mut Self {
+        self.config.done_sentinel = done_sentinel;
+        self
+    }
+
+    pub fn parse_usage_tokens(&mut self, parse_usage_tokens: bool) -> &mut Self {
+        self.config.parse_usage_tokens = parse_usage_tokens;
+        self
+    }
+
+    /// 每个请求的输入 token 数估算值，结果中报告 RPS × tokens 的输入吞吐（标注为估算）
+    pub fn input_tokens_estimate(&mut self, tokens: u32) -> &mut Self {
+        self.config.input_tokens_estimate = Some(tokens);
+        self
+    }
+
+    pub fn output_tokens_estimate(&mut self, tokens: u32) -> &mut Self {
+        self.config.output_tokens_estimate = Some(tokens);
+        self
+    }
+
+    /// 记录 TGI 在响应头中报告的排队、prefill、decode 耗时
+    pub fn tgi_headers(&mut self, tgi_headers: bool) -> &mut Self {
+        self.config.tgi_headers = tgi_headers;
+        self
+    }
+
+    /// 记录服务端在 usage 中报告的 prompt / completion token 数
+    pub fn vllm_usage(&mut self, vllm_usage: bool) -> &mut Self {
+        self.config.vllm_usage = vllm_usage;
+        self
+    }
+
+    pub fn tokens_per_chunk(&mut self, tokens_per_chunk: u32) -> &mut Self {
+        self.config.tokens_per_chunk = tokens_per_chunk;
+        self
+    }
+
+    pub fn ramp_up(&mut self, ramp_up: Duration) -> &mut Self {
+        self.config.ramp_up = Some(ramp_up);
+        self
+    }
+
+    pub fn warmup(&mut self, warmup: usize) -> &mut Self {
+        self.config.warmup = warmup;
+        self
+    }
+
+    /// 压测开始前预先建立 n 个连接，排除冷启动建连对延迟的影响
+    pub fn min_connections(&mut self, n: usize) -> &mut Self {
+        self.config.min_connections = n;
+        self
+    }
+
+    pub fn rate(&mut self, rate: f64) -> &mut Self {
+        self.config.rate = Some(rate);
+        self
+    }
+
+    pub fn percentiles(&mut self, percentiles: &[f64]) -> &mut Self {
+        self.config.percentiles = percentiles.to_vec();
+        self
+    }
+
+    /// 追加一个运行标签，可多次调用
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.config.tags.push(tag.into());
+        self
+    }
+
+    pub fn log(&mut self, log: LogTarget) -> &mut Self {
+        self.config.log = log;
+        self
+    }
+
+    /// 校验并生成配置，构造器本身可继续复用
+    pub fn build(&self) -> Result<BenchmarkConfig, BenchmarkConfigError> {
+        for url in std::iter::once(&self.config.url).chain(&self.config.urls) {
+            let url =
+                Url::parse(url).map_err(|e| BenchmarkConfigError::InvalidUrl(e.to_string()))?;
+            if url.scheme() != "http" {
+                return Err(BenchmarkConfigError::UnsupportedScheme(
+                    url.scheme().to_string(),
+                ));
+            }
+        }
+        let weights = &self.config.url_weights;
+        if !weights.is_empty() && (weights.len() != self.config.urls.len() || weights.contains(&0))
+        {
+            return Err(BenchmarkConfigError::InvalidUrlWeights);
+        }
+        if self.config.concurrency == 0 {
+            return Err(BenchmarkConfigError::ZeroConcurrency);
+        }
+        if self.config.requests == Some(0)
+            || self.config.iterations == Some(0)
+            || self.config.duration == Some(Duration::ZERO)
+        {
+            return Err(BenchmarkConfigError::EmptyLimit);
+        }
+        if let Some(rate) = self.config.rate.filter(|&r| {
+            r <= 0.0 || !r.is_finite() || Duration::try_from_secs_f64(1.0 / r).is_err()
+        }) {
+            return Err(BenchmarkConfigError::InvalidRate(rate));
+        }
+        if let Some(&perc) = self
+            .config
+            .percentiles
+            .iter()
+            .find(|p| !(**p > 0.0 && **p < 100.0))
+        {
+            return Err(BenchmarkConfigError::InvalidPercentile(perc));
+        }
+
+        let mut config = self.config.clone();
+        config.headers =
+            build_header_map(&self.headers).map_err(BenchmarkConfigError::InvalidHeader)?;
+        config.method = self.method.clone().unwrap_or(if config.has_body() {
+            Method::POST
+        } else {
+            Method::GET
+        });
+        Ok(config)
+    }
+}
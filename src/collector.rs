@@ -0,0 +1,274 @@
+use crate::BoxError;
+use crate::hdr::HdrRecorder;
+use crate::stats::{ErrorKind, LatencyResult, RequestOutcome, TokenUsage};
+use crate::tdigest::StreamingStats;
+use crate::tui::TuiFeed;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// --raw-output 中每行的记录
+#[derive(Debug, Serialize)]
+struct RawRecord<'a> {
+    ttft_ms: f64,
+    total_ms: f64,
+    tokens: u32,
+    bytes: u64,
+    chunks: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<TokenUsage>,
+    worker_id: usize,
+    start_ts: f64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+}
+
+impl<'a> RawRecord<'a> {
+    fn new(r: &'a LatencyResult, tags: &'a [String]) -> Self {
+        Self {
+            ttft_ms: r.ttft.as_secs_f64() * 1000.0,
+            total_ms: r.total.as_secs_f64() * 1000.0,
+            tokens: r.tokens,
+            bytes: r.bytes_received,
+            chunks: r.chunks,
+            usage: r.usage,
+            worker_id: r.worker_id,
+            start_ts: r.start_ts,
+            truncated: r.truncated,
+            request_id: r.request_id.as_deref(),
+            tags,
+        }
+    }
+}
+
+/// 写一行 CSV，包含逗号、引号或换行的字段加引号并转义
+fn write_csv_row<W: Write>(w: &mut W, fields: &[String]) -> std::io::Result<()> {
+    let row: Vec<String> = fields
+        .iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.clone()
+            }
+        })
+        .collect();
+    writeln!(w, "{}", row.join(","))
+}
+
+/// 汇总收到的结果，并按需逐条写入原始数据文件
+pub(crate) struct ResultCollector {
+    results: Vec<LatencyResult>,
+    /// 每行写完即 flush，进程被中断时已写入的数据仍然完整
+    raw: Option<LineWriter<File>>,
+    csv: Option<BufWriter<File>>,
+    /// 写入原始数据每一行的运行标签
+    pub(crate) tags: Vec<String>,
+    /// --hdr-histogram 时同步记录
+    pub(crate) hdr: Option<HdrRecorder>,
+    /// --tui 时同步给界面
+    pub(crate) tui: Option<Arc<Mutex<TuiFeed>>>,
+    /// --streaming-quantiles 时只汇总到 t-digest，不保留逐请求结果
+    pub(crate) streaming: Option<StreamingStats>,
+    /// 按最终 HTTP 状态码统计的请求数（含失败请求）
+    pub(crate) status_codes: BTreeMap<u16, usize>,
+    /// 按原因分类的失败请求数
+    pub(crate) failures: BTreeMap<ErrorKind, usize>,
+    /// 按 URL 下标统计的失败请求数
+    pub(crate) url_errors: BTreeMap<usize, usize>,
+    /// 未通过响应检查的成功请求数
+    pub(crate) assertion_failures: usize,
+    /// 响应体被 --max-body-size 截断的成功请求数
+    pub(crate) truncated: usize,
+    count: usize,
+    completed: usize,
+}
+
+impl ResultCollector {
+    pub(crate) fn new(
+        capacity: usize,
+        raw_output: Option<&Path>,
+        csv_output: Option<&Path>,
+    ) -> std::io::Result<Self> {
+        let raw = raw_output
+            .map(File::create)
+            .transpose()?
+            .map(LineWriter::new);
+        let mut csv = csv_output
+            .map(File::create)
+            .transpose()?
+            .map(BufWriter::new);
+        if let Some(csv) = &mut csv {
+            write_csv_row(
+                csv,
+                &["ttft_ms", "total_ms", "worker_id", "start_unix_ms"].map(String::from),
+            )?;
+        }
+        Ok(Self {
+            results: Vec::with_capacity(capacity),
+            raw,
+            csv,
+            tags: Vec::new(),
+            hdr: None,
+            tui: None,
+            streaming: None,
+            status_codes: BTreeMap::new(),
+            failures: BTreeMap::new(),
+            url_errors: BTreeMap::new(),
+            assertion_failures: 0,
+            truncated: 0,
+            count: 0,
+            completed: 0,
+        })
+    }
+
+    /// 成功请求数
+    pub(crate) fn len(&self) -> usize {
+        self.count
+    }
+
+    /// 已完成的请求数：成功请求加重试后仍然失败的请求，--requests 按它计数
+    pub(crate) fn completed(&self) -> usize {
+        self.completed
+    }
+
+    pub(crate) fn results(&self) -> &[LatencyResult] {
+        &self.results
+    }
+
+    pub(crate) fn push(&mut self, outcome: RequestOutcome) -> Result<(), BoxError> {
+        self.completed += 1;
+        let res = match outcome {
+            RequestOutcome::Success(res) => *res,
+            RequestOutcome::Failure {
+                kind,
+                status,
+                url_index,
+            } => {
+                *self.failures.entry(kind).or_default() += 1;
+                *self.url_errors.entry(url_index).or_default() += 1;
+                if let Some(status) = status {
+                    *self.status_codes.entry(status).or_default() += 1;
+                }
+                return Ok(());
+            }
+        };
+        if let Some(raw) = &mut self.raw {
+            writeln!(
+                raw,
+                "{}",
+                serde_json::to_string(&RawRecord::new(&res, &self.tags))?
+            )?;
+        }
+        if let Some(csv) = &mut self.csv {
+            write_csv_row(
+                csv,
+                &[
+                    format!("{:.2}", res.ttft.as_secs_f64() * 1000.0),
+                    format!("{:.2}", res.total.as_secs_f64() * 1000.0),
+                    res.worker_id.to_string(),
+                    format!("{:.2}", res.start_ts * 1000.0),
+                ],
+            )?;
+        }
+        if let Some(hdr) = &mut self.hdr {
+            hdr.record(&res);
+        }
+        if let Some(tui) = &self.tui {
+            tui.lock().unwrap().record(&res);
+        }
+        self.count += 1;
+        *self.status_codes.entry(res.status).or_default() += 1;
+        self.assertion_failures += usize::from(res.assertion_failed);
+        self.truncated += usize::from(res.truncated);
+        match &mut self.streaming {
+            Some(streaming) => streaming.record(&res),
+            None => self.results.push(res),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> std::io::Result<Vec<LatencyResult>> {
+        if let Some(raw) = &mut self.raw {
+            raw.flush()?;
+        }
+        if let Some(csv) = &mut self.csv {
+            csv.flush()?;
+        }
+        Ok(self.results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempFile;
+    use std::time::Duration;
+
+    fn result(ttft_us: u64, total_us: u64, worker_id: usize, start_ts: f64) -> RequestOutcome {
+        RequestOutcome::Success(Box::new(LatencyResult {
+            ttft: Duration::from_micros(ttft_us),
+            total: Duration::from_micros(total_us),
+            itl: Vec::new(),
+            tokens: 1,
+            bytes_received: 0,
+            chunks: 1,
+            usage: None,
+            server_timing: None,
+            extracted: Vec::new(),
+            assertion_failed: false,
+            truncated: false,
+            request_id: None,
+            status: 200,
+            worker_id,
+            url_index: 0,
+            start_ts,
+        }))
+    }
+
+    #[test]
+    fn writes_csv_rows() {
+        let file = TempFile::new("results.csv");
+        let mut collector = ResultCollector::new(2, None, Some(file.path())).unwrap();
+        collector
+            .push(result(12_345, 250_006, 0, 1_700_000_000.123_4))
+            .unwrap();
+        // 失败请求不写入 CSV
+        collector
+            .push(RequestOutcome::Failure {
+                kind: ErrorKind::Timeout,
+                status: None,
+                url_index: 0,
+            })
+            .unwrap();
+        collector.push(result(1_000, 2_000, 3, 1.5)).unwrap();
+        let results = collector.finish().unwrap();
+        let csv = std::fs::read_to_string(file.path()).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            csv,
+            "ttft_ms,total_ms,worker_id,start_unix_ms\n\
+             12.35,250.01,0,1700000000123.40\n\
+             1.00,2.00,3,1500.00\n"
+        );
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let mut out = Vec::new();
+        let fields = ["plain", "a,b", "say \"hi\"", "two\nlines"].map(String::from);
+        write_csv_row(&mut out, &fields).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\n"
+        );
+    }
+}
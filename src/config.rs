@@ -0,0 +1,491 @@
+use crate::cookie::CookieJar;
+use crate::jsonpath::JsonPath;
+use crate::schema::JsonSchema;
+use crate::soak::Soak;
+use crate::spike::Spike;
+use crate::template::BodyTemplate;
+use crate::util::{duration_secs, option_duration_secs, write_atomic};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Method, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// 默认的 User-Agent，便于在服务端日志中区分压测流量
+pub const DEFAULT_USER_AGENT: &str = concat!("interface-perf-test/", env!("CARGO_PKG_VERSION"));
+
+/// 启动信息、进度条和中间统计的输出位置
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    /// 不输出任何提示
+    Off,
+    Stdout,
+    Stderr,
+}
+
+impl LogTarget {
+    pub(crate) fn is_terminal(self) -> bool {
+        match self {
+            LogTarget::Off => false,
+            LogTarget::Stdout => std::io::stdout().is_terminal(),
+            LogTarget::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// --max-errors 的计数方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaxErrorsMode {
+    /// 连续失败次数，任一请求成功即清零
+    Consecutive,
+    /// 累计失败次数
+    Total,
+}
+
+/// SSE 模式下的事件格式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SseFormat {
+    /// 每个 JSON `data:` 事件计为一个 token 事件，`data: [DONE]` 结束
+    #[default]
+    OpenAi,
+    /// 带 `delta.text` 的 `content_block_delta` 事件计为一个 token 事件，`message_stop` 结束
+    Anthropic,
+}
+
+/// 只连接目标地址中该协议族的 IP
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /// 绑定该协议族的未指定地址后，连接器只会尝试同一协议族的目标地址
+    pub(crate) fn unspecified(self) -> IpAddr {
+        match self {
+            IpFamily::V4 => Ipv4Addr::UNSPECIFIED.into(),
+            IpFamily::V6 => Ipv6Addr::UNSPECIFIED.into(),
+        }
+    }
+
+    pub(crate) fn matches(self, addr: IpAddr) -> bool {
+        matches!(
+            (self, addr),
+            (IpFamily::V4, IpAddr::V4(_)) | (IpFamily::V6, IpAddr::V6(_))
+        )
+    }
+}
+
+/// 所有请求经由的 HTTP 代理
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    pub url: String,
+    /// 代理的 Basic 认证（用户名, 密码）
+    pub basic_auth: Option<(String, String)>,
+    /// 不经过代理的主机，格式同 NO_PROXY 环境变量
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            basic_auth: None,
+            no_proxy: None,
+        }
+    }
+
+    /// 构造 reqwest 的代理，url 无效时返回错误
+    pub fn to_proxy(&self) -> reqwest::Result<Proxy> {
+        let mut proxy = Proxy::all(&self.url)?;
+        if let Some((user, password)) = &self.basic_auth {
+            proxy = proxy.basic_auth(user, password);
+        }
+        if let Some(hosts) = &self.no_proxy {
+            proxy = proxy.no_proxy(NoProxy::from_string(hosts));
+        }
+        Ok(proxy)
+    }
+}
+
+/// 一次压测的完整配置，字段含义与命令行参数一一对应
+///
+/// 可序列化为 JSON（见 [`to_json_file`](Self::to_json_file)）：时长为秒数（f64），
+/// 请求头为 `[name, value]` 数组，`shutdown` 不序列化。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkConfig {
+    pub url: String,
+    /// 非空时请求按轮询分配到这些 URL（`url` 只用于显示和输出），为空时只请求 `url`
+    pub urls: Vec<String>,
+    /// 与 `urls` 一一对应的权重，请求按权重比例分配；为空时均匀轮询
+    pub url_weights: Vec<u32>,
+    #[serde(with = "method_str")]
+    pub method: Method,
+    /// 请求间轮流使用的请求体，为空表示不发送请求体
+    pub bodies: Vec<String>,
+    /// 非 None 时每个请求按模板生成请求体，代替 `bodies`
+    pub body_template: Option<BodyTemplate>,
+    /// 附加请求头，同名时覆盖默认的 Content-Type
+    #[serde(with = "header_pairs")]
+    pub headers: HeaderMap,
+    /// User-Agent 请求头，`headers` 中指定时以其为准
+    pub user_agent: String,
+    /// 并发 worker 数
+    pub concurrency: usize,
+    /// 总请求数（成功与重试后仍失败的请求都计入），与 `duration` 均为 None 时默认为 10
+    pub requests: Option<usize>,
+    /// 测试持续时间，与 `requests` 同时指定时先到者为准
+    #[serde(with = "option_duration_secs")]
+    pub duration: Option<Duration>,
+    /// 每个 worker 依次发起的请求数（含失败），指定时总请求数为 concurrency × iterations
+    pub iterations: Option<usize>,
+    /// 单个请求的总超时
+    #[serde(with = "duration_secs")]
+    pub timeout: Duration,
+    /// 建立连接的超时
+    #[serde(with = "duration_secs")]
+    pub connect_timeout: Duration,
+    /// 非 2xx 或网络错误时的最大重试次数
+    pub retries: u32,
+    /// 首次重试前的等待时间，之后每次翻倍，最长 60 秒
+    #[serde(with = "duration_secs")]
+    pub retry_delay: Duration,
+    /// 失败数达到该值时中止压测
+    pub max_errors: Option<usize>,
+    pub max_errors_mode: MaxErrorsMode,
+    /// 最近 10 秒错误率超过 50% 时暂停所有 worker 5 秒
+    pub circuit_breaker: bool,
+    pub proxy: Option<ProxyConfig>,
+    /// 只使用 IPv4 或 IPv6 连接，None 时由系统决定
+    pub ip_family: Option<IpFamily>,
+    /// 为 false 时不复用连接：每个请求新建连接，并关闭 TCP keepalive
+    pub keepalive: bool,
+    /// 为 false 时关闭 TCP_NODELAY（启用 Nagle 算法）
+    pub tcp_nodelay: bool,
+    /// TCP keepalive 探测间隔，None 使用 reqwest 的默认值
+    #[serde(with = "option_duration_secs")]
+    pub tcp_keepalive: Option<Duration>,
+    /// 每个响应最多读取的字节数，达到后停止读取并将结果标记为截断，None 为不限
+    pub max_body_size: Option<u64>,
+    /// 每个请求新建一个 HTTP 客户端（连接池、DNS 缓存都不共享），测量完整的冷启动延迟
+    pub connection_per_request: bool,
+    /// 每个主机最多保留的空闲连接数，None 为不限
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 空闲连接的保留时长，None 使用 reqwest 的默认值（90 秒）
+    #[serde(with = "option_duration_secs")]
+    pub pool_idle_timeout: Option<Duration>,
+    /// 为 false 时不跟随重定向，3xx 响应计为失败
+    pub follow_redirects: bool,
+    /// 跟随重定向的最大次数，超过时请求失败
+    pub max_redirects: usize,
+    /// 每个 chunk 折算的 token 数
+    pub tokens_per_chunk: u32,
+    /// 从 usage.completion_tokens 读取 token 数
+    pub parse_usage_tokens: bool,
+    /// 解析响应中的 usage（prompt / completion / total tokens），写入 `LatencyResult::usage`
+    pub vllm_usage: bool,
+    /// 读取 TGI 的 x-inference-time 等耗时响应头，写入 `LatencyResult::server_timing`
+    pub tgi_headers: bool,
+    /// 每个请求的输入 / 输出 token 数估算值，用于计算按 token 归一化的吞吐
+    pub input_tokens_estimate: Option<u32>,
+    pub output_tokens_estimate: Option<u32>,
+    /// 校验 2xx 响应体（SSE 模式下为每个事件），不符合时计为 `ErrorKind::SchemaValidation` 失败
+    pub response_schema: Option<JsonSchema>,
+    /// 对每个成功响应求值的 JSONPath，结果写入 `LatencyResult::extracted`
+    pub extract: Vec<JsonPath>,
+    /// 响应中 JSONPath 的取值须等于给定值（字符串按原文比较，其余按 JSON 文本比较）
+    pub field_assertions: Vec<(JsonPath, String)>,
+    /// 成功响应的状态码须等于该值
+    pub expected_status: Option<u16>,
+    /// 按 SSE `data:` 事件解析响应流
+    pub sse: bool,
+    /// `sse` 为 true 时的事件格式
+    pub sse_format: SseFormat,
+    /// 非 SSE 模式下读到 `data: [DONE]` 即结束读取，不等待 100ms 的空闲超时（SSE 模式下总是如此）
+    pub done_sentinel: bool,
+    /// 在该时长内逐个启动 worker，期间的结果不计入统计
+    #[serde(with = "option_duration_secs")]
+    pub ramp_up: Option<Duration>,
+    /// 正式计时前的预热请求数，成功与失败的请求都计入，且都不进入结果
+    pub warmup: usize,
+    /// 压测开始前用 HEAD 请求预先建立的连接数，0 为不预建
+    pub min_connections: usize,
+    /// 所有 worker 合计每秒最多发起的请求数
+    pub rate: Option<f64>,
+    /// 需要统计的百分位，取值范围 (0, 100)
+    pub percentiles: Vec<f64>,
+    /// 逐条写入原始数据（JSONL）
+    pub raw_output: Option<PathBuf>,
+    /// 逐条写入延迟数据（CSV）
+    pub csv_output: Option<PathBuf>,
+    /// 结束时将所有请求及响应（每个响应体最多 64 KB）写为 HAR 文件
+    pub record_har: Option<PathBuf>,
+    /// 将前 N 个请求的完整请求与响应以 curl -v 的格式写入 `trace_file`，0 为不记录
+    pub trace_requests: usize,
+    pub trace_file: PathBuf,
+    /// 运行期间在该端口提供 Prometheus /metrics
+    pub metrics_port: Option<u16>,
+    /// /metrics 监听的地址，默认只监听本机
+    pub metrics_addr: IpAddr,
+    /// OTLP/HTTP 接收端地址
+    pub otlp_endpoint: Option<String>,
+    /// 为每个请求注入 W3C traceparent 请求头
+    pub trace_context: bool,
+    /// 设置后每个 worker 以此为初始 cookie，并按响应的 Set-Cookie 更新；None 时不处理 cookie
+    pub cookie_jar: Option<CookieJar>,
+    /// 为每个请求生成一个 UUID 放入该请求头，并写入 --verbose 输出与逐请求记录
+    #[serde(with = "option_header_name")]
+    pub request_id_header: Option<HeaderName>,
+    /// 将第一个成功请求的响应体打印到 stdout
+    pub print_response: bool,
+    /// 用 t-digest 估算百分位，不保留逐请求结果（`BenchmarkResult::results` 为空）
+    pub streaming_quantiles: bool,
+    /// 用 HDR 直方图额外统计 P99.9 / P99.99
+    pub hdr_histogram: bool,
+    /// 将 TTFT 的 HDR 直方图以 .hgrm 百分位分布格式写入该文件
+    pub hdr_output: Option<PathBuf>,
+    /// 每个请求完成后向 stderr 打印一行耗时信息
+    pub verbose: bool,
+    /// 每个 worker 在两个请求之间的空闲时间，模拟用户思考
+    #[serde(with = "option_duration_secs")]
+    pub think_time: Option<Duration>,
+    /// 在 think_time 基础上额外增加 [0, think_time_jitter) 的随机时长
+    #[serde(with = "duration_secs")]
+    pub think_time_jitter: Duration,
+    /// 服务端本身的预期 TTFT，用于估算平均连接开销
+    #[serde(with = "option_duration_secs")]
+    pub expected_server_latency: Option<Duration>,
+    /// TTFT 的 SLO，结果中报告达标请求的占比
+    #[serde(with = "option_duration_secs")]
+    pub slo_ttft: Option<Duration>,
+    /// 端到端延迟的 SLO
+    #[serde(with = "option_duration_secs")]
+    pub slo_total: Option<Duration>,
+    /// 稳定性测试：按间隔记录时间序列，结束时比较首尾延迟
+    pub soak: Option<Soak>,
+    /// 突发负载：concurrency 为突发阶段的并发数
+    pub spike: Option<Spike>,
+    /// 以全屏界面代替进度条（仅当 stdout 为终端时）
+    pub tui: bool,
+    /// 以 RPS 走势图代替进度条（仅当 stderr 为终端时）
+    pub plot: bool,
+    /// 标识本次运行的标签（如版本号），写入结果、原始数据和历史记录
+    pub tags: Vec<String>,
+    /// 运行期间打印中间统计的间隔
+    #[serde(with = "option_duration_secs")]
+    pub report_interval: Option<Duration>,
+    pub log: LogTarget,
+    /// 外部置位后（如收到 Ctrl-C）停止发起新请求，并返回已收集的结果
+    #[serde(skip)]
+    pub shutdown: Option<Arc<AtomicBool>>,
+}
+
+impl BenchmarkConfig {
+    /// 与命令行默认值一致的配置（不输出提示信息）
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            urls: Vec::new(),
+            url_weights: Vec::new(),
+            method: Method::POST,
+            bodies: Vec::new(),
+            body_template: None,
+            headers: HeaderMap::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            concurrency: 10,
+            requests: None,
+            duration: None,
+            iterations: None,
+            timeout: Duration::from_secs(60),
+            connect_timeout: Duration::from_secs(10),
+            retries: 0,
+            retry_delay: Duration::from_millis(500),
+            max_errors: None,
+            max_errors_mode: MaxErrorsMode::Consecutive,
+            circuit_breaker: false,
+            proxy: None,
+            ip_family: None,
+            keepalive: true,
+            connection_per_request: false,
+            max_body_size: None,
+            tcp_nodelay: true,
+            tcp_keepalive: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            follow_redirects: true,
+            max_redirects: 10,
+            tokens_per_chunk: 1,
+            parse_usage_tokens: false,
+            vllm_usage: false,
+            tgi_headers: false,
+            input_tokens_estimate: None,
+            output_tokens_estimate: None,
+            response_schema: None,
+            extract: Vec::new(),
+            field_assertions: Vec::new(),
+            expected_status: None,
+            sse: false,
+            sse_format: SseFormat::OpenAi,
+            done_sentinel: false,
+            ramp_up: None,
+            warmup: 0,
+            min_connections: 0,
+            rate: None,
+            percentiles: vec![50.0, 95.0, 99.0, 99.9],
+            raw_output: None,
+            csv_output: None,
+            record_har: None,
+            trace_requests: 0,
+            trace_file: PathBuf::from("traces.txt"),
+            metrics_port: None,
+            metrics_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            otlp_endpoint: None,
+            trace_context: false,
+            request_id_header: None,
+            cookie_jar: None,
+            print_response: false,
+            streaming_quantiles: false,
+            hdr_histogram: false,
+            hdr_output: None,
+            verbose: false,
+            think_time: None,
+            think_time_jitter: Duration::ZERO,
+            expected_server_latency: None,
+            slo_ttft: None,
+            slo_total: None,
+            soak: None,
+            spike: None,
+            tui: false,
+            plot: false,
+            tags: Vec::new(),
+            report_interval: None,
+            log: LogTarget::Off,
+            shutdown: None,
+        }
+    }
+
+    /// 每个请求实际发送的请求头：有请求体时默认 `Content-Type: application/json`，
+    /// `headers` 中的同名请求头覆盖默认值（不含按请求生成的 traceparent）
+    pub fn request_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if self.has_body() {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        // extend 会整体替换同名请求头
+        headers.extend(self.headers.clone());
+        headers
+    }
+
+    /// 是否发送请求体（固定请求体或模板）
+    pub fn has_body(&self) -> bool {
+        !self.bodies.is_empty() || self.body_template.is_some()
+    }
+
+    /// 读取 [`to_json_file`](Self::to_json_file) 写出的配置；缺少的字段取 [`new`](Self::new)
+    /// 的默认值，只有 `url` 必须给出
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config {}: {e}", path.display()))?;
+        let invalid = |e: serde_json::Error| format!("{}: invalid config: {e}", path.display());
+        let serde_json::Value::Object(fields) = serde_json::from_str(&text).map_err(invalid)?
+        else {
+            return Err(format!("{}: config must be a JSON object", path.display()));
+        };
+        let Some(url) = fields.get("url").and_then(|url| url.as_str()) else {
+            return Err(format!("{}: config is missing \"url\"", path.display()));
+        };
+        let mut config = serde_json::to_value(Self::new(url)).map_err(invalid)?;
+        config.as_object_mut().unwrap().extend(fields);
+        serde_json::from_value(config).map_err(invalid)
+    }
+
+    /// 以带缩进的 JSON 写入 path；请求头（含认证信息）按原样写出
+    pub fn to_json_file(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_atomic(path, text + "\n")
+            .map_err(|e| format!("failed to write config {}: {e}", path.display()))
+    }
+
+    /// 需要完成的请求数，None 表示只受 `duration` 限制
+    pub fn request_limit(&self) -> Option<usize> {
+        // 均未指定时保持原来的默认值：10 个请求
+        match (self.iterations, self.requests, self.duration) {
+            (Some(iterations), _, _) => Some(iterations * self.concurrency),
+            (None, None, None) => Some(10),
+            (None, requests, _) => requests,
+        }
+    }
+}
+
+/// Method 序列化为 "POST" 等字符串
+mod method_str {
+    use reqwest::Method;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(method: &Method, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(method.as_str())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Method, D::Error> {
+        let method = String::deserialize(d)?;
+        Method::from_bytes(method.as_bytes()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// HeaderMap 序列化为 `[name, value]` 数组，保留同名请求头的顺序
+mod header_pairs {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(headers: &HeaderMap, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), String::from_utf8_lossy(value.as_bytes()))),
+        )
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<HeaderMap, D::Error> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in Vec::<(String, String)>::deserialize(d)? {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(D::Error::custom)?;
+            let value = HeaderValue::from_str(&value).map_err(D::Error::custom)?;
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+mod option_header_name {
+    use reqwest::header::HeaderName;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        name: &Option<HeaderName>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        match name {
+            Some(name) => s.serialize_some(name.as_str()),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<HeaderName>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|name| HeaderName::from_bytes(name.as_bytes()).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
@@ -0,0 +1,99 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// 新建连接的累计耗时（DNS 解析 + TCP 握手），复用连接池中的连接不计入
+#[derive(Debug, Default)]
+pub(crate) struct ConnectStats {
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl ConnectStats {
+    fn record(&self, d: Duration) {
+        let us = d.as_micros() as u64;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    /// 累计新建的连接数
+    pub(crate) fn connections(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// 没有新建过连接时返回 None
+    pub(crate) fn summary(&self) -> Option<ConnectSummary> {
+        let count = self.count.load(Ordering::Relaxed);
+        (count > 0).then(|| ConnectSummary {
+            connections: count,
+            avg_ms: self.sum_us.load(Ordering::Relaxed) as f64 / count as f64 / 1000.0,
+            max_ms: self.max_us.load(Ordering::Relaxed) as f64 / 1000.0,
+        })
+    }
+}
+
+/// 连接建立耗时的统计值，单位 ms
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConnectSummary {
+    /// 新建连接数
+    pub connections: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+/// 包装 reqwest 的连接器，记录每次 connect() 的耗时
+#[derive(Clone)]
+pub(crate) struct ConnectTimingLayer {
+    pub(crate) stats: Arc<ConnectStats>,
+}
+
+impl<S> Layer<S> for ConnectTimingLayer {
+    type Service = ConnectTiming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTiming {
+            inner,
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectTiming<S> {
+    inner: S,
+    stats: Arc<ConnectStats>,
+}
+
+impl<S, R> Service<R> for ConnectTiming<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let start = Instant::now();
+        let connecting = self.inner.call(req);
+        let stats = self.stats.clone();
+        Box::pin(async move {
+            let conn = connecting.await;
+            if conn.is_ok() {
+                stats.record(start.elapsed());
+            }
+            conn
+        })
+    }
+}
@@ -0,0 +1,510 @@
+use crate::util::unix_now;
+use reqwest::Url;
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 单个 cookie
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// None 表示发往所有主机（--cookie 未指定 domain 时）
+    pub domain: Option<String>,
+    /// 是否同时发往 domain 的子域名
+    pub include_subdomains: bool,
+    pub path: String,
+    /// 只通过 https 发送
+    pub secure: bool,
+    /// 过期时刻（Unix 时间戳，秒），None 为会话 cookie
+    pub expires: Option<f64>,
+}
+
+impl Cookie {
+    fn matches(&self, url: &Url, now: f64) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_ok = match &self.domain {
+            None => true,
+            Some(domain) => {
+                host == *domain
+                    || (self.include_subdomains && host.ends_with(&format!(".{domain}")))
+            }
+        };
+        domain_ok
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+/// 请求路径是否在 cookie 的 path 之下（RFC 6265 5.1.4）
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// 解析 --cookie 的 "name=value; path=/; domain=example.com"，未知属性忽略
+pub fn parse_cookie(s: &str) -> Result<Cookie, String> {
+    let invalid = || format!("invalid cookie (expected name=value): {s}");
+    let mut parts = s.split(';');
+    let (name, value) = parts
+        .next()
+        .and_then(|p| p.split_once('='))
+        .ok_or_else(invalid)?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        domain: None,
+        include_subdomains: false,
+        path: "/".to_string(),
+        secure: false,
+        expires: None,
+    };
+    apply_attributes(&mut cookie, parts, unix_now());
+    Ok(cookie)
+}
+
+/// 按 Path、Domain、Secure、Max-Age、Expires 属性修改 cookie；Max-Age 优先于 Expires
+fn apply_attributes<'a>(cookie: &mut Cookie, attributes: impl Iterator<Item = &'a str>, now: f64) {
+    let mut max_age = None;
+    for attribute in attributes {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "path" if value.starts_with('/') => cookie.path = value.to_string(),
+            "domain" if !value.is_empty() => {
+                cookie.domain = Some(value.trim_start_matches('.').to_ascii_lowercase());
+                cookie.include_subdomains = true;
+            }
+            "secure" => cookie.secure = true,
+            "max-age" => max_age = value.parse::<i64>().ok(),
+            "expires" if max_age.is_none() => {
+                if let Some(expires) = parse_http_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(max_age) = max_age {
+        cookie.expires = Some(now + max_age as f64);
+    }
+}
+
+/// 读取 --cookie-jar 指定的 Netscape 格式 cookie 文件（curl -c / 浏览器扩展导出的 cookies.txt）：
+/// 每行 7 个以 Tab 分隔的字段 domain、include_subdomains、path、secure、expires、name、value，
+/// 跳过空行和 `#` 开头的注释（`#HttpOnly_` 前缀的行除外），expires 为 0 表示会话 cookie
+pub fn load_cookie_file(path: &Path) -> Result<Vec<Cookie>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read --cookie-jar {}: {e}", path.display()))?;
+    let mut cookies = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |reason: &str| format!("{}:{}: {reason}", path.display(), i + 1);
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [
+            domain,
+            subdomains,
+            cookie_path,
+            secure,
+            expires,
+            name,
+            value,
+        ] = fields[..]
+        else {
+            return Err(invalid("expected 7 tab-separated fields"));
+        };
+        let expires: f64 = expires
+            .trim()
+            .parse()
+            .map_err(|_| invalid("invalid expiry"))?;
+        cookies.push(Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: Some(domain.trim_start_matches('.').to_ascii_lowercase()),
+            include_subdomains: subdomains.eq_ignore_ascii_case("TRUE"),
+            path: cookie_path.to_string(),
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            expires: (expires > 0.0).then_some(expires),
+        });
+    }
+    Ok(cookies)
+}
+
+/// 每个 worker 各自维护的 cookie，按响应中的 Set-Cookie 更新
+///
+/// reqwest 跟随重定向时看不到中间响应的 Set-Cookie，只有最终响应中的会被记录。
+///
+/// ```
+/// use interface_performance_test::{CookieJar, parse_cookie};
+///
+/// let jar = CookieJar::new(vec![parse_cookie("session=abc123; path=/v1").unwrap()]);
+/// assert_eq!(jar.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// 同名、同 domain、同 path 的 cookie 后者覆盖前者
+    pub fn new(cookies: Vec<Cookie>) -> Self {
+        let mut jar = Self::default();
+        for cookie in cookies {
+            jar.insert(cookie);
+        }
+        jar
+    }
+
+    pub fn into_cookies(self) -> Vec<Cookie> {
+        self.cookies
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    fn insert(&mut self, cookie: Cookie) {
+        self.remove(&cookie);
+        self.cookies.push(cookie);
+    }
+
+    /// 删除与 cookie 同名、同 path 且同 domain 的 cookie；未指定 domain 的 --cookie 视为同 domain，
+    /// 服务端刷新同名 cookie 时不会同时发送新旧两个值
+    fn remove(&mut self, cookie: &Cookie) {
+        self.cookies.retain(|c| {
+            c.name != cookie.name
+                || c.path != cookie.path
+                || (c.domain.is_some() && c.domain != cookie.domain)
+        });
+    }
+
+    /// 发往 url 的 Cookie 请求头，path 更长的 cookie 排在前面；没有匹配的 cookie 时为 None
+    pub(crate) fn header_for(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        let now = unix_now();
+        let mut matching: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(&url, now))
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    /// 记录响应的 Set-Cookie；已过期（如 Max-Age=0）的 cookie 从 jar 中删除
+    pub(crate) fn store_response(&mut self, url: &Url, headers: &HeaderMap) {
+        let now = unix_now();
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        // 未指定 Path 时取请求路径的目录部分（RFC 6265 5.1.4）
+        let default_path = match url.path().rfind('/') {
+            Some(0) | None => "/".to_string(),
+            Some(i) => url.path()[..i].to_string(),
+        };
+        for header in headers.get_all(SET_COOKIE) {
+            let Ok(header) = header.to_str() else {
+                continue;
+            };
+            let mut parts = header.split(';');
+            let Some((name, value)) = parts.next().and_then(|p| p.split_once('=')) else {
+                continue;
+            };
+            if name.trim().is_empty() {
+                continue;
+            }
+            let mut cookie = Cookie {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                domain: Some(host.clone()),
+                include_subdomains: false,
+                path: default_path.clone(),
+                secure: false,
+                expires: None,
+            };
+            apply_attributes(&mut cookie, parts, now);
+            // 不接受为其他域名设置的 cookie
+            let domain = cookie.domain.as_deref().unwrap_or_default();
+            if host != domain && !host.ends_with(&format!(".{domain}")) {
+                continue;
+            }
+            if cookie.expires.is_some_and(|expires| expires <= now) {
+                self.remove(&cookie);
+            } else {
+                self.insert(cookie);
+            }
+        }
+    }
+}
+
+/// 解析 Expires 中的 HTTP 日期，如 "Sun, 06 Nov 1994 08:49:37 GMT"、"Sunday, 06-Nov-94 08:49:37 GMT"
+fn parse_http_date(s: &str) -> Option<f64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+    for token in s.split([' ', ',', '-']).filter(|t| !t.is_empty()) {
+        if let [h, m, sec] = token.split(':').collect::<Vec<_>>()[..] {
+            let field = |s: &str| s.parse::<i64>().ok();
+            time = Some((field(h)?, field(m)?, field(sec)?));
+        } else if let Some(i) = MONTHS.iter().position(|m| token.eq_ignore_ascii_case(m)) {
+            month = Some(i as i64 + 1);
+        } else if let Ok(n) = token.parse::<i64>() {
+            match (token.len(), day) {
+                (1 | 2, None) => day = Some(n),
+                // 两位年份：70-99 为 19xx，00-69 为 20xx
+                (2, Some(_)) => year = Some(if n >= 70 { 1900 + n } else { 2000 + n }),
+                _ => year = Some(n),
+            }
+        }
+    }
+    let (year, month, day, (h, m, sec)) = (year?, month?, day?, time?);
+
+    // Howard Hinnant 的 days_from_civil 算法
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some((days * 86_400 + h * 3600 + m * 60 + sec) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempFile;
+    use reqwest::header::HeaderValue;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn store(jar: &mut CookieJar, request_url: &str, set_cookies: &[&str]) {
+        let mut headers = HeaderMap::new();
+        for value in set_cookies {
+            headers.append(SET_COOKIE, HeaderValue::from_str(value).unwrap());
+        }
+        jar.store_response(&url(request_url), &headers);
+    }
+
+    #[test]
+    fn parses_cookie_attributes() {
+        let cookie =
+            parse_cookie(" session = abc ; Path=/v1; Domain=.Example.COM; Secure; HttpOnly")
+                .unwrap();
+        assert_eq!(cookie.name, "session");
+        assert_eq!(cookie.value, "abc");
+        assert_eq!(cookie.path, "/v1");
+        assert_eq!(cookie.domain.as_deref(), Some("example.com"));
+        assert!(cookie.include_subdomains);
+        assert!(cookie.secure);
+        assert_eq!(cookie.expires, None);
+
+        // 值中可以包含 =
+        assert_eq!(parse_cookie("token=a=b").unwrap().value, "a=b");
+        assert!(parse_cookie("novalue").is_err());
+        assert!(parse_cookie("=abc").is_err());
+    }
+
+    #[test]
+    fn max_age_overrides_expires() {
+        let mut cookie = parse_cookie("a=1").unwrap();
+        let attributes = "Expires=Sun, 06 Nov 1994 08:49:37 GMT; Max-Age=60".split(';');
+        apply_attributes(&mut cookie, attributes, 1000.0);
+        assert_eq!(cookie.expires, Some(1060.0));
+
+        let mut cookie = parse_cookie("a=1").unwrap();
+        apply_attributes(
+            &mut cookie,
+            "Max-Age=60; Expires=Sun, 06 Nov 1994 08:49:37 GMT".split(';'),
+            1000.0,
+        );
+        assert_eq!(cookie.expires, Some(1060.0));
+    }
+
+    #[test]
+    fn parses_http_dates() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777.0)
+        );
+        assert_eq!(
+            parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"),
+            Some(784_111_777.0)
+        );
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0.0));
+        assert_eq!(
+            parse_http_date("Wed, 09 Jun 2021 10:18:14 GMT"),
+            Some(1_623_233_894.0)
+        );
+        assert_eq!(parse_http_date("not a date"), None);
+    }
+
+    #[test]
+    fn scopes_cookies_by_domain_and_path() {
+        let cookie = |s: &str| parse_cookie(s).unwrap();
+        let jar = CookieJar::new(vec![
+            cookie("any=1"),
+            cookie("api=2; path=/v1"),
+            cookie("sub=3; domain=example.com"),
+            cookie("secure=4; domain=example.com; secure"),
+        ]);
+        assert_eq!(
+            jar.header_for("http://api.example.com/v1/chat").as_deref(),
+            Some("api=2; any=1; sub=3")
+        );
+        assert_eq!(
+            jar.header_for("https://example.com/v1").as_deref(),
+            Some("api=2; any=1; sub=3; secure=4")
+        );
+        // /v10 不在 /v1 之下；其他主机收不到带 domain 的 cookie
+        assert_eq!(
+            jar.header_for("http://other.test/v10").as_deref(),
+            Some("any=1")
+        );
+        assert_eq!(
+            jar.header_for("http://notexample.com/").as_deref(),
+            Some("any=1")
+        );
+
+        let jar = CookieJar::new(vec![cookie("api=2; path=/v1/")]);
+        assert_eq!(jar.header_for("http://host/v1"), None);
+        assert!(jar.header_for("http://host/v1/x").is_some());
+    }
+
+    #[test]
+    fn stores_and_expires_set_cookie() {
+        let mut jar = CookieJar::new(vec![parse_cookie("session=old").unwrap()]);
+        store(
+            &mut jar,
+            "http://api.example.com/v1/chat/completions",
+            &[
+                "session=new; Path=/",
+                "scoped=1",
+                "parent=2; Domain=example.com; Path=/",
+                "foreign=3; Domain=other.test",
+                "gone=4; Max-Age=0",
+            ],
+        );
+        // 同名 --cookie 被覆盖；未指定 Path 时取请求路径的目录部分；不接受其他域名
+        assert_eq!(jar.len(), 3);
+        assert_eq!(
+            jar.header_for("http://api.example.com/v1/chat/x")
+                .as_deref(),
+            Some("scoped=1; session=new; parent=2")
+        );
+        assert_eq!(
+            jar.header_for("http://api.example.com/").as_deref(),
+            Some("session=new; parent=2")
+        );
+        assert_eq!(
+            jar.header_for("http://www.example.com/").as_deref(),
+            Some("parent=2")
+        );
+
+        store(
+            &mut jar,
+            "http://api.example.com/",
+            &[
+                "session=x; Max-Age=0",
+                "parent=y; Domain=example.com; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            ],
+        );
+        assert_eq!(jar.len(), 1);
+        assert_eq!(jar.header_for("http://api.example.com/"), None);
+    }
+
+    #[test]
+    fn skips_expired_cookies() {
+        let mut expired = parse_cookie("old=1").unwrap();
+        expired.expires = Some(unix_now() - 1.0);
+        let mut valid = parse_cookie("new=2").unwrap();
+        valid.expires = Some(unix_now() + 3600.0);
+        let jar = CookieJar::new(vec![expired, valid]);
+        assert_eq!(jar.header_for("http://host/").as_deref(), Some("new=2"));
+    }
+
+    #[test]
+    fn loads_netscape_cookie_file() {
+        let file = TempFile::new("cookies.txt");
+        std::fs::write(
+            file.path(),
+            "# Netscape HTTP Cookie File\n\
+             \n\
+             .example.com\tTRUE\t/\tFALSE\t0\tsession\tabc\r\n\
+             #HttpOnly_api.example.com\tFALSE\t/v1\tTRUE\t4102444800\ttoken\txyz\n",
+        )
+        .unwrap();
+        let cookies = load_cookie_file(file.path()).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(
+            cookies[0],
+            Cookie {
+                name: "session".to_string(),
+                value: "abc".to_string(),
+                domain: Some("example.com".to_string()),
+                include_subdomains: true,
+                path: "/".to_string(),
+                secure: false,
+                expires: None,
+            }
+        );
+        let token = &cookies[1];
+        assert_eq!(
+            (token.name.as_str(), token.value.as_str()),
+            ("token", "xyz")
+        );
+        assert_eq!(token.domain.as_deref(), Some("api.example.com"));
+        assert!(!token.include_subdomains && token.secure);
+        assert_eq!(token.path, "/v1");
+        assert_eq!(token.expires, Some(4_102_444_800.0));
+    }
+
+    #[test]
+    fn rejects_malformed_cookie_file() {
+        let file = TempFile::new("bad-cookies.txt");
+        for (content, error) in [
+            (
+                "example.com\tFALSE\t/\tFALSE\t0\tname\n",
+                ":1: expected 7 tab-separated fields",
+            ),
+            (
+                "# c\nexample.com\tFALSE\t/\tFALSE\tsoon\tname\tv\n",
+                ":2: invalid expiry",
+            ),
+        ] {
+            std::fs::write(file.path(), content).unwrap();
+            let err = load_cookie_file(file.path()).unwrap_err();
+            assert!(err.ends_with(error), "{err}");
+        }
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(
+            load_cookie_file(&path)
+                .unwrap_err()
+                .starts_with("failed to read --cookie-jar")
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! 不依赖外部 crate 的 gzip 解压（RFC 1952 / RFC 1951），仅用于读取 HAR 中压缩过的请求体，
+//! 按 zlib 的 puff.c 以逐位解码的方式实现，不追求速度。
+
+const MAX_BITS: usize = 15;
+
+/// 长度码 257..=285 的基础长度与附加位数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// 距离码 0..=29 的基础距离与附加位数
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// 动态 Huffman 块中码长码表的码长顺序
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// 解压 gzip 数据，校验尾部的 CRC32 与长度
+pub(crate) fn gunzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 18 || data[0] != 0x1f || data[1] != 0x8b {
+        return Err("not gzip data".to_string());
+    }
+    if data[2] != 8 {
+        return Err(format!("unsupported gzip compression method {}", data[2]));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    let truncated = || "truncated gzip header".to_string();
+    if flags & 0x04 != 0 {
+        // FEXTRA
+        let len = data.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + usize::from(u16::from_le_bytes([len[0], len[1]]));
+    }
+    for flag in [0x08, 0x10] {
+        // FNAME、FCOMMENT：以 0 结尾的字符串
+        if flags & flag != 0 {
+            let end = data.get(pos..).and_then(|d| d.iter().position(|&b| b == 0));
+            pos += end.ok_or_else(truncated)? + 1;
+        }
+    }
+    if flags & 0x02 != 0 {
+        // FHCRC
+        pos += 2;
+    }
+    let body = data.get(pos..data.len() - 8).ok_or_else(truncated)?;
+    let out = inflate(body)?;
+
+    let trailer = &data[data.len() - 8..];
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if size != out.len() as u32 || crc != crc32(&out) {
+        return Err("gzip checksum mismatch".to_string());
+    }
+    Ok(out)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32, String> {
+        while self.bit_count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("unexpected end of deflate stream")?;
+            self.pos += 1;
+            self.bit_buf |= u32::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buf & ((1u32 << n) - 1);
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    /// 丢弃当前字节中剩余的位（stored 块从字节边界开始）
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+/// 规范 Huffman 码表：每个码长的码字数，以及按码字顺序排列的符号
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+        // 码长过多（超额订阅）的码表无效，不完整的码表允许（只有一个距离码时很常见）
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - i32::from(count);
+            if left < 0 {
+                return Err("invalid Huffman code lengths".to_string());
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader {
+        data,
+        pos: 0,
+        bit_buf: 0,
+        bit_count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = data
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or("truncated stored block")?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("invalid stored block length".to_string());
+                }
+                let start = reader.pos + 4;
+                let block = data
+                    .get(start..start + usize::from(len))
+                    .ok_or("truncated stored block")?;
+                out.extend_from_slice(block);
+                reader.pos = start + usize::from(len);
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths)?;
+                let distances = Huffman::new(&[5; 30])?;
+                inflate_block(&mut reader, &literals, &distances, &mut out)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &literals, &distances, &mut out)?;
+            }
+            _ => return Err("invalid deflate block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let codes = Huffman::new(&code_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match codes.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("repeat with no previous length")?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err("too many code lengths".to_string());
+    }
+    if lengths[256] == 0 {
+        return Err("missing end-of-block code".to_string());
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count])?,
+        Huffman::new(&lengths[literal_count..])?,
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = usize::from(symbol - 257);
+                if i >= LENGTH_BASE.len() {
+                    return Err("invalid length code".to_string());
+                }
+                let extra = reader.bits(u32::from(LENGTH_EXTRA[i]))?;
+                let len = usize::from(LENGTH_BASE[i]) + extra as usize;
+                let d = usize::from(distances.decode(reader)?);
+                if d >= DIST_BASE.len() {
+                    return Err("invalid distance code".to_string());
+                }
+                let extra = reader.bits(u32::from(DIST_EXTRA[d]))?;
+                let dist = usize::from(DIST_BASE[d]) + extra as usize;
+                if dist > out.len() {
+                    return Err("distance too far back".to_string());
+                }
+                // 距离小于长度时会重复刚写入的字节，只能逐字节复制
+                let start = out.len() - dist;
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以下数据由 zlib 生成（mtime 为 0），注释为每个 deflate 块的类型
+
+    /// stored 块：`{"prompt":"hi"}`
+    const STORED: [u8; 38] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x03, 0x01, 0x0f, 0x00, 0xf0, 0xff,
+        0x7b, 0x22, 0x70, 0x72, 0x6f, 0x6d, 0x70, 0x74, 0x22, 0x3a, 0x22, 0x68, 0x69, 0x22, 0x7d,
+        0x56, 0x05, 0x28, 0x18, 0x0f, 0x00, 0x00, 0x00,
+    ];
+
+    /// 固定 Huffman 块：`{"prompt":"hello hello hello"}`
+    const FIXED: [u8; 40] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x56, 0x2a, 0x28, 0xca,
+        0xcf, 0x2d, 0x28, 0x51, 0xb2, 0x52, 0xca, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0x40, 0x22, 0x95,
+        0x6a, 0x01, 0x38, 0xbc, 0x1b, 0x87, 0x1e, 0x00, 0x00, 0x00,
+    ];
+
+    /// 动态 Huffman 块：DYNAMIC_TEXT
+    const DYNAMIC: [u8; 119] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x75, 0xcc, 0x3b, 0x0a, 0x02,
+        0x41, 0x10, 0x84, 0xe1, 0xab, 0x34, 0x1d, 0x2f, 0x1e, 0x60, 0xcf, 0x61, 0x22, 0xb2, 0x41,
+        0x2b, 0xe5, 0x6b, 0x7b, 0xa6, 0x65, 0xaa, 0x27, 0x90, 0x65, 0xef, 0xee, 0x9a, 0x08, 0x06,
+        0x46, 0x05, 0x3f, 0x7c, 0xb5, 0x68, 0x01, 0x69, 0x57, 0x50, 0xc7, 0xe3, 0xa2, 0x2d, 0x1c,
+        0x3a, 0x2a, 0x5f, 0x4c, 0x14, 0x1d, 0xf4, 0x1c, 0x35, 0x51, 0x73, 0x4b, 0x87, 0xe8, 0x62,
+        0x0d, 0x62, 0x72, 0x83, 0x3f, 0x2f, 0xdd, 0xc5, 0xc8, 0x3b, 0xd3, 0x6a, 0xee, 0x74, 0x1d,
+        0xbe, 0xb4, 0x13, 0xed, 0x07, 0xee, 0xe1, 0x2e, 0xe5, 0x03, 0x1f, 0x31, 0x6f, 0x73, 0x8a,
+        0x9e, 0x7f, 0x5e, 0xa6, 0xf5, 0x0d, 0x7e, 0x6a, 0x28, 0x37, 0x8f, 0x00, 0x00, 0x00,
+    ];
+
+    const DYNAMIC_TEXT: &str = concat!(
+        r#"{"messages":[{"role":"system","content":"You are a helpful assistant."},"#,
+        r#"{"role":"user","content":"Tell me a joke about a helpful assistant."}]}"#,
+    );
+
+    /// deflate 数据第一个块的 BTYPE
+    fn block_type(gz: &[u8]) -> u8 {
+        (gz[10] >> 1) & 0b11
+    }
+
+    #[test]
+    fn decodes_stored_block() {
+        assert_eq!(block_type(&STORED), 0);
+        assert_eq!(gunzip(&STORED).unwrap(), br#"{"prompt":"hi"}"#);
+    }
+
+    #[test]
+    fn decodes_fixed_huffman_block() {
+        assert_eq!(block_type(&FIXED), 1);
+        assert_eq!(
+            gunzip(&FIXED).unwrap(),
+            br#"{"prompt":"hello hello hello"}"#
+        );
+    }
+
+    #[test]
+    fn decodes_dynamic_huffman_block() {
+        assert_eq!(block_type(&DYNAMIC), 2);
+        assert_eq!(gunzip(&DYNAMIC).unwrap(), DYNAMIC_TEXT.as_bytes());
+    }
+
+    #[test]
+    fn rejects_crc_and_size_mismatch() {
+        let mut bad_crc = FIXED;
+        bad_crc[FIXED.len() - 8] ^= 0xff;
+        assert_eq!(gunzip(&bad_crc).unwrap_err(), "gzip checksum mismatch");
+
+        let mut bad_size = FIXED;
+        bad_size[FIXED.len() - 4] += 1;
+        assert_eq!(gunzip(&bad_size).unwrap_err(), "gzip checksum mismatch");
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        assert_eq!(gunzip(&DYNAMIC[..10]).unwrap_err(), "not gzip data");
+        // 保留尾部 8 字节，去掉 deflate 数据的后半部分
+        let truncated = [&DYNAMIC[..50], &DYNAMIC[DYNAMIC.len() - 8..]].concat();
+        assert!(gunzip(&truncated).is_err());
+        let truncated = [&STORED[..20], &STORED[STORED.len() - 8..]].concat();
+        assert_eq!(gunzip(&truncated).unwrap_err(), "truncated stored block");
+    }
+
+    #[test]
+    fn rejects_non_gzip_data() {
+        let mut data = STORED;
+        data[2] = 7;
+        assert_eq!(
+            gunzip(&data).unwrap_err(),
+            "unsupported gzip compression method 7"
+        );
+        assert_eq!(gunzip(&[0u8; 32]).unwrap_err(), "not gzip data");
+    }
+}
@@ -0,0 +1,373 @@
+use crate::gzip::gunzip;
+use crate::util::{format_utc, write_atomic};
+use base64::prelude::*;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// --record-har / --trace-requests 中每个响应体最多保留的字节数
+pub(crate) const MAX_RECORDED_BODY: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+struct HarEntry {
+    request: HarRequest,
+}
+
+#[derive(Deserialize)]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    #[serde(rename = "postData")]
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Deserialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct HarPostData {
+    #[serde(rename = "mimeType", default)]
+    mime_type: String,
+    #[serde(default)]
+    text: String,
+    /// 非标准字段：部分工具对二进制请求体以 "base64" 编码导出
+    encoding: Option<String>,
+}
+
+impl HarRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+}
+
+/// 从 HAR 文件中提取 URL 匹配 url_pattern 的 JSON POST 请求体，按抓包顺序排列。
+///
+/// URL 包含 url_pattern，或与其路径相同（抓包环境与压测目标的主机不同时）即视为匹配；
+/// 请求体按 `encoding: "base64"` 解码，带 `Content-Encoding: gzip` 或 gzip 文件头时解压。
+pub fn load_har_bodies(path: &Path, url_pattern: &str) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read --har-file {}: {e}", path.display()))?;
+    let har: Har =
+        serde_json::from_str(&text).map_err(|e| format!("{}: invalid HAR: {e}", path.display()))?;
+    let pattern_path = reqwest::Url::parse(url_pattern)
+        .ok()
+        .map(|url| url.path().to_string())
+        .filter(|path| path != "/");
+
+    let mut bodies = Vec::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let request = &entry.request;
+        let url_matches = request.url.contains(url_pattern)
+            || pattern_path.as_deref().is_some_and(|pattern| {
+                reqwest::Url::parse(&request.url).is_ok_and(|url| url.path() == pattern)
+            });
+        if !request.method.eq_ignore_ascii_case("POST") || !url_matches {
+            continue;
+        }
+        let Some(post_data) = &request.post_data else {
+            continue;
+        };
+        let mime_type = request
+            .header("content-type")
+            .unwrap_or(&post_data.mime_type);
+        let is_json = mime_type
+            .split(';')
+            .next()
+            .is_some_and(|m| m.trim().eq_ignore_ascii_case("application/json"));
+        if !is_json || post_data.text.is_empty() {
+            continue;
+        }
+
+        let context = |e: String| format!("{}: entry {}: {e}", path.display(), i + 1);
+        let mut bytes = match post_data.encoding.as_deref() {
+            Some("base64") => BASE64_STANDARD
+                .decode(post_data.text.trim())
+                .map_err(|e| context(format!("invalid base64 body: {e}")))?,
+            _ => post_data.text.clone().into_bytes(),
+        };
+        let gzipped = request
+            .header("content-encoding")
+            .is_some_and(|e| e.eq_ignore_ascii_case("gzip"));
+        if gzipped || bytes.starts_with(&[0x1f, 0x8b]) {
+            bytes = gunzip(&bytes).map_err(context)?;
+        }
+        let body =
+            String::from_utf8(bytes).map_err(|e| context(format!("body is not UTF-8: {e}")))?;
+        serde_json::from_str::<serde_json::Value>(&body)
+            .map_err(|e| context(format!("invalid JSON body: {e}")))?;
+        bodies.push(body);
+    }
+    if bodies.is_empty() {
+        return Err(format!(
+            "{} contains no JSON POST requests matching {url_pattern}",
+            path.display()
+        ));
+    }
+    Ok(bodies)
+}
+
+/// --record-har / --trace-requests 记录的请求
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub(crate) method: String,
+    pub(crate) url: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<String>,
+}
+
+/// --record-har / --trace-requests 记录的响应；未收到响应（网络错误）时 status 为 0，error 为错误信息
+pub(crate) struct RecordedResponse<'a> {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: &'a [u8],
+    /// 请求发出到首个响应数据（TTFT）
+    pub(crate) wait: Duration,
+    /// 首个响应数据到读取结束
+    pub(crate) receive: Duration,
+    pub(crate) error: Option<String>,
+}
+
+pub(crate) fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+/// 收集所有 worker 的请求，结束时写为一个 HAR 1.2 文件
+#[derive(Debug, Default)]
+pub(crate) struct HarRecorder {
+    entries: Mutex<Vec<(f64, Value)>>,
+}
+
+impl HarRecorder {
+    pub(crate) fn record(
+        &self,
+        start_ts: f64,
+        request: &RecordedRequest,
+        response: &RecordedResponse,
+    ) {
+        let headers = |headers: &[(String, String)]| -> Vec<Value> {
+            headers
+                .iter()
+                .map(|(name, value)| json!({"name": name, "value": value}))
+                .collect()
+        };
+        let find_header = |headers: &[(String, String)], name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+        };
+        let query: Vec<Value> = reqwest::Url::parse(&request.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| json!({"name": name, "value": value}))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+        let mut request_json = json!({
+            "method": request.method,
+            "url": request.url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(&request.headers),
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": request.body.as_ref().map_or(0, String::len),
+        });
+        if let Some(body) = &request.body {
+            request_json["postData"] = json!({
+                "mimeType": find_header(&request.headers, "content-type").unwrap_or_default(),
+                "text": body,
+            });
+        }
+
+        let recorded = &response.body[..response.body.len().min(MAX_RECORDED_BODY)];
+        let mut content = json!({
+            "size": response.body.len(),
+            "mimeType": find_header(&response.headers, "content-type").unwrap_or_default(),
+            "text": String::from_utf8_lossy(recorded),
+        });
+        if recorded.len() < response.body.len() {
+            content["comment"] = json!(format!("truncated to {MAX_RECORDED_BODY} bytes"));
+        }
+        let status_text = reqwest::StatusCode::from_u16(response.status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("");
+        let mut entry = json!({
+            "startedDateTime": format_iso8601(start_ts),
+            "time": ms(response.wait + response.receive),
+            "request": request_json,
+            "response": {
+                "status": response.status,
+                "statusText": status_text,
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": headers(&response.headers),
+                "content": content,
+                "redirectURL": find_header(&response.headers, "location").unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": response.body.len(),
+            },
+            "cache": {},
+            // 连接阶段的耗时没有单独测量
+            "timings": {
+                "blocked": -1,
+                "dns": -1,
+                "connect": -1,
+                "ssl": -1,
+                "send": 0,
+                "wait": ms(response.wait),
+                "receive": ms(response.receive),
+            },
+        });
+        if let Some(error) = &response.error {
+            entry["_error"] = json!(error);
+        }
+        self.entries.lock().unwrap().push((start_ts, entry));
+    }
+
+    /// 按请求发出时刻排序后写入 path，返回写入的请求数
+    pub(crate) fn write(&self, path: &Path) -> Result<usize, String> {
+        let mut entries = std::mem::take(&mut *self.entries.lock().unwrap());
+        entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let count = entries.len();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": "interface-perf-test",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": entries.into_iter().map(|(_, entry)| entry).collect::<Vec<_>>(),
+            }
+        });
+        let text = serde_json::to_string_pretty(&har).map_err(|e| e.to_string())?;
+        write_atomic(path, text)
+            .map_err(|e| format!("failed to write --record-har {}: {e}", path.display()))?;
+        Ok(count)
+    }
+}
+
+/// Unix 时间戳（秒）转为带毫秒的 ISO 8601 UTC 时间，如 "2024-01-01T00:00:00.123Z"
+pub(crate) fn format_iso8601(ts: f64) -> String {
+    let secs = format_utc(ts);
+    let millis = (ts.fract() * 1000.0) as u32;
+    format!("{}.{millis:03}Z", secs.trim_end_matches('Z'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempFile;
+
+    const URL: &str = "http://127.0.0.1:8000/v1/chat/completions";
+
+    fn entry(method: &str, url: &str, headers: Value, post_data: Value) -> Value {
+        json!({"request": {"method": method, "url": url, "headers": headers, "postData": post_data}})
+    }
+
+    fn write_har(name: &str, entries: Vec<Value>) -> TempFile {
+        let file = TempFile::new(&format!("{name}.har"));
+        let har = json!({"log": {"version": "1.2", "entries": entries}});
+        std::fs::write(file.path(), har.to_string()).unwrap();
+        file
+    }
+
+    #[test]
+    fn loads_plain_and_gzipped_bodies() {
+        let json_body = |text: &str| json!({"mimeType": "application/json", "text": text});
+        let file = write_har(
+            "bodies",
+            vec![
+                entry("POST", URL, json!([]), json_body(r#"{"prompt":"plain"}"#)),
+                // 固定 Huffman 编码的 `{"prompt":"hello hello hello"}`
+                entry(
+                    "POST",
+                    URL,
+                    json!([{"name": "Content-Encoding", "value": "gzip"}]),
+                    json!({
+                        "mimeType": "application/json",
+                        "text": "H4sIAAAAAAACA6tWKijKzy0oUbJSykjNyclXQCKVagE4vBuHHgAAAA==",
+                        "encoding": "base64",
+                    }),
+                ),
+                entry("GET", URL, json!([]), json_body(r#"{"prompt":"get"}"#)),
+                entry(
+                    "POST",
+                    "http://127.0.0.1:8000/v1/embeddings",
+                    json!([]),
+                    json_body("{}"),
+                ),
+                entry(
+                    "POST",
+                    URL,
+                    json!([]),
+                    json!({"mimeType": "text/plain", "text": "hi"}),
+                ),
+                // 主机不同但路径相同
+                entry(
+                    "POST",
+                    "https://api.example.com/v1/chat/completions",
+                    json!([{"name": "content-type", "value": "application/json; charset=utf-8"}]),
+                    json!({"mimeType": "", "text": r#"{"prompt":"other host"}"#}),
+                ),
+            ],
+        );
+        let bodies = load_har_bodies(file.path(), URL).unwrap();
+        assert_eq!(
+            bodies,
+            [
+                r#"{"prompt":"plain"}"#,
+                r#"{"prompt":"hello hello hello"}"#,
+                r#"{"prompt":"other host"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_bad_entries() {
+        let entries = vec![entry(
+            "POST",
+            URL,
+            json!([]),
+            json!({"mimeType": "application/json", "text": "{not json"}),
+        )];
+        let file = write_har("invalid", entries);
+        let err = load_har_bodies(file.path(), URL).unwrap_err();
+        let missing =
+            load_har_bodies(file.path(), "http://127.0.0.1:8000/v1/embeddings").unwrap_err();
+        assert!(err.contains("entry 1: invalid JSON body"), "{err}");
+        assert!(missing.contains("no JSON POST requests"), "{missing}");
+    }
+}
@@ -0,0 +1,277 @@
+use crate::stats::LatencyResult;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::time::Duration;
+
+/// 3 位有效数字：每个桶分为 2048 个子桶，相对误差不超过 0.1%
+const SUB_BUCKET_HALF_COUNT_MAGNITUDE: u32 = 10;
+const SUB_BUCKET_HALF_COUNT: u64 = 1 << SUB_BUCKET_HALF_COUNT_MAGNITUDE;
+const SUB_BUCKET_MASK: u64 = (SUB_BUCKET_HALF_COUNT << 1) - 1;
+/// .hgrm 输出中每个“剩余一半”区间打印的百分位行数，与 HdrHistogram 默认值一致
+const TICKS_PER_HALF_DISTANCE: u32 = 5;
+
+/// HdrHistogram 同款的对数-线性直方图，以微秒为单位记录，内存只随取值范围增长
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HdrHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+    sum: f64,
+    sum_sq: f64,
+}
+
+fn index_of(value: u64) -> usize {
+    let pow2ceiling = 64 - (value | SUB_BUCKET_MASK).leading_zeros();
+    let bucket = pow2ceiling - (SUB_BUCKET_HALF_COUNT_MAGNITUDE + 1);
+    let sub_bucket = value >> bucket;
+    (((u64::from(bucket) + 1) << SUB_BUCKET_HALF_COUNT_MAGNITUDE) + sub_bucket
+        - SUB_BUCKET_HALF_COUNT) as usize
+}
+
+/// 下标对应区间内的最大值
+fn highest_equivalent_value(index: usize) -> u64 {
+    let index = index as u64;
+    let mut bucket = (index >> SUB_BUCKET_HALF_COUNT_MAGNITUDE) as i64 - 1;
+    let mut sub_bucket = (index & (SUB_BUCKET_HALF_COUNT - 1)) + SUB_BUCKET_HALF_COUNT;
+    if bucket < 0 {
+        sub_bucket -= SUB_BUCKET_HALF_COUNT;
+        bucket = 0;
+    }
+    (sub_bucket << bucket) + (1 << bucket) - 1
+}
+
+impl HdrHistogram {
+    pub(crate) fn record(&mut self, d: Duration) {
+        let us = d.as_micros() as u64;
+        let index = index_of(us);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+        self.max = self.max.max(us);
+        self.sum += us as f64;
+        self.sum_sq += (us as f64).powi(2);
+    }
+
+    /// 返回 (取值 µs, 不超过该值的样本数)
+    fn value_at_percentile(&self, perc: f64) -> (u64, u64) {
+        // 减去一个极小量再取整，避免 99.9% × 2000 = 1998.0000000000002 被取成 1999
+        let rank = perc / 100.0 * self.total as f64;
+        let target = ((rank - 1e-9).ceil() as u64).clamp(1, self.total);
+        let mut cumulative = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= target {
+                return (highest_equivalent_value(index).min(self.max), cumulative);
+            }
+        }
+        (self.max, self.total)
+    }
+
+    /// 百分位对应的取值，单位 ms；没有样本时为 None
+    pub(crate) fn percentile_ms(&self, perc: f64) -> Option<f64> {
+        (self.total > 0).then(|| self.value_at_percentile(perc).0 as f64 / 1000.0)
+    }
+
+    /// 以 HdrHistogram 的 .hgrm 百分位分布格式输出，数值单位为 ms，
+    /// 可直接用 HdrHistogram 的 plotFiles 页面绘图
+    pub(crate) fn write_hgrm(&self, w: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            w,
+            "{:>12} {:>14} {:>10} {:>14}\n",
+            "Value", "Percentile", "TotalCount", "1/(1-Percentile)"
+        )?;
+        if self.total == 0 {
+            return Ok(());
+        }
+        let mut level = 0.0_f64;
+        loop {
+            let (value, count) = self.value_at_percentile(level);
+            if count >= self.total {
+                break;
+            }
+            writeln!(
+                w,
+                "{:12.3} {:2.12} {:10} {:14.2}",
+                value as f64 / 1000.0,
+                level / 100.0,
+                count,
+                1.0 / (1.0 - level / 100.0)
+            )?;
+            // 与 HdrHistogram 相同：离 100% 越近，步长按剩余距离减半
+            let half_distance = 2_f64.powi((100.0 / (100.0 - level)).log2().floor() as i32 + 1);
+            level += 100.0 / (f64::from(TICKS_PER_HALF_DISTANCE) * half_distance);
+        }
+        writeln!(
+            w,
+            "{:12.3} {:2.12} {:10}",
+            self.max as f64 / 1000.0,
+            1.0,
+            self.total
+        )?;
+
+        let mean = self.sum / self.total as f64;
+        let std_dev = (self.sum_sq / self.total as f64 - mean.powi(2))
+            .max(0.0)
+            .sqrt();
+        writeln!(
+            w,
+            "#[Mean    = {:12.3}, StdDeviation   = {:12.3}]",
+            mean / 1000.0,
+            std_dev / 1000.0
+        )?;
+        writeln!(
+            w,
+            "#[Max     = {:12.3}, Total count    = {:12}]",
+            self.max as f64 / 1000.0,
+            self.total
+        )?;
+        writeln!(
+            w,
+            "#[Buckets = {:12}, SubBuckets     = {:12}]",
+            (self.counts.len() as u64 >> SUB_BUCKET_HALF_COUNT_MAGNITUDE).max(1),
+            SUB_BUCKET_MASK + 1
+        )
+    }
+}
+
+/// 高百分位的取值，单位 ms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HdrPercentiles {
+    pub p999: f64,
+    pub p9999: f64,
+    pub max: f64,
+}
+
+/// --hdr-histogram 记录的 TTFT 与端到端延迟高百分位
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HdrSummary {
+    pub ttft: Option<HdrPercentiles>,
+    pub end_to_end: Option<HdrPercentiles>,
+}
+
+impl HdrSummary {
+    pub(crate) fn print(&self) {
+        println!("\n--- HDR Histogram ---");
+        for (name, stats) in [("TTFT", &self.ttft), ("End-to-End", &self.end_to_end)] {
+            if let Some(stats) = stats {
+                println!(
+                    "{}: P99.9 {:.2} ms, P99.99 {:.2} ms, Max {:.2} ms",
+                    name, stats.p999, stats.p9999, stats.max
+                );
+            }
+        }
+    }
+}
+
+/// 随结果收集同步记录的一组直方图
+#[derive(Debug, Default)]
+pub(crate) struct HdrRecorder {
+    pub(crate) ttft: HdrHistogram,
+    pub(crate) total: HdrHistogram,
+}
+
+impl HdrRecorder {
+    pub(crate) fn record(&mut self, r: &LatencyResult) {
+        self.ttft.record(r.ttft);
+        self.total.record(r.total);
+    }
+
+    pub(crate) fn summary(&self) -> HdrSummary {
+        let percentiles = |h: &HdrHistogram| {
+            Some(HdrPercentiles {
+                p999: h.percentile_ms(99.9)?,
+                p9999: h.percentile_ms(99.99)?,
+                max: h.percentile_ms(100.0)?,
+            })
+        };
+        HdrSummary {
+            ttft: percentiles(&self.ttft),
+            end_to_end: percentiles(&self.total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 固定种子的线性同余序列，跨越 1µs 到约 17 分钟的多个数量级
+    fn samples(n: usize) -> Vec<u64> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                let magnitude = (state >> 59) as u32 % 30;
+                1 + (state >> 20) % (1 << magnitude)
+            })
+            .collect()
+    }
+
+    fn histogram(values: &[u64]) -> HdrHistogram {
+        let mut h = HdrHistogram::default();
+        for &us in values {
+            h.record(Duration::from_micros(us));
+        }
+        h
+    }
+
+    fn percentile_us(h: &HdrHistogram, perc: f64) -> u64 {
+        (h.percentile_ms(perc).unwrap() * 1000.0).round() as u64
+    }
+
+    #[test]
+    fn percentiles_within_configured_precision() {
+        let values = samples(20_000);
+        let h = histogram(&values);
+        let mut sorted = values.clone();
+        sorted.sort_unstable();
+        // 以万分之一为单位，精确值按整数运算取最近秩
+        for basis_points in [100, 1000, 5000, 9000, 9900, 9990, 9999] {
+            let rank = (basis_points * sorted.len()).div_ceil(10_000);
+            let exact = sorted[rank - 1];
+            let perc = basis_points as f64 / 100.0;
+            let value = percentile_us(&h, perc);
+            // 取区间上界：不小于精确值，且相对误差不超过 1/1024（3 位有效数字）
+            assert!(value >= exact, "p{perc}: {value} < {exact}");
+            assert!(value - exact <= exact / 1024, "p{perc}: {value} vs {exact}");
+        }
+    }
+
+    #[test]
+    fn small_values_are_exact() {
+        let values: Vec<u64> = (1..=2000).collect();
+        let h = histogram(&values);
+        assert_eq!(percentile_us(&h, 50.0), 1000);
+        assert_eq!(percentile_us(&h, 99.9), 1998);
+    }
+
+    #[test]
+    fn tracks_min_and_max() {
+        let h = histogram(&[1_234_567, 5_000, 987_654_321]);
+        assert_eq!(percentile_us(&h, 100.0), 987_654_321);
+        let min = percentile_us(&h, 0.0);
+        assert!((5_000..=5_000 + 5_000 / 1024).contains(&min), "{min}");
+
+        let mut out = Vec::new();
+        h.write_hgrm(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("#[Max     =   987654.321, Total count    =            3]"));
+    }
+
+    #[test]
+    fn empty_histogram() {
+        let h = HdrHistogram::default();
+        assert_eq!(h.percentile_ms(50.0), None);
+        assert_eq!(h.percentile_ms(100.0), None);
+        let mut out = Vec::new();
+        h.write_hgrm(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+
+        let summary = HdrRecorder::default().summary();
+        assert!(summary.ttft.is_none() && summary.end_to_end.is_none());
+    }
+}
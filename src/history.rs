@@ -0,0 +1,121 @@
+use crate::BoxError;
+use crate::baseline::p99;
+use crate::config::BenchmarkConfig;
+use crate::stats::BenchmarkResult;
+use crate::util::format_utc;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// 历史记录中的一次运行，每次运行在历史文件中追加一行 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// 开始时间（Unix 时间戳，秒）
+    pub timestamp: f64,
+    pub url: String,
+    pub concurrency: usize,
+    /// 完成的请求数（含失败）
+    pub requests: usize,
+    pub mean_ttft_ms: Option<f64>,
+    pub p99_ttft_ms: Option<f64>,
+    pub mean_total_ms: Option<f64>,
+    pub p99_total_ms: Option<f64>,
+    pub requests_per_sec: f64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl HistoryRecord {
+    pub fn new(config: &BenchmarkConfig, result: &BenchmarkResult) -> Self {
+        let ttft = result.results.iter().map(|r| r.ttft).collect();
+        let total = result.results.iter().map(|r| r.total).collect();
+        Self {
+            timestamp: result.started_at,
+            url: config.url.clone(),
+            concurrency: config.concurrency,
+            requests: result.total,
+            mean_ttft_ms: result.ttft.as_ref().map(|s| s.avg),
+            p99_ttft_ms: p99(result.ttft.as_ref(), ttft),
+            mean_total_ms: result.end_to_end.as_ref().map(|s| s.avg),
+            p99_total_ms: p99(result.end_to_end.as_ref(), total),
+            requests_per_sec: result.requests_per_sec,
+            tags: config.tags.clone(),
+        }
+    }
+}
+
+/// 将一次运行追加到历史文件（JSON Lines，每行一条记录），文件不存在时创建
+pub fn append_history(path: &Path, record: &HistoryRecord) -> Result<(), BoxError> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// 按时间顺序返回最近 last 次运行，指定 tag 时只保留带该标签的记录
+pub fn load_history(
+    path: &Path,
+    tag: Option<&str>,
+    last: usize,
+) -> Result<Vec<HistoryRecord>, BoxError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read history {}: {e}", path.display()))?;
+    let mut records = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: HistoryRecord =
+            serde_json::from_str(line).map_err(|e| format!("{}:{}: {e}", path.display(), i + 1))?;
+        if tag.is_none_or(|tag| record.tags.iter().any(|t| t == tag)) {
+            records.push(record);
+        }
+    }
+    let skip = records.len().saturating_sub(last);
+    records.drain(..skip);
+    Ok(records)
+}
+
+/// 以表格打印历史记录
+pub fn print_history(records: &[HistoryRecord]) {
+    if records.is_empty() {
+        println!("No runs recorded");
+        return;
+    }
+    let ms = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.2}", v));
+    let tags: Vec<String> = records
+        .iter()
+        .map(|r| {
+            if r.tags.is_empty() {
+                "-".to_string()
+            } else {
+                r.tags.join(",")
+            }
+        })
+        .collect();
+    let width = tags
+        .iter()
+        .map(|t| t.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:<20} {:<width$} {:>6} {:>8} {:>10} {:>10} {:>10} {:>10} {:>9}  URL",
+        "Time", "Tags", "Conc", "Requests", "TTFT avg", "TTFT P99", "E2E avg", "E2E P99", "RPS"
+    );
+    for (r, tags) in records.iter().zip(&tags) {
+        println!(
+            "{:<20} {:<width$} {:>6} {:>8} {:>10} {:>10} {:>10} {:>10} {:>9.2}  {}",
+            format_utc(r.timestamp),
+            tags,
+            r.concurrency,
+            r.requests,
+            ms(r.mean_ttft_ms),
+            ms(r.p99_ttft_ms),
+            ms(r.mean_total_ms),
+            ms(r.p99_total_ms),
+            r.requests_per_sec,
+            r.url
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::BoxError;
+use crate::baseline::p99;
+use crate::config::BenchmarkConfig;
+use crate::stats::BenchmarkResult;
+use crate::util::random_u64;
+use reqwest::{Client, Url};
+use std::fmt::Write;
+
+/// 逐请求数据的 measurement
+const MEASUREMENT: &str = "llm_benchmark";
+/// 汇总数据的 measurement
+const SUMMARY_MEASUREMENT: &str = "llm_benchmark_summary";
+
+/// tag 值中的逗号、等号和空格需要反斜杠转义
+fn escape_tag(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Unix 时间戳（秒）转为纳秒精度的时间戳
+fn nanos(ts: f64) -> i64 {
+    (ts * 1e9) as i64
+}
+
+/// 将结果转为 InfluxDB line protocol：每个成功请求一行 `llm_benchmark`，
+/// 最后一行 `llm_benchmark_summary`；tag 为 url、concurrency 和随机生成的 run_id，
+/// 同一次运行的所有行共享一个 run_id。
+///
+/// `--streaming-quantiles` 下没有逐请求结果，只输出汇总行。
+pub fn to_line_protocol(config: &BenchmarkConfig, result: &BenchmarkResult) -> String {
+    let tags = format!(
+        "url={},concurrency={},run_id={:016x}",
+        escape_tag(&config.url),
+        config.concurrency,
+        random_u64()
+    );
+    let mut out = String::new();
+    for r in &result.results {
+        let _ = writeln!(
+            out,
+            "{MEASUREMENT},{tags} ttft_ms={:.3},total_ms={:.3},worker_id={}i,tokens={}i {}",
+            r.ttft.as_secs_f64() * 1000.0,
+            r.total.as_secs_f64() * 1000.0,
+            r.worker_id,
+            r.tokens,
+            nanos(r.start_ts)
+        );
+    }
+
+    let mut fields = vec![
+        format!("requests={}i", result.total),
+        format!("success={}i", result.success),
+        format!("errors={}i", result.errors),
+        format!("requests_per_sec={:.3}", result.requests_per_sec),
+        format!("total_time_secs={:.3}", result.total_time_secs),
+    ];
+    let ttft = result.results.iter().map(|r| r.ttft).collect();
+    let total = result.results.iter().map(|r| r.total).collect();
+    let latencies = [
+        (
+            "ttft",
+            result.ttft.as_ref(),
+            p99(result.ttft.as_ref(), ttft),
+        ),
+        (
+            "total",
+            result.end_to_end.as_ref(),
+            p99(result.end_to_end.as_ref(), total),
+        ),
+    ];
+    for (name, stats, p99) in latencies {
+        if let Some(stats) = stats {
+            fields.push(format!("mean_{name}_ms={:.3}", stats.avg));
+        }
+        if let Some(p99) = p99 {
+            fields.push(format!("p99_{name}_ms={:.3}", p99));
+        }
+    }
+    if let Some(throughput) = &result.throughput {
+        fields.push(format!("tokens_per_sec={:.3}", throughput.avg));
+    }
+    let _ = writeln!(
+        out,
+        "{SUMMARY_MEASUREMENT},{tags} {} {}",
+        fields.join(","),
+        nanos(result.started_at)
+    );
+    out
+}
+
+/// 通过 InfluxDB v2 的 /api/v2/write 接口写入 line protocol 数据
+pub async fn push_line_protocol(
+    host: &str,
+    token: Option<&str>,
+    org: Option<&str>,
+    bucket: &str,
+    lines: String,
+) -> Result<(), BoxError> {
+    let mut endpoint = Url::parse(&format!("{}/api/v2/write", host.trim_end_matches('/')))?;
+    {
+        let mut query = endpoint.query_pairs_mut();
+        query
+            .append_pair("bucket", bucket)
+            .append_pair("precision", "ns");
+        if let Some(org) = org {
+            query.append_pair("org", org);
+        }
+    }
+    let mut request = Client::new().post(endpoint).body(lines);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Token {token}"));
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("InfluxDB write failed ({status}): {}", body.trim()).into());
+    }
+    Ok(())
+}
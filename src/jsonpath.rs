@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// `.name` 或 `['name']`
+    Key(String),
+    /// `[N]`，负数从末尾计数
+    Index(i64),
+    /// `.*` 或 `[*]`
+    Wildcard,
+    /// `..name`（任意深度的同名字段），`..*` 时为 None
+    Descendant(Option<String>),
+}
+
+/// JSONPath 表达式，支持 `$`、`.name`、`['name']`、`[N]`、`[*]` / `.*` 和 `..name`
+///
+/// ```
+/// use interface_performance_test::JsonPath;
+/// use serde_json::json;
+///
+/// let value = json!({"choices": [{"index": 0}, {"index": 1}], "usage": {"total_tokens": 12}});
+/// let path = JsonPath::parse("$.usage.total_tokens").unwrap();
+/// assert_eq!(path.select(&value), [&json!(12)]);
+/// let path = JsonPath::parse("$.choices[*].index").unwrap();
+/// assert_eq!(path.select(&value), [&json!(0), &json!(1)]);
+/// assert!(JsonPath::parse("usage").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct JsonPath {
+    source: String,
+    steps: Vec<Step>,
+}
+
+/// 序列化为表达式原文
+impl From<JsonPath> for String {
+    fn from(path: JsonPath) -> Self {
+        path.source
+    }
+}
+
+impl TryFrom<String> for JsonPath {
+    type Error = String;
+
+    fn try_from(path: String) -> Result<Self, String> {
+        JsonPath::parse(&path)
+    }
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid JSONPath {path:?}: {reason}");
+        let mut rest = path
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| invalid("must start with $"))?;
+        let mut steps = Vec::new();
+        // 字段名到下一个 `.` 或 `[` 为止
+        let name_len = |s: &str| s.find(['.', '[']).unwrap_or(s.len());
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let len = name_len(after);
+                let name = &after[..len];
+                if name.is_empty() {
+                    return Err(invalid("expected a field name after .."));
+                }
+                steps.push(Step::Descendant((name != "*").then(|| name.to_string())));
+                rest = &after[len..];
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let len = name_len(after);
+                steps.push(match &after[..len] {
+                    "" => return Err(invalid("expected a field name after .")),
+                    "*" => Step::Wildcard,
+                    name => Step::Key(name.to_string()),
+                });
+                rest = &after[len..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed ["))?;
+                let inner = after[..end].trim();
+                let quoted = ['\'', '"']
+                    .iter()
+                    .find_map(|&q| inner.strip_prefix(q).and_then(|s| s.strip_suffix(q)));
+                steps.push(match (inner, quoted) {
+                    (_, Some(name)) => Step::Key(name.to_string()),
+                    ("*", None) => Step::Wildcard,
+                    (index, None) => Step::Index(
+                        index
+                            .parse()
+                            .map_err(|_| invalid(&format!("invalid index [{index}]")))?,
+                    ),
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid(&format!("unexpected {rest:?}")));
+            }
+        }
+        Ok(Self {
+            source: path.trim().to_string(),
+            steps,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 返回所有匹配的值，按文档顺序排列
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for value in current {
+                match step {
+                    Step::Key(name) => next.extend(value.get(name)),
+                    Step::Index(index) => {
+                        let items = value.as_array().map_or(&[][..], Vec::as_slice);
+                        let index = if *index < 0 {
+                            items.len().checked_sub(index.unsigned_abs() as usize)
+                        } else {
+                            Some(*index as usize)
+                        };
+                        next.extend(index.and_then(|i| items.get(i)));
+                    }
+                    Step::Wildcard => next.extend(children(value)),
+                    Step::Descendant(name) => descendants(value, name.as_deref(), &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// 从响应体中取第一个匹配的值：整段 JSON 直接匹配，
+    /// 否则按 SSE `data:` 事件取最后一个有匹配的事件（如只在最后一个事件中出现的 usage）
+    pub(crate) fn extract(&self, body: &[u8]) -> Option<Value> {
+        let text = String::from_utf8_lossy(body);
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            return self.select(&value).first().map(|v| (*v).clone());
+        }
+        text.lines()
+            .rev()
+            .filter_map(|line| line.trim().strip_prefix("data:"))
+            .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+            .find_map(|value| self.select(&value).first().map(|v| (*v).clone()))
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Object(object) => object.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 深度优先收集 value 及其所有后代中名为 name 的字段（name 为 None 时收集所有后代）
+fn descendants<'a>(value: &'a Value, name: Option<&str>, out: &mut Vec<&'a Value>) {
+    match name {
+        Some(name) => out.extend(value.get(name)),
+        None => out.extend(children(value)),
+    }
+    for child in children(value) {
+        descendants(child, name, out);
+    }
+}
@@ -0,0 +1,114 @@
+//! 面向 LLM 推理服务的 HTTP 压测库，`concurrency_testing` 命令行工具即基于此实现。
+//!
+//! 测量每个请求的 TTFT（首个 chunk 或 SSE 事件到达的时间）、端到端延迟和 token 间隔，
+//! 汇总为 [`BenchmarkResult`]。配置可直接填写 [`BenchmarkConfig`]，
+//! 也可以用 [`BenchmarkBuilder`] 链式构造：
+//!
+//! ```no_run
+//! use interface_performance_test::{BenchmarkBuilder, BenchmarkRunner};
+//!
+//! # async fn demo() -> Result<(), interface_performance_test::BoxError> {
+//! let config = BenchmarkBuilder::new("http://127.0.0.1:8080/v1/chat/completions")
+//!     .body(r#"{"model": "m", "stream": true}"#)
+//!     .requests(100)
+//!     .sse(true)
+//!     .build()?;
+//!
+//! let result = BenchmarkRunner::run(config).await?;
+//! assert_eq!(result.errors, 0);
+//! println!("{}", serde_json::to_string_pretty(&result)?);
+//! # Ok(())
+//! # }
+//! ```
+
+/// 提示信息：按 `config.log` 输出到 stdout、stderr 或不输出
+macro_rules! info {
+    ($config:expr, $($arg:tt)*) => {
+        match $config.log {
+            $crate::LogTarget::Off => {}
+            $crate::LogTarget::Stdout => println!($($arg)*),
+            $crate::LogTarget::Stderr => eprintln!($($arg)*),
+        }
+    };
+}
+
+mod baseline;
+mod breaker;
+mod builder;
+mod collector;
+mod config;
+mod connect;
+mod cookie;
+mod gzip;
+mod har;
+mod hdr;
+mod history;
+mod influx;
+mod jsonpath;
+mod metrics;
+mod otlp;
+mod progress;
+mod report;
+mod request;
+mod runner;
+mod schema;
+mod soak;
+mod spike;
+mod sse;
+mod stats;
+mod steps;
+mod tdigest;
+mod template;
+#[cfg(feature = "testing")]
+mod testing;
+mod trace;
+mod transport;
+mod tui;
+mod util;
+mod worker;
+
+pub use baseline::{
+    MetricChange, MetricDiff, compare_results, compare_with_baseline, print_baseline_comparison,
+    print_comparison,
+};
+pub use builder::{BenchmarkBuilder, BenchmarkConfigError};
+pub use config::{
+    BenchmarkConfig, DEFAULT_USER_AGENT, IpFamily, LogTarget, MaxErrorsMode, ProxyConfig, SseFormat,
+};
+pub use connect::ConnectSummary;
+pub use cookie::{Cookie, CookieJar, load_cookie_file, parse_cookie};
+pub use har::load_har_bodies;
+pub use hdr::{HdrPercentiles, HdrSummary};
+pub use history::{HistoryRecord, append_history, load_history, print_history};
+pub use influx::{push_line_protocol, to_line_protocol};
+pub use jsonpath::JsonPath;
+pub use report::write_html_report;
+pub use request::{
+    build_header_map, load_body_variations, load_prompt_csv, load_url_list, parse_header,
+    parse_weighted_url, resolve_method,
+};
+pub use runner::BenchmarkRunner;
+pub use schema::JsonSchema;
+pub use soak::{Soak, SoakSummary, SoakWindow};
+pub use spike::{PhaseResult, Spike};
+pub use stats::{
+    BenchmarkResult, ChunkStats, ErrorKind, ExtractStats, LatencyResult, LatencyStats,
+    NumericSummary, QuantileEstimator, RequestOutcome, ServerTiming, ServerTimingStats,
+    SloCompliance, ThroughputStats, TimeBucket, TokenRates, TokenUsage, TransferStats, UrlStats,
+    UsageStats, WorkerStats,
+};
+pub use steps::{
+    AutoConcurrency, AutoConcurrencyResult, AutoStopReason, Breakpoint, BreakpointResult, StepLoad,
+    StepResult, print_step_table,
+};
+pub use template::{BodyTemplate, parse_var};
+#[cfg(feature = "testing")]
+pub use testing::{MockLLMServer, MockLLMServerBuilder, MockTransport, TempFile};
+pub use transport::{
+    BenchmarkRequest, BenchmarkResponse, HttpTransport, Transport, TransportError,
+};
+pub use tui::restore_terminal;
+pub use util::{parse_duration, write_atomic};
+
+/// 库中各操作返回的错误类型
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Prometheus 直方图的桶上界（秒）
+const HISTOGRAM_BUCKETS: [f64; 13] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// 无锁直方图：每个桶独立计数，输出时再累加为 Prometheus 的累计形式
+#[derive(Debug, Default)]
+struct Histogram {
+    /// 最后一个元素对应 +Inf
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS.len() + 1],
+    sum_ns: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, d: Duration) {
+        let secs = d.as_secs_f64();
+        let idx = HISTOGRAM_BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(HISTOGRAM_BUCKETS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns
+            .fetch_add(d.as_nanos() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} histogram\n"));
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = HISTOGRAM_BUCKETS
+                .get(i)
+                .map_or("+Inf".to_string(), |le| le.to_string());
+            out.push_str(&format!("{name}_bucket{{le=\"{le}\"}} {cumulative}\n"));
+        }
+        let sum = self.sum_ns.load(Ordering::Relaxed) as f64 / 1e9;
+        out.push_str(&format!("{name}_sum {sum}\n"));
+        out.push_str(&format!(
+            "{name}_count {}\n",
+            self.count.load(Ordering::Relaxed)
+        ));
+    }
+}
+
+/// 运行期间的累计指标，供 --metrics-port 暴露给 Prometheus
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    success: AtomicU64,
+    errors: AtomicU64,
+    ttft: Histogram,
+    total: Histogram,
+}
+
+impl Metrics {
+    pub(crate) fn record_success(&self, ttft: Duration, total: Duration) {
+        self.success.fetch_add(1, Ordering::Relaxed);
+        self.ttft.observe(ttft);
+        self.total.observe(total);
+    }
+
+    pub(crate) fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Prometheus 文本格式（0.0.4）
+    fn render(&self, elapsed: Duration) -> String {
+        let success = self.success.load(Ordering::Relaxed);
+        let mut out = String::new();
+        out.push_str("# HELP bench_requests_total Completed benchmark requests.\n");
+        out.push_str("# TYPE bench_requests_total counter\n");
+        out.push_str(&format!(
+            "bench_requests_total{{status=\"success\"}} {success}\n"
+        ));
+        out.push_str(&format!(
+            "bench_requests_total{{status=\"error\"}} {}\n",
+            self.errors.load(Ordering::Relaxed)
+        ));
+        self.ttft
+            .render("bench_ttft_seconds", "Time to first token.", &mut out);
+        self.total.render(
+            "bench_total_seconds",
+            "End-to-end request latency.",
+            &mut out,
+        );
+        out.push_str(
+            "# HELP bench_rps Successful requests per second since the benchmark started.\n",
+        );
+        out.push_str("# TYPE bench_rps gauge\n");
+        out.push_str(&format!(
+            "bench_rps {}\n",
+            success as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+        ));
+        out
+    }
+}
+
+/// 在指定端口提供 GET /metrics，测试结束时由主线程 abort
+pub(crate) async fn serve_metrics(
+    listener: tokio::net::TcpListener,
+    metrics: Arc<Metrics>,
+    start: Instant,
+) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    loop {
+        // accept 失败（如文件描述符耗尽）通常会立即再次失败，稍等再重试以免空转
+        let mut socket = match listener.accept().await {
+            Ok((socket, _)) => socket,
+            Err(e) => {
+                eprintln!("⚠️  Metrics endpoint failed to accept a connection: {e}");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let Ok(n) = socket.read(&mut buf).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let response = if path == "/metrics" {
+                let body = metrics.render(start.elapsed());
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+            socket.write_all(response.as_bytes()).await.ok();
+            socket.shutdown().await.ok();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 返回直方图各桶的 (le, 累计值) 以及 _count
+    fn buckets(text: &str, name: &str) -> (Vec<(String, u64)>, u64) {
+        let buckets = text
+            .lines()
+            .filter_map(|line| line.strip_prefix(&format!("{name}_bucket{{le=\"")))
+            .map(|rest| {
+                let (le, count) = rest.split_once("\"} ").unwrap();
+                (le.to_string(), count.parse().unwrap())
+            })
+            .collect();
+        let count = text
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{name}_count ")))
+            .unwrap()
+            .parse()
+            .unwrap();
+        (buckets, count)
+    }
+
+    #[test]
+    fn renders_cumulative_histograms() {
+        let metrics = Metrics::default();
+        for ms in [1, 3, 7, 40, 40, 300, 90_000] {
+            metrics.record_success(Duration::from_millis(ms), Duration::from_millis(ms * 2));
+        }
+        metrics.record_error();
+        let text = metrics.render(Duration::from_secs(2));
+
+        assert!(text.contains("bench_requests_total{status=\"success\"} 7\n"));
+        assert!(text.contains("bench_requests_total{status=\"error\"} 1\n"));
+        assert!(text.contains("bench_rps 3.5\n"));
+        assert!(text.contains("# TYPE bench_ttft_seconds histogram\n"));
+
+        let (ttft, count) = buckets(&text, "bench_ttft_seconds");
+        assert_eq!(count, 7);
+        assert_eq!(ttft.len(), HISTOGRAM_BUCKETS.len() + 1);
+        assert_eq!(ttft[0], ("0.005".to_string(), 2));
+        assert_eq!(ttft[1], ("0.01".to_string(), 3));
+        assert_eq!(ttft[3], ("0.05".to_string(), 5));
+        assert_eq!(ttft[6], ("0.5".to_string(), 6));
+        assert_eq!(ttft[HISTOGRAM_BUCKETS.len() - 1], ("60".to_string(), 6));
+        assert_eq!(ttft.last().unwrap(), &("+Inf".to_string(), count));
+        assert!(ttft.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(text.contains("bench_ttft_seconds_sum 90.391\n"));
+
+        let (total, count) = buckets(&text, "bench_total_seconds");
+        assert_eq!(total.last().unwrap(), &("+Inf".to_string(), count));
+        assert_eq!(count, 7);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::util::{random_u64, unix_now};
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// 一个请求对应的 span 数据
+#[derive(Debug)]
+pub(crate) struct SpanRecord {
+    pub(crate) span_id: u64,
+    /// Unix 时间戳（秒）
+    pub(crate) start_ts: f64,
+    pub(crate) end_ts: f64,
+    pub(crate) worker_id: usize,
+    pub(crate) status: u16,
+    pub(crate) ttft: Option<Duration>,
+}
+
+/// 通过 OTLP/HTTP（JSON 编码）导出 trace：所有请求 span 挂在同一个 "benchmark" 根 span 下，
+/// 后台任务按批发送
+pub(crate) struct OtlpExporter {
+    sender: mpsc::UnboundedSender<SpanRecord>,
+    pub(crate) trace_id: u128,
+    root_span_id: u64,
+}
+
+/// 导出任务的关闭句柄：通知后台任务补发剩余 span 和根 span，并等待其完成
+pub(crate) struct OtlpShutdown {
+    signal: tokio::sync::oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl OtlpShutdown {
+    pub(crate) async fn shutdown(self) -> Result<(), tokio::task::JoinError> {
+        let _ = self.signal.send(());
+        self.task.await
+    }
+}
+
+impl OtlpExporter {
+    pub(crate) fn start(
+        endpoint: &str,
+        url: &str,
+        concurrency: usize,
+        started_at: f64,
+    ) -> (Arc<Self>, OtlpShutdown) {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<SpanRecord>();
+        let (signal, mut shutdown) = tokio::sync::oneshot::channel::<()>();
+        let exporter = Arc::new(Self {
+            sender,
+            trace_id: (u128::from(random_u64()) << 64) | u128::from(random_u64()),
+            root_span_id: random_u64(),
+        });
+
+        let client = Client::new();
+        let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+        let trace_id = format!("{:032x}", exporter.trace_id);
+        let root_span_id = format!("{:016x}", exporter.root_span_id);
+        let url = url.to_string();
+        let task = tokio::spawn(async move {
+            let mut batch = Vec::new();
+            let mut flush_timer = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    span = receiver.recv() => match span {
+                        Some(span) => {
+                            batch.push(otlp_request_span(&span, &trace_id, &root_span_id));
+                            if batch.len() < 512 {
+                                continue;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = flush_timer.tick() => {}
+                    _ = &mut shutdown => break,
+                }
+                if !batch.is_empty() {
+                    otlp_export(&client, &endpoint, std::mem::take(&mut batch)).await;
+                }
+            }
+
+            // 补发已排队的 span 和根 span
+            while let Ok(span) = receiver.try_recv() {
+                batch.push(otlp_request_span(&span, &trace_id, &root_span_id));
+            }
+            batch.push(serde_json::json!({
+                "traceId": trace_id,
+                "spanId": root_span_id,
+                "name": "benchmark",
+                "kind": 1,
+                "startTimeUnixNano": unix_nanos(started_at),
+                "endTimeUnixNano": unix_nanos(unix_now()),
+                "attributes": [
+                    otlp_attr("url.full", serde_json::json!({ "stringValue": url })),
+                    otlp_attr("benchmark.concurrency", serde_json::json!({ "intValue": concurrency.to_string() })),
+                ],
+            }));
+            otlp_export(&client, &endpoint, batch).await;
+        });
+        (exporter, OtlpShutdown { signal, task })
+    }
+
+    pub(crate) fn record(&self, span: SpanRecord) {
+        let _ = self.sender.send(span);
+    }
+}
+
+fn unix_nanos(ts: f64) -> String {
+    ((ts * 1e9) as u64).to_string()
+}
+
+fn otlp_attr(key: &str, value: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "key": key, "value": value })
+}
+
+fn otlp_request_span(span: &SpanRecord, trace_id: &str, root_span_id: &str) -> serde_json::Value {
+    let mut attributes = vec![
+        otlp_attr(
+            "http.response.status_code",
+            serde_json::json!({ "intValue": span.status.to_string() }),
+        ),
+        otlp_attr(
+            "benchmark.worker_id",
+            serde_json::json!({ "intValue": span.worker_id.to_string() }),
+        ),
+        otlp_attr(
+            "benchmark.total_ms",
+            serde_json::json!({ "doubleValue": (span.end_ts - span.start_ts) * 1000.0 }),
+        ),
+    ];
+    if let Some(ttft) = span.ttft {
+        attributes.push(otlp_attr(
+            "benchmark.ttft_ms",
+            serde_json::json!({ "doubleValue": ttft.as_secs_f64() * 1000.0 }),
+        ));
+    }
+    let success = (200..300).contains(&span.status);
+    serde_json::json!({
+        "traceId": trace_id,
+        "spanId": format!("{:016x}", span.span_id),
+        "parentSpanId": root_span_id,
+        "name": "request",
+        "kind": 3,
+        "startTimeUnixNano": unix_nanos(span.start_ts),
+        "endTimeUnixNano": unix_nanos(span.end_ts),
+        "attributes": attributes,
+        "status": { "code": if success { 1 } else { 2 } },
+    })
+}
+
+/// 发送一批 span；导出失败只打印警告，不影响压测
+async fn otlp_export(client: &Client, endpoint: &str, spans: Vec<serde_json::Value>) {
+    let payload = serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    otlp_attr("service.name", serde_json::json!({ "stringValue": env!("CARGO_PKG_NAME") })),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "concurrency_testing" },
+                "spans": spans,
+            }],
+        }],
+    });
+    let res = client
+        .post(endpoint)
+        .timeout(Duration::from_secs(10))
+        .json(&payload)
+        .send()
+        .await;
+    match res {
+        Ok(resp) if !resp.status().is_success() => {
+            eprintln!("⚠️  OTLP export failed: HTTP {}", resp.status())
+        }
+        Err(e) => eprintln!("⚠️  OTLP export failed: {}", e),
+        Ok(_) => {}
+    }
+}
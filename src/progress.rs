@@ -0,0 +1,219 @@
+use crate::config::{BenchmarkConfig, LogTarget};
+use crate::stats::{LatencyResult, LatencyStats};
+use crate::util::terminal_size;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// 运行中的进度计数，worker 每发送一个结果更新一次
+#[derive(Debug, Default)]
+pub(crate) struct Progress {
+    completed: AtomicUsize,
+    ttft_us_sum: AtomicU64,
+    pub(crate) done: AtomicBool,
+}
+
+impl Progress {
+    pub(crate) fn record(&self, ttft: Duration) {
+        self.ttft_us_sum
+            .fetch_add(ttft.as_micros() as u64, Ordering::Relaxed);
+        self.completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn completed(&self) -> usize {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    /// 当前进度比例，同时指定请求数和时长时取较大者
+    pub(crate) fn fraction(
+        &self,
+        limit: Option<usize>,
+        duration: Option<Duration>,
+        elapsed: Duration,
+    ) -> f64 {
+        let completed = self.completed.load(Ordering::Relaxed);
+        let by_requests = limit.map_or(0.0, |n| completed as f64 / n.max(1) as f64);
+        let by_time = duration.map_or(0.0, |d| elapsed.as_secs_f64() / d.as_secs_f64());
+        by_requests.max(by_time).min(1.0)
+    }
+
+    fn render(
+        &self,
+        limit: Option<usize>,
+        duration: Option<Duration>,
+        elapsed: Duration,
+    ) -> String {
+        const WIDTH: usize = 30;
+        let completed = self.completed.load(Ordering::Relaxed);
+        let filled = (self.fraction(limit, duration, elapsed) * WIDTH as f64) as usize;
+        let bar = match filled {
+            0 => " ".repeat(WIDTH),
+            n if n >= WIDTH => "=".repeat(WIDTH),
+            n => format!("{}>{}", "=".repeat(n - 1), " ".repeat(WIDTH - n)),
+        };
+        let count = match limit {
+            Some(n) => format!("{}/{}", completed, n),
+            None => completed.to_string(),
+        };
+        let avg_ttft = match completed {
+            0 => 0.0,
+            n => self.ttft_us_sum.load(Ordering::Relaxed) as f64 / n as f64 / 1000.0,
+        };
+        let rps = completed as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        format!(
+            "[{}] {} requests  avg_ttft={:.0}ms rps={:.1}",
+            bar, count, avg_ttft, rps
+        )
+    }
+}
+
+/// 后台刷新进度：终端下每 100ms 原地重绘一行，否则每完成 10% 打印一行；
+/// `done` 置位后清除进度条并退出
+pub(crate) fn spawn_progress(
+    progress: Arc<Progress>,
+    limit: Option<usize>,
+    duration: Option<Duration>,
+    start: Instant,
+    target: LogTarget,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let is_tty = target.is_terminal();
+        let mut out: Box<dyn Write + Send> = match target {
+            LogTarget::Stderr => Box::new(std::io::stderr()),
+            _ => Box::new(std::io::stdout()),
+        };
+
+        let mut interval = tokio::time::interval(Duration::from_millis(100));
+        let mut last_step = 0;
+        while !progress.done.load(Ordering::Relaxed) {
+            interval.tick().await;
+            let elapsed = start.elapsed();
+            if is_tty {
+                write!(
+                    out,
+                    "\r\x1b[2K{}",
+                    progress.render(limit, duration, elapsed)
+                )
+                .ok();
+                out.flush().ok();
+            } else {
+                let step = (progress.fraction(limit, duration, elapsed) * 10.0) as usize;
+                if step > last_step {
+                    last_step = step;
+                    writeln!(out, "{}", progress.render(limit, duration, elapsed)).ok();
+                }
+            }
+        }
+        if is_tty {
+            write!(out, "\r\x1b[2K").ok();
+            out.flush().ok();
+        }
+    })
+}
+
+/// 由低到高的 8 级方块字符
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// 按观测到的最小、最大值缩放成一行方块字符
+pub(crate) fn sparkline(samples: &VecDeque<f64>) -> String {
+    let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    samples
+        .iter()
+        .map(|&v| {
+            let level = if max > min {
+                ((v - min) / (max - min) * (SPARK_LEVELS.len() - 1) as f64).round() as usize
+            } else {
+                SPARK_LEVELS.len() / 2
+            };
+            SPARK_LEVELS[level]
+        })
+        .collect()
+}
+
+/// --plot：每秒采样一次完成数，在 stderr 上原地重绘 RPS 走势，历史长度不超过终端宽度。
+/// 代替进度条使用，只在 stderr 为终端时启用
+pub(crate) fn spawn_sparkline(progress: Arc<Progress>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        const LABEL: &str = "RPS: ";
+        // 保留当前值的显示空间
+        let (columns, _) = terminal_size();
+        let capacity = columns.saturating_sub(LABEL.len() + 16).max(10);
+
+        let mut samples = VecDeque::with_capacity(capacity);
+        let mut last = 0;
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        interval.tick().await;
+        let mut out = std::io::stderr();
+        while !progress.done.load(Ordering::Relaxed) {
+            // 分段等待，测试结束后及时退出
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tokio::time::sleep(Duration::from_millis(100)) => continue,
+            }
+            let completed = progress.completed.load(Ordering::Relaxed);
+            let rps = (completed - last) as f64;
+            last = completed;
+            if samples.len() == capacity {
+                samples.pop_front();
+            }
+            samples.push_back(rps);
+            write!(
+                out,
+                "\r\x1b[2K{}{} {:.1}/s",
+                LABEL,
+                sparkline(&samples),
+                rps
+            )
+            .ok();
+            out.flush().ok();
+        }
+        write!(out, "\r\x1b[2K").ok();
+        out.flush().ok();
+    })
+}
+
+/// --report-interval 的中间统计，每次基于已收到的全部结果重新计算
+pub(crate) fn print_snapshot(
+    config: &BenchmarkConfig,
+    results: &[LatencyResult],
+    elapsed: Duration,
+) {
+    let line = |samples: Vec<Duration>| match LatencyStats::from_samples(
+        samples.into_iter(),
+        &[95.0, 99.0],
+    ) {
+        Some(s) => format!(
+            "avg={:.2}ms p95={:.2}ms p99={:.2}ms",
+            s.avg, s.percentiles[0].1, s.percentiles[1].1
+        ),
+        None => "n/a".to_string(),
+    };
+    let rps = results.len() as f64 / elapsed.as_secs_f64();
+
+    // 终端下先清掉同一行上的进度条
+    let clear = if config.log.is_terminal() {
+        "\r\x1b[2K"
+    } else {
+        ""
+    };
+    info!(
+        config,
+        "{}--- Progress (t={}s) ---",
+        clear,
+        elapsed.as_secs()
+    );
+    info!(
+        config,
+        "TTFT:  {}",
+        line(results.iter().map(|r| r.ttft).collect())
+    );
+    info!(
+        config,
+        "Total: {}",
+        line(results.iter().map(|r| r.total).collect())
+    );
+    info!(config, "RPS:   {:.2}", rps);
+}
@@ -0,0 +1,166 @@
+use crate::config::BenchmarkConfig;
+use crate::stats::{BenchmarkResult, LatencyResult, LatencyStats, percentile_label};
+use crate::util::format_utc;
+use std::path::Path;
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// TTFT 各百分位的柱状图
+fn svg_percentile_bars(ttft: &LatencyStats) -> String {
+    const W: f64 = 600.0;
+    const H: f64 = 240.0;
+    const PAD: f64 = 40.0;
+    let max = ttft.max.max(f64::EPSILON);
+    let slot = (W - PAD * 2.0) / ttft.percentiles.len().max(1) as f64;
+
+    let mut svg = format!(r#"<svg width="{W}" height="{H}" xmlns="http://www.w3.org/2000/svg">"#);
+    for (i, &(perc, value)) in ttft.percentiles.iter().enumerate() {
+        let h = value / max * (H - PAD * 2.0);
+        let x = PAD + slot * i as f64 + slot * 0.15;
+        let y = H - PAD - h;
+        svg += &format!(
+            r##"<rect x="{x:.1}" y="{y:.1}" width="{:.1}" height="{h:.1}" fill="#4e79a7"/><text x="{:.1}" y="{:.1}" font-size="12" text-anchor="middle">{:.1} ms</text><text x="{:.1}" y="{:.1}" font-size="12" text-anchor="middle">{}</text>"##,
+            slot * 0.7,
+            x + slot * 0.35,
+            y - 4.0,
+            value,
+            x + slot * 0.35,
+            H - PAD + 16.0,
+            percentile_label(perc)
+        );
+    }
+    svg += "</svg>";
+    svg
+}
+
+/// 每个请求的端到端延迟随时间分布的散点图，横轴为相对测试开始的秒数
+fn svg_latency_scatter(results: &[LatencyResult]) -> String {
+    const W: f64 = 600.0;
+    const H: f64 = 280.0;
+    const PAD: f64 = 40.0;
+    let t0 = results
+        .iter()
+        .map(|r| r.start_ts)
+        .fold(f64::INFINITY, f64::min);
+    let t_max = results
+        .iter()
+        .map(|r| r.start_ts - t0)
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+    let y_max = results
+        .iter()
+        .map(|r| r.total.as_secs_f64() * 1000.0)
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+
+    let mut svg = format!(r#"<svg width="{W}" height="{H}" xmlns="http://www.w3.org/2000/svg">"#);
+    svg += &format!(
+        r##"<line x1="{PAD}" y1="{0}" x2="{1}" y2="{0}" stroke="#999"/><line x1="{PAD}" y1="{PAD}" x2="{PAD}" y2="{0}" stroke="#999"/>"##,
+        H - PAD,
+        W - PAD
+    );
+    svg += &format!(
+        r#"<text x="{PAD}" y="{:.1}" font-size="12">{:.1} ms</text><text x="{:.1}" y="{:.1}" font-size="12" text-anchor="end">{:.1} s</text>"#,
+        PAD - 8.0,
+        y_max,
+        W - PAD,
+        H - PAD + 16.0,
+        t_max
+    );
+    for r in results {
+        let x = PAD + (r.start_ts - t0) / t_max * (W - PAD * 2.0);
+        let y = H - PAD - r.total.as_secs_f64() * 1000.0 / y_max * (H - PAD * 2.0);
+        svg += &format!(
+            r##"<circle cx="{x:.1}" cy="{y:.1}" r="2" fill="#e15759" fill-opacity="0.6"/>"##
+        );
+    }
+    svg += "</svg>";
+    svg
+}
+
+/// 生成单文件 HTML 报告：测试参数、统计表、TTFT 百分位柱状图和延迟散点图，不依赖外部资源
+pub fn write_html_report(
+    path: &Path,
+    config: &BenchmarkConfig,
+    summary: &BenchmarkResult,
+) -> std::io::Result<()> {
+    let mut rows = String::new();
+    let mut row = |name: &str, value: String| {
+        rows += &format!("<tr><td>{}</td><td>{}</td></tr>", html_escape(name), value);
+    };
+    row("Total", summary.total.to_string());
+    row("Success", summary.success.to_string());
+    row("Failed", summary.errors.to_string());
+    row("Total time", format!("{:.2} s", summary.total_time_secs));
+    row("Requests/sec", format!("{:.2}", summary.requests_per_sec));
+    for (title, stats) in [
+        ("TTFT", &summary.ttft),
+        ("End-to-End", &summary.end_to_end),
+        ("Inter-Token Latency", &summary.itl),
+    ] {
+        let Some(stats) = stats else { continue };
+        row(&format!("{title} avg"), format!("{:.2} ms", stats.avg));
+        for &(perc, value) in &stats.percentiles {
+            row(
+                &format!("{title} {}", percentile_label(perc)),
+                format!("{:.2} ms", value),
+            );
+        }
+        row(&format!("{title} max"), format!("{:.2} ms", stats.max));
+    }
+    if let Some(throughput) = &summary.throughput {
+        row("Throughput avg", format!("{:.2} tokens/s", throughput.avg));
+    }
+
+    let bars = summary
+        .ttft
+        .as_ref()
+        .map(svg_percentile_bars)
+        .unwrap_or_default();
+    let scatter = if summary.results.is_empty() {
+        String::new()
+    } else {
+        svg_latency_scatter(&summary.results)
+    };
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Benchmark report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+td {{ border: 1px solid #ccc; padding: 4px 12px; }}
+</style>
+</head>
+<body>
+<h1>Benchmark report</h1>
+<p>URL: <code>{url}</code><br>
+Method: {method}<br>
+Concurrency: {concurrency}<br>
+Requests: {requests}<br>
+Started at: {started_at}</p>
+<h2>Summary</h2>
+<table>{rows}</table>
+<h2>TTFT percentiles</h2>
+{bars}
+<h2>End-to-end latency over time</h2>
+{scatter}
+</body>
+</html>
+"#,
+        url = html_escape(&config.url),
+        method = html_escape(config.method.as_str()),
+        concurrency = config.concurrency,
+        requests = summary.total,
+        started_at = format_utc(summary.started_at),
+    );
+    std::fs::write(path, html)
+}
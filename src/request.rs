@@ -0,0 +1,226 @@
+use reqwest::Method;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::path::Path;
+
+/// 读取 JSONL 请求体文件，跳过空行，每行须为合法 JSON
+pub fn load_body_variations(path: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "failed to read --body-variation-file {}: {e}",
+            path.display()
+        )
+    })?;
+    let mut bodies = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        serde_json::from_str::<serde_json::Value>(line)
+            .map_err(|e| format!("{}:{}: invalid JSON: {e}", path.display(), i + 1))?;
+        bodies.push(line.to_string());
+    }
+    if bodies.is_empty() {
+        return Err(format!("{} contains no request bodies", path.display()));
+    }
+    Ok(bodies)
+}
+
+/// 读取 --prompt-csv 文件中第 column 列（从 1 开始）的值，跳过表头和空行；
+/// 字段可用双引号包裹，其中 `""` 表示一个双引号，并可包含逗号和换行
+pub fn load_prompt_csv(path: &Path, column: usize) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read --prompt-csv {}: {e}", path.display()))?;
+    if column == 0 {
+        return Err("--prompt-column starts at 1".to_string());
+    }
+    let mut prompts = Vec::new();
+    for (line, row) in parse_csv(&content).map_err(|e| format!("{}: {e}", path.display()))? {
+        if line == 1 || row.iter().all(String::is_empty) {
+            continue;
+        }
+        let value = row
+            .into_iter()
+            .nth(column - 1)
+            .ok_or_else(|| format!("{}:{line}: row has no column {column}", path.display()))?;
+        prompts.push(value);
+    }
+    if prompts.is_empty() {
+        return Err(format!("{} contains no prompts", path.display()));
+    }
+    Ok(prompts)
+}
+
+/// 按 RFC 4180 拆分 CSV，返回每行的起始行号与字段
+fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let (mut line, mut row_line) = (1, 1);
+    let mut chars = text.chars().peekable();
+    let mut in_quotes = false;
+    while let Some(c) = chars.next() {
+        match (in_quotes, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => in_quotes = false,
+            (true, c) => {
+                line += usize::from(c == '\n');
+                field.push(c);
+            }
+            (false, '"') if field.is_empty() => in_quotes = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {row_line}: unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+/// 读取 --url-list 文件，每行一个 URL，跳过空行和 `#` 开头的注释
+pub fn load_url_list(path: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read --url-list {}: {e}", path.display()))?;
+    let mut urls = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        reqwest::Url::parse(line)
+            .map_err(|e| format!("{}:{}: invalid URL: {e}", path.display(), i + 1))?;
+        urls.push(line.to_string());
+    }
+    if urls.is_empty() {
+        return Err(format!("{} contains no URLs", path.display()));
+    }
+    Ok(urls)
+}
+
+/// 将 --method 映射为 reqwest::Method，并检查与 --body 的组合是否合理
+pub fn resolve_method(method: &str, body: Option<&str>) -> Result<Method, String> {
+    let method = match method.to_ascii_uppercase().as_str() {
+        "GET" => Method::GET,
+        "POST" => Method::POST,
+        "PUT" => Method::PUT,
+        "PATCH" => Method::PATCH,
+        "DELETE" => Method::DELETE,
+        other => return Err(format!("unsupported method: {other}")),
+    };
+    match (&method, body) {
+        (&Method::GET, Some(_)) => Err("--body cannot be used with --method GET".to_string()),
+        (&Method::POST | &Method::PUT | &Method::PATCH, None) => {
+            Err(format!("--body is required for --method {method}"))
+        }
+        _ => Ok(method),
+    }
+}
+
+/// 拆分 URL 末尾的 `:权重`，如 "http://canary:8081:10" -> ("http://canary:8081", Some(10))；
+/// 紧跟在主机名后的 `:N` 是端口而不是权重（如 "http://host:8080"）
+pub fn parse_weighted_url(s: &str) -> Result<(String, Option<u32>), String> {
+    let weighted = s.rsplit_once(':').filter(|(rest, weight)| {
+        let authority = rest.split_once("://").map_or(*rest, |(_, r)| r);
+        // IPv6 地址中的冒号在方括号内
+        let after_host = authority.rsplit(']').next().unwrap_or(authority);
+        !weight.is_empty()
+            && weight.bytes().all(|b| b.is_ascii_digit())
+            && after_host.contains([':', '/'])
+    });
+    let Some((url, weight)) = weighted else {
+        return Ok((s.to_string(), None));
+    };
+    let weight: u32 = weight
+        .parse()
+        .map_err(|e| format!("invalid weight in {s:?}: {e}"))?;
+    if weight == 0 {
+        return Err(format!("weight must be greater than 0: {s}"));
+    }
+    Ok((url.to_string(), Some(weight)))
+}
+
+/// 按第一个冒号拆分 "Name: Value" 形式的请求头
+pub fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header (expected \"Name: Value\"): {s}"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("invalid header (empty name): {s}"));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// 将解析出的请求头转换为 HeaderMap，同名请求头保留全部取值
+pub fn build_header_map(headers: &[(String, String)]) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("invalid header name {name:?}: {e}"))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("invalid value for header {name}: {e}"))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_weight_from_url() {
+        let parse = |s: &str| parse_weighted_url(s).unwrap();
+        assert_eq!(
+            parse("http://canary:8081:10"),
+            ("http://canary:8081".to_string(), Some(10))
+        );
+        assert_eq!(parse("http://h/a:3"), ("http://h/a".to_string(), Some(3)));
+        assert_eq!(
+            parse("http://[::1]:8080:2"),
+            ("http://[::1]:8080".to_string(), Some(2))
+        );
+        // 紧跟主机名的是端口
+        assert_eq!(
+            parse("http://host:8080"),
+            ("http://host:8080".to_string(), None)
+        );
+        assert_eq!(
+            parse("http://[::1]:8080"),
+            ("http://[::1]:8080".to_string(), None)
+        );
+        assert_eq!(
+            parse("http://host/path"),
+            ("http://host/path".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_weights() {
+        assert!(
+            parse_weighted_url("http://h/a:0")
+                .unwrap_err()
+                .contains("greater than 0")
+        );
+        assert!(
+            parse_weighted_url("http://h/a:99999999999")
+                .unwrap_err()
+                .contains("invalid weight")
+        );
+    }
+}
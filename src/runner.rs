@@ -0,0 +1,704 @@
+use crate::BoxError;
+use crate::breaker::{CircuitBreaker, CircuitBreakerState};
+use crate::builder::BenchmarkConfigError;
+use crate::collector::ResultCollector;
+use crate::config::{BenchmarkConfig, IpFamily, LogTarget, MaxErrorsMode};
+use crate::connect::ConnectStats;
+use crate::har::HarRecorder;
+use crate::hdr::HdrRecorder;
+use crate::metrics::{Metrics, serve_metrics};
+use crate::otlp::OtlpExporter;
+use crate::progress::{Progress, print_snapshot, spawn_progress, spawn_sparkline};
+use crate::soak::SoakRecorder;
+use crate::spike::{PHASE_POST, PHASE_PRE, PHASE_SPIKE, split_phases};
+use crate::stats::{
+    BenchmarkResult, ErrorKind, ExtractStats, RequestOutcome, SloCompliance, TokenRates, UrlStats,
+};
+use crate::tdigest::StreamingStats;
+use crate::trace::RequestTracer;
+use crate::transport::{BenchmarkRequest, HttpTransport, Transport};
+use crate::tui::{TuiFeed, TuiOptions, spawn_tui};
+use crate::util::{random_u64, unix_now};
+use crate::worker::{ErrorCounter, OutcomeSink, RateLimiter, UrlSchedule, Worker, WorkerShared};
+use futures::StreamExt;
+use reqwest::Url;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, mpsc, watch};
+
+/// --min-connections：以 min(N, concurrency) 的并发发出 N 个 HEAD 请求，使连接池在压测开始前建满；
+/// 服务端不接受 HEAD（405/501）时改用 GET。返回得到响应的请求数，失败只打印警告
+async fn prewarm_connections<T: Transport>(transport: &T, config: &BenchmarkConfig) -> usize {
+    let headers = config.request_headers();
+    let parallel = config.min_connections.min(config.concurrency).max(1);
+    let warm = |i: usize| {
+        let url = config
+            .urls
+            .get(i % config.urls.len().max(1))
+            .unwrap_or(&config.url);
+        let send = |method| {
+            transport.send_request(BenchmarkRequest {
+                method,
+                url: url.clone(),
+                headers: headers.clone(),
+                body: None,
+            })
+        };
+        async move {
+            let resp = match send(reqwest::Method::HEAD).await {
+                Ok(resp) if matches!(resp.status.as_u16(), 405 | 501) => {
+                    send(reqwest::Method::GET).await
+                }
+                res => res,
+            };
+            // 读完响应体，连接才会回到连接池
+            match resp {
+                Ok(resp) => resp.bytes().await.map(|_| ()),
+                Err(e) => Err(e),
+            }
+        }
+    };
+    let results: Vec<_> = futures::stream::iter(0..config.min_connections)
+        .map(warm)
+        .buffer_unordered(parallel)
+        .collect()
+        .await;
+    let mut warmed = 0;
+    for result in results {
+        match result {
+            Ok(()) => warmed += 1,
+            Err(e) => eprintln!("⚠️  Pre-warming request failed: {e}"),
+        }
+    }
+    warmed
+}
+
+/// 解析目标主机（--verbose 时在启动信息中显示），指定协议族时只取该协议族的地址
+async fn resolve_target(url: &str, family: Option<IpFamily>) -> String {
+    let Some((host, port)) = Url::parse(url)
+        .ok()
+        .and_then(|u| Some((u.host_str()?.to_string(), u.port_or_known_default()?)))
+    else {
+        return "n/a".to_string();
+    };
+    match tokio::net::lookup_host((host.trim_matches(['[', ']']), port)).await {
+        Ok(addrs) => {
+            match addrs
+                .map(|a| a.ip())
+                .find(|ip| family.is_none_or(|f| f.matches(*ip)))
+            {
+                Some(ip) => format!("{host} -> {ip}"),
+                None => format!("{host} has no address of the requested IP family"),
+            }
+        }
+        Err(e) => format!("failed to resolve {host}: {e}"),
+    }
+}
+
+/// 压测开始前打印目标、限制条件与连接设置
+async fn print_startup(config: &BenchmarkConfig, request_limit: Option<usize>) {
+    let limit_desc = match (request_limit, config.duration) {
+        (Some(n), _) if config.iterations.is_some() => {
+            format!("requests={} ({} per worker)", n, n / config.concurrency)
+        }
+        (Some(n), Some(d)) => format!("requests={}, duration={:?}", n, d),
+        (Some(n), None) => format!("requests={}", n),
+        (None, Some(d)) => format!("duration={:?}", d),
+        (None, None) => unreachable!(),
+    };
+    info!(
+        config,
+        "Starting benchmark: {} ({}, concurrency={})", config.url, limit_desc, config.concurrency
+    );
+    if !config.tags.is_empty() {
+        info!(config, "Tags: {}", config.tags.join(", "));
+    }
+    if config.connection_per_request {
+        info!(
+            config,
+            "Connection reuse: disabled (new client per request, cold path)"
+        );
+    } else if config.keepalive {
+        info!(
+            config,
+            "Connection pool: keep-alive on, max idle per host={}, idle timeout={:?}",
+            config
+                .pool_max_idle_per_host
+                .map_or("unlimited".to_string(), |n| n.to_string()),
+            config.pool_idle_timeout.unwrap_or(Duration::from_secs(90))
+        );
+    } else {
+        info!(
+            config,
+            "Connection pool: keep-alive off (new connection per request)"
+        );
+    }
+    info!(
+        config,
+        "TCP: nodelay {}, keepalive {}",
+        if config.tcp_nodelay {
+            "on"
+        } else {
+            "off (Nagle's algorithm enabled)"
+        },
+        match (config.keepalive, config.tcp_keepalive) {
+            (false, _) => "off".to_string(),
+            (true, Some(interval)) => format!("every {interval:?}"),
+            (true, None) => "default".to_string(),
+        }
+    );
+    if !config.url_weights.is_empty() {
+        let total: u32 = config.url_weights.iter().sum();
+        let urls: Vec<String> = config
+            .urls
+            .iter()
+            .zip(&config.url_weights)
+            .map(|(url, w)| format!("{} ({:.0}%)", url, *w as f64 / total as f64 * 100.0))
+            .collect();
+        info!(
+            config,
+            "Weighted across {} URLs: {}",
+            urls.len(),
+            urls.join(", ")
+        );
+    } else if config.urls.len() > 1 {
+        info!(
+            config,
+            "Round-robin across {} URLs: {}",
+            config.urls.len(),
+            config.urls.join(", ")
+        );
+    }
+    if let Some(jar) = &config.cookie_jar {
+        info!(
+            config,
+            "Cookies: {} preset, Set-Cookie tracked per worker",
+            jar.len()
+        );
+    }
+    if config.follow_redirects {
+        info!(config, "Redirects: followed (max {})", config.max_redirects);
+    } else {
+        info!(
+            config,
+            "Redirects: not followed (3xx responses count as failures)"
+        );
+    }
+    if config.verbose {
+        let resolved = resolve_target(&config.url, config.ip_family).await;
+        info!(config, "Resolved address: {}", resolved);
+    }
+    if config.print_response {
+        info!(
+            config,
+            "ℹ️  Response of the first successful request will be printed below:\n--- RESPONSE START ---"
+        );
+    }
+}
+
+/// 压测入口
+pub struct BenchmarkRunner;
+
+impl BenchmarkRunner {
+    /// 按配置启动全部 worker，达到请求数或时长限制后返回统计结果
+    pub async fn run(config: BenchmarkConfig) -> Result<BenchmarkResult, BoxError> {
+        let connect_stats = Arc::new(ConnectStats::default());
+        let transport = HttpTransport::with_stats(&config, connect_stats.clone())?;
+        Self::run_inner(config, Arc::new(transport), connect_stats).await
+    }
+
+    /// 同 [`run`](Self::run)，但请求经由 transport 发出；连接相关的配置（连接池、代理等）
+    /// 由 transport 自行处理，结果中没有建连统计
+    pub async fn run_with_transport<T: Transport>(
+        config: BenchmarkConfig,
+        transport: T,
+    ) -> Result<BenchmarkResult, BoxError> {
+        Self::run_inner(config, Arc::new(transport), Arc::default()).await
+    }
+
+    async fn run_inner<T: Transport>(
+        config: BenchmarkConfig,
+        transport: Arc<T>,
+        connect_stats: Arc<ConnectStats>,
+    ) -> Result<BenchmarkResult, BoxError> {
+        let config = Arc::new(config);
+        // 命令行与直接填写的配置不经过 BenchmarkBuilder 的校验
+        if config.concurrency == 0 {
+            return Err(BenchmarkConfigError::ZeroConcurrency.into());
+        }
+
+        let har = config
+            .record_har
+            .as_ref()
+            .map(|_| Arc::new(HarRecorder::default()));
+        let tracer = (config.trace_requests > 0)
+            .then(|| Arc::new(RequestTracer::new(config.trace_requests)));
+        let url_schedule = UrlSchedule::new(config.urls.len(), &config.url_weights);
+        let rate_limiter = match config.rate {
+            Some(rate) => {
+                Some(RateLimiter::new(rate).ok_or(BenchmarkConfigError::InvalidRate(rate))?)
+            }
+            None => None,
+        };
+
+        let request_limit = config.request_limit();
+
+        let (result_sender, mut result_receiver) =
+            mpsc::channel::<RequestOutcome>(request_limit.unwrap_or(config.concurrency).max(1));
+        let stop = Arc::new(AtomicBool::new(false));
+        // 失败过多时由主线程发出取消信号，worker 不再发起新请求，重试等待也立即结束
+        let (cancel_sender, cancel) = watch::channel(false);
+        let breaker: Option<CircuitBreaker> = config
+            .circuit_breaker
+            .then(|| Arc::new(Mutex::new(CircuitBreakerState::new())));
+        let error_counter = Arc::new(ErrorCounter {
+            count: AtomicUsize::new(0),
+            mode: config.max_errors_mode,
+            max: config.max_errors,
+            tripped: Notify::new(),
+        });
+
+        print_startup(&config, request_limit).await;
+
+        // --ramp-up 期间的结果不计入统计，全部 worker 启动后置位
+        let ramped_up = Arc::new(AtomicBool::new(config.ramp_up.is_none()));
+        if let Some(ramp_up) = config.ramp_up {
+            info!(
+                config,
+                "Ramping up to {} workers over {:?}…", config.concurrency, ramp_up
+            );
+        }
+
+        // 突发阶段由主线程在正式计时开始后切换，超出基础并发的 worker 只在突发阶段发起请求
+        let spike_phase = Arc::new(AtomicU8::new(PHASE_PRE));
+        if let Some(spike) = &config.spike {
+            info!(
+                config,
+                "Spike: {} workers, jumping to {} after {:?} for {:?}",
+                spike.baseline_concurrency,
+                config.concurrency,
+                spike.delay,
+                spike.duration
+            );
+        }
+
+        if config.min_connections > 0 {
+            info!(
+                config,
+                "Pre-warming connection pool ({} connections)…", config.min_connections
+            );
+            let warmed = prewarm_connections(&*transport, &config).await;
+            info!(
+                config,
+                "Connection pool ready: {}/{} requests succeeded, {} new connections",
+                warmed,
+                config.min_connections,
+                connect_stats.connections()
+            );
+        }
+
+        // 预热请求与正式请求共用同一个 transport，连接池在预热阶段即被填充
+        let warmup_remaining = Arc::new(AtomicUsize::new(config.warmup));
+        let warmup_done = Arc::new(Notify::new());
+        if config.warmup > 0 {
+            info!(config, "Warming up ({} requests)…", config.warmup);
+        }
+
+        let progress = Arc::new(Progress::default());
+        let metrics = Arc::new(Metrics::default());
+        // 在启动 worker 之前监听，端口被占用时直接报错，不会留下仍在发送请求的 worker
+        let metrics_listener = match config.metrics_port {
+            Some(port) => {
+                let addr = std::net::SocketAddr::new(config.metrics_addr, port);
+                let listener = tokio::net::TcpListener::bind(addr)
+                    .await
+                    .map_err(|e| format!("failed to listen on --metrics-port {addr}: {e}"))?;
+                Some((listener, addr))
+            }
+            None => None,
+        };
+        let mut start = Instant::now();
+        let mut started_at = unix_now();
+
+        let (otlp, otlp_shutdown) = match &config.otlp_endpoint {
+            Some(endpoint) => {
+                let (exporter, shutdown) =
+                    OtlpExporter::start(endpoint, &config.url, config.concurrency, started_at);
+                (Some(exporter), Some(shutdown))
+            }
+            None => (None, None),
+        };
+        // 与 OTLP 导出共用 trace ID，服务端 span 可直接挂到同一条 trace 上
+        let trace_id = config.trace_context.then(|| match &otlp {
+            Some(exporter) => exporter.trace_id,
+            None => (u128::from(random_u64()) << 64) | u128::from(random_u64()),
+        });
+
+        let shared = Arc::new(WorkerShared {
+            config: config.clone(),
+            transport,
+            sink: OutcomeSink {
+                sender: result_sender,
+                progress: progress.clone(),
+                metrics: metrics.clone(),
+                error_counter: error_counter.clone(),
+                breaker,
+                ramped_up: ramped_up.clone(),
+                warmup_remaining,
+                warmup_done: warmup_done.clone(),
+            },
+            headers: config.request_headers(),
+            body_counter: AtomicUsize::new(0),
+            url_schedule,
+            rate_limiter,
+            printed: AtomicBool::new(false),
+            stop: stop.clone(),
+            request_seq: AtomicUsize::new(0),
+            spike_phase: spike_phase.clone(),
+            trace_id,
+            otlp,
+            har: har.clone(),
+            tracer: tracer.clone(),
+        });
+        for worker_id in 0..config.concurrency {
+            if let Some(ramp_up) = config.ramp_up.filter(|_| worker_id > 0) {
+                tokio::time::sleep(ramp_up / config.concurrency as u32).await;
+            }
+            tokio::spawn(Worker::new(worker_id, shared.clone(), cancel.clone()).run());
+        }
+
+        // 只保留 worker 持有的 sender，worker 全部退出后 recv() 返回 None
+        drop(shared);
+
+        if let Some(ramp_up) = config.ramp_up {
+            // 最后一个 worker 启动后再等一个间隔，整个爬坡阶段正好持续 ramp_up
+            tokio::time::sleep(ramp_up / config.concurrency as u32).await;
+            ramped_up.store(true, Ordering::Relaxed);
+            if config.warmup == 0 {
+                info!(config, "Ramp-up complete, benchmark started.");
+                start = Instant::now();
+                started_at = unix_now();
+            }
+        }
+
+        let mut interrupted = false;
+        // 预热期间即到达 --duration 时不再进入正式计时，结果中没有请求
+        let mut warmup_expired = false;
+        if config.warmup > 0 {
+            let warmup_deadline = config.duration.map(|d| tokio::time::Instant::now() + d);
+            let mut shutdown_check = tokio::time::interval(Duration::from_millis(100));
+            loop {
+                let deadline_reached = async {
+                    match warmup_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    _ = warmup_done.notified() => break,
+                    _ = deadline_reached => {
+                        eprintln!("\n⚠️  Warm-up did not finish within --duration");
+                        warmup_expired = true;
+                        break;
+                    }
+                    _ = shutdown_check.tick(), if config.shutdown.is_some() => {
+                        if config.shutdown.as_ref().is_some_and(|s| s.load(Ordering::Relaxed)) {
+                            interrupted = true;
+                            break;
+                        }
+                    }
+                }
+            }
+            if !interrupted && !warmup_expired {
+                info!(config, "Benchmark started.");
+                start = Instant::now();
+                started_at = unix_now();
+            }
+        }
+
+        let metrics_task = metrics_listener.map(|(listener, addr)| {
+            info!(
+                config,
+                "Serving Prometheus metrics on http://{addr}/metrics"
+            );
+            tokio::spawn(serve_metrics(listener, metrics.clone(), start))
+        });
+
+        let spike_task = config.spike.clone().map(|spike| {
+            let config = config.clone();
+            let spike_phase = spike_phase.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(spike.delay).await;
+                spike_phase.store(PHASE_SPIKE, Ordering::Relaxed);
+                info!(config, "\nSpike started: {} workers", config.concurrency);
+                tokio::time::sleep(spike.duration).await;
+                spike_phase.store(PHASE_POST, Ordering::Relaxed);
+                info!(
+                    config,
+                    "\nSpike ended: back to {} workers", spike.baseline_concurrency
+                );
+            })
+        });
+
+        // 全屏界面需要 stdout 是终端，否则退回普通进度条
+        let tui_feed = (config.tui && std::io::stdout().is_terminal())
+            .then(|| Arc::new(Mutex::new(TuiFeed::default())));
+        let progress_task = (config.log != LogTarget::Off).then(|| {
+            if let Some(feed) = &tui_feed {
+                let options = TuiOptions {
+                    url: config.url.clone(),
+                    concurrency: config.concurrency,
+                    limit: request_limit,
+                    duration: config.duration,
+                    percentiles: config.percentiles.clone(),
+                };
+                spawn_tui(progress.clone(), feed.clone(), options, start)
+            } else if config.plot && std::io::stderr().is_terminal() {
+                spawn_sparkline(progress.clone())
+            } else {
+                spawn_progress(
+                    progress.clone(),
+                    request_limit,
+                    config.duration,
+                    start,
+                    config.log,
+                )
+            }
+        });
+
+        // 收集结果
+        let deadline = config
+            .duration
+            .map(|d| tokio::time::Instant::from_std(start + d));
+        let mut results = ResultCollector::new(
+            request_limit.unwrap_or(0),
+            config.raw_output.as_deref(),
+            config.csv_output.as_deref(),
+        )?;
+        results.tags = config.tags.clone();
+        results.hdr = config.hdr_histogram.then(HdrRecorder::default);
+        results.tui = tui_feed;
+        results.streaming = config.streaming_quantiles.then(StreamingStats::default);
+        let mut aborted = false;
+        let mut shutdown_check = tokio::time::interval(Duration::from_millis(100));
+        let mut soak = config.soak.as_ref().map(SoakRecorder::new).transpose()?;
+        let mut soak_timer = config.soak.as_ref().map(|s| {
+            tokio::time::interval_at(tokio::time::Instant::now() + s.interval, s.interval)
+        });
+        let mut report_timer = config
+            .report_interval
+            .map(|d| tokio::time::interval_at(tokio::time::Instant::now() + d, d));
+        while !interrupted
+            && !warmup_expired
+            && request_limit.is_none_or(|n| results.completed() < n)
+        {
+            let deadline_reached = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let report_due = async {
+                match &mut report_timer {
+                    Some(timer) => timer.tick().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let soak_due = async {
+                match &mut soak_timer {
+                    Some(timer) => timer.tick().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                received = result_receiver.recv() => match received {
+                    Some(res) => results.push(res)?,
+                    None => break,
+                },
+                _ = deadline_reached => break,
+                _ = report_due => print_snapshot(&config, results.results(), start.elapsed()),
+                _ = soak_due => if let Some(soak) = &mut soak {
+                    let window = soak.record(
+                        results.results(),
+                        metrics.errors(),
+                        connect_stats.connections(),
+                        start.elapsed(),
+                    )?;
+                    let ms = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.2}ms", v));
+                    let clear = if config.log.is_terminal() { "\r\x1b[2K" } else { "" };
+                    info!(
+                        config,
+                        "{}[soak t={}s] rps={:.2} errors={} p99_ttft={} new_conns={} rss={}",
+                        clear,
+                        window.elapsed_secs,
+                        window.requests_per_sec,
+                        window.errors,
+                        ms(window.p99_ttft_ms),
+                        window.new_connections,
+                        window.rss_kb.map_or("n/a".to_string(), |kb| format!("{}KB", kb))
+                    );
+                },
+                _ = shutdown_check.tick(), if config.shutdown.is_some() => {
+                    if config.shutdown.as_ref().is_some_and(|s| s.load(Ordering::Relaxed)) {
+                        interrupted = true;
+                        break;
+                    }
+                }
+                _ = error_counter.tripped.notified(), if config.max_errors.is_some() => {
+                    let errors = error_counter.count.load(Ordering::Relaxed);
+                    // consecutive 模式下通知发出后可能已被成功请求清零
+                    if config.max_errors.is_some_and(|max| errors >= max) {
+                        let mode = match config.max_errors_mode {
+                            MaxErrorsMode::Consecutive => "consecutive",
+                            MaxErrorsMode::Total => "total",
+                        };
+                        eprintln!("\naborting: too many errors ({} {})", errors, mode);
+                        let _ = cancel_sender.send(true);
+                        aborted = true;
+                        break;
+                    }
+                }
+            }
+        }
+        stop.store(true, Ordering::Relaxed);
+
+        // 按时长结束时，等待已发出的请求完成并计入结果
+        if interrupted {
+            // 中断时只短暂等待正在进行的请求，不等它们全部完成
+            let drain_deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+            while request_limit.is_none_or(|n| results.completed() < n) {
+                match tokio::time::timeout_at(drain_deadline, result_receiver.recv()).await {
+                    Ok(Some(res)) => results.push(res)?,
+                    _ => break,
+                }
+            }
+        } else if deadline.is_some() && !aborted {
+            while request_limit.is_none_or(|n| results.completed() < n) {
+                match result_receiver.recv().await {
+                    Some(res) => results.push(res)?,
+                    None => break,
+                }
+            }
+        }
+
+        // 结束前已发出但尚未收到的失败也计入统计（不超过请求数），多出的成功结果丢弃
+        while let Ok(outcome) = result_receiver.try_recv() {
+            if let RequestOutcome::Failure { .. } = outcome
+                && request_limit.is_none_or(|n| results.completed() < n)
+            {
+                results.push(outcome)?;
+            }
+        }
+
+        let total_time = start.elapsed();
+        progress.done.store(true, Ordering::Relaxed);
+        if let Some(task) = progress_task {
+            task.await?;
+        }
+        if let Some(task) = metrics_task {
+            task.abort();
+        }
+        if let Some(task) = spike_task {
+            task.abort();
+        }
+        if let Some(otlp) = otlp_shutdown {
+            otlp.shutdown().await?;
+        }
+        let hdr = results.hdr.take();
+        if let (Some(har), Some(path)) = (&har, &config.record_har) {
+            let count = har.write(path)?;
+            info!(config, "Recorded {} requests to {}", count, path.display());
+        }
+        if let Some(tracer) = &tracer {
+            let count = tracer.write(&config.trace_file)?;
+            info!(
+                config,
+                "Traced {} requests to {}",
+                count,
+                config.trace_file.display()
+            );
+        }
+        if let (Some(hdr), Some(path)) = (&hdr, &config.hdr_output) {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            hdr.ttft.write_hgrm(&mut file)?;
+            file.flush()?;
+        }
+        let success = results.len();
+        let status_codes = std::mem::take(&mut results.status_codes);
+        let failures = std::mem::take(&mut results.failures);
+        let url_errors = std::mem::take(&mut results.url_errors);
+        let assertion_failures = results.assertion_failures;
+        let truncated = results.truncated;
+        let streaming = results.streaming.take();
+        let results = results.finish()?;
+        let soak = soak
+            .map(|soak| soak.finish(&results, started_at, total_time))
+            .transpose()?;
+        let total = success + failures.values().sum::<usize>();
+
+        let mut result = match streaming {
+            Some(streaming) => BenchmarkResult::from_streaming(
+                streaming,
+                total,
+                total_time,
+                started_at,
+                &config.percentiles,
+            ),
+            None => {
+                BenchmarkResult::new(results, total, total_time, started_at, &config.percentiles)
+            }
+        };
+        result.aborted = aborted;
+        result.interrupted = interrupted;
+        result.connection_reuse_disabled = config.connection_per_request;
+        result.tags = config.tags.clone();
+        result.status_codes = status_codes;
+        result.schema_errors = config.response_schema.is_some().then(|| {
+            failures
+                .get(&ErrorKind::SchemaValidation)
+                .copied()
+                .unwrap_or(0)
+        });
+        result.failures = failures;
+        result.assertion_failures = (config.expected_status.is_some()
+            || !config.field_assertions.is_empty())
+        .then_some(assertion_failures);
+        result.truncated_responses = config.max_body_size.map(|_| truncated);
+        result.token_rates = TokenRates::new(
+            result.requests_per_sec,
+            result.usage.as_ref(),
+            config.input_tokens_estimate,
+            config.output_tokens_estimate,
+        );
+        if !config.extract.is_empty() {
+            result.extractions = ExtractStats::from_results(&config.extract, &result.results);
+        }
+        if config.urls.len() > 1 {
+            result.urls = UrlStats::from_results(&config.urls, &result.results, &url_errors);
+        }
+        result.connect = connect_stats.summary();
+        result.soak = soak;
+        result.hdr = hdr.map(|hdr| hdr.summary());
+        if let Some(spike) = &config.spike {
+            result.phases = split_phases(
+                spike,
+                &result.results,
+                started_at,
+                total_time,
+                &config.percentiles,
+            );
+        }
+        result.slo_ttft = config
+            .slo_ttft
+            .map(|slo| SloCompliance::new(result.results.iter().map(|r| r.ttft), slo, total));
+        result.slo_total = config
+            .slo_total
+            .map(|slo| SloCompliance::new(result.results.iter().map(|r| r.total), slo, total));
+        if let (Some(baseline), Some(ttft)) = (config.expected_server_latency, &result.ttft) {
+            result.connect_overhead_ms = Some(ttft.avg - baseline.as_secs_f64() * 1000.0);
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,469 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// `$ref` 展开和嵌套子模式的最大深度，超过时视为循环引用
+const MAX_DEPTH: usize = 64;
+
+/// 校验响应体的 JSON Schema
+///
+/// 支持常用关键字：`type`、`enum`、`const`、`properties`、`required`、`additionalProperties`、
+/// `items`、`minItems` / `maxItems`、`minLength` / `maxLength`、`minimum` / `maximum`、
+/// `exclusiveMinimum` / `exclusiveMaximum`、`allOf` / `anyOf` / `oneOf` / `not`，
+/// 以及指向同一文档的 `$ref`（如 `#/definitions/choice`）；其余关键字（如 `pattern`）忽略。
+///
+/// ```
+/// use interface_performance_test::JsonSchema;
+/// use serde_json::json;
+///
+/// let schema = JsonSchema::new(json!({
+///     "type": "object",
+///     "required": ["choices"],
+///     "properties": {"choices": {"type": "array", "minItems": 1}},
+/// }))
+/// .unwrap();
+/// assert!(schema.validate(&json!({"choices": [{}]})).is_ok());
+/// assert!(schema.validate(&json!({"error": "context too long"})).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "Value", try_from = "Value")]
+pub struct JsonSchema {
+    root: Value,
+}
+
+/// 序列化为模式原文
+impl From<JsonSchema> for Value {
+    fn from(schema: JsonSchema) -> Self {
+        schema.root
+    }
+}
+
+impl TryFrom<Value> for JsonSchema {
+    type Error = String;
+
+    fn try_from(schema: Value) -> Result<Self, String> {
+        JsonSchema::new(schema)
+    }
+}
+
+impl JsonSchema {
+    /// 模式须为 JSON 对象或布尔值
+    pub fn new(schema: Value) -> Result<Self, String> {
+        if !(schema.is_object() || schema.is_boolean()) {
+            return Err("JSON Schema must be an object or a boolean".to_string());
+        }
+        Ok(Self { root: schema })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read --response-schema {}: {e}", path.display()))?;
+        let schema = serde_json::from_str(&text)
+            .map_err(|e| format!("{}: invalid JSON: {e}", path.display()))?;
+        Self::new(schema).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// 返回第一处不符合模式的位置（JSON Pointer）和原因
+    pub fn validate(&self, instance: &Value) -> Result<(), String> {
+        self.check(&self.root, instance, "", 0)
+    }
+
+    /// SSE 模式下逐个校验 `data:` 事件（跳过 `[DONE]`），否则将整个响应体作为一个 JSON 校验
+    pub(crate) fn validate_response(&self, body: &[u8], sse: bool) -> Result<(), String> {
+        let text = String::from_utf8_lossy(body);
+        if !sse {
+            let value = serde_json::from_str(&text)
+                .map_err(|e| format!("response is not valid JSON: {e}"))?;
+            return self.validate(&value);
+        }
+        let events = text
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("data:"))
+            .map(str::trim)
+            .filter(|data| *data != "[DONE]");
+        for (i, data) in events.enumerate() {
+            let value = serde_json::from_str(data)
+                .map_err(|e| format!("event {}: not valid JSON: {e}", i + 1))?;
+            self.validate(&value)
+                .map_err(|e| format!("event {}: {e}", i + 1))?;
+        }
+        Ok(())
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("schema nesting too deep (recursive $ref?)".to_string());
+        }
+        let location = if path.is_empty() { "/" } else { path };
+        let fail = |reason: String| Err(format!("{location}: {reason}"));
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => return fail("not allowed by schema".to_string()),
+            Value::Object(schema) => schema,
+            _ => return Ok(()),
+        };
+        let sub =
+            |schema: &Value, value: &Value, path: &str| self.check(schema, value, path, depth + 1);
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+                .ok_or_else(|| format!("unresolvable $ref {reference:?}"))?;
+            sub(target, value, path)?;
+        }
+
+        if let Some(ty) = schema.get("type") {
+            let types: Vec<&str> = match ty {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+                return fail(format!(
+                    "expected {}, got {}",
+                    types.join(" or "),
+                    type_name(value)
+                ));
+            }
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array)
+            && !options.contains(value)
+        {
+            return fail(format!("{value} is not one of the allowed values"));
+        }
+        if let Some(expected) = schema.get("const")
+            && expected != value
+        {
+            return fail(format!("expected {expected}, got {value}"));
+        }
+
+        match value {
+            Value::Object(object) => {
+                if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                    for name in required.iter().filter_map(Value::as_str) {
+                        if !object.contains_key(name) {
+                            return fail(format!("missing required property {name:?}"));
+                        }
+                    }
+                }
+                let properties = schema.get("properties").and_then(Value::as_object);
+                for (name, item) in object {
+                    let escaped = name.replace('~', "~0").replace('/', "~1");
+                    let item_path = format!("{path}/{escaped}");
+                    match properties.and_then(|p| p.get(name)) {
+                        Some(property) => sub(property, item, &item_path)?,
+                        None => match schema.get("additionalProperties") {
+                            Some(Value::Bool(false)) => {
+                                return fail(format!("unexpected property {name:?}"));
+                            }
+                            Some(additional) => sub(additional, item, &item_path)?,
+                            None => {}
+                        },
+                    }
+                }
+            }
+            Value::Array(items) => {
+                let len = items.len() as u64;
+                if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                    && len < min
+                {
+                    return fail(format!("expected at least {min} items, got {len}"));
+                }
+                if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                    && len > max
+                {
+                    return fail(format!("expected at most {max} items, got {len}"));
+                }
+                match schema.get("items") {
+                    // 数组形式（draft-07 的元组校验）按位置对应
+                    Some(Value::Array(tuple)) => {
+                        for (i, (item, schema)) in items.iter().zip(tuple).enumerate() {
+                            sub(schema, item, &format!("{path}/{i}"))?;
+                        }
+                    }
+                    Some(schema) => {
+                        for (i, item) in items.iter().enumerate() {
+                            sub(schema, item, &format!("{path}/{i}"))?;
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count() as u64;
+                if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                    && len < min
+                {
+                    return fail(format!("expected at least {min} characters, got {len}"));
+                }
+                if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                    && len > max
+                {
+                    return fail(format!("expected at most {max} characters, got {len}"));
+                }
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or(f64::NAN);
+                let bound = |name: &str| schema.get(name).and_then(Value::as_f64);
+                if let Some(min) = bound("minimum").filter(|&min| n < min) {
+                    return fail(format!("{n} is less than the minimum {min}"));
+                }
+                if let Some(max) = bound("maximum").filter(|&max| n > max) {
+                    return fail(format!("{n} is greater than the maximum {max}"));
+                }
+                if let Some(min) = bound("exclusiveMinimum").filter(|&min| n <= min) {
+                    return fail(format!("{n} is not greater than {min}"));
+                }
+                if let Some(max) = bound("exclusiveMaximum").filter(|&max| n >= max) {
+                    return fail(format!("{n} is not less than {max}"));
+                }
+            }
+            _ => {}
+        }
+
+        let subschemas = |name: &str| schema.get(name).and_then(Value::as_array);
+        if let Some(all) = subschemas("allOf") {
+            for schema in all {
+                sub(schema, value, path)?;
+            }
+        }
+        if let Some(any) = subschemas("anyOf")
+            && !any.iter().any(|schema| sub(schema, value, path).is_ok())
+        {
+            return fail("does not match any schema in anyOf".to_string());
+        }
+        if let Some(one) = subschemas("oneOf") {
+            let matched = one
+                .iter()
+                .filter(|schema| sub(schema, value, path).is_ok())
+                .count();
+            if matched != 1 {
+                return fail(format!(
+                    "matches {matched} schemas in oneOf, expected exactly 1"
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not")
+            && sub(not, value, path).is_ok()
+        {
+            return fail("must not match the schema in not".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn type_matches(ty: &str, value: &Value) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 类似 chat completions 响应的模式
+    fn completion_schema() -> JsonSchema {
+        JsonSchema::new(json!({
+            "type": "object",
+            "required": ["model", "choices"],
+            "properties": {
+                "model": {"type": "string", "minLength": 1},
+                "choices": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {"$ref": "#/definitions/choice"},
+                },
+                "usage": {
+                    "type": ["object", "null"],
+                    "properties": {"total_tokens": {"type": "integer", "minimum": 0}},
+                },
+            },
+            "definitions": {
+                "choice": {
+                    "type": "object",
+                    "required": ["finish_reason"],
+                    "properties": {
+                        "finish_reason": {"enum": ["stop", "length", null]},
+                        "message/content": {"type": "string"},
+                    },
+                    "additionalProperties": false,
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn error(schema: &JsonSchema, value: Value) -> String {
+        schema.validate(&value).unwrap_err()
+    }
+
+    #[test]
+    fn accepts_matching_instance() {
+        let schema = completion_schema();
+        let value = json!({
+            "model": "m",
+            "choices": [{"finish_reason": "stop"}, {"finish_reason": null}],
+            "usage": {"total_tokens": 12},
+        });
+        assert_eq!(schema.validate(&value), Ok(()));
+        assert_eq!(
+            schema.validate(
+                &json!({"model": "m", "choices": [{"finish_reason": "length"}], "usage": null})
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn reports_type_and_required_failures() {
+        let schema = completion_schema();
+        assert_eq!(error(&schema, json!([])), "/: expected object, got array");
+        assert_eq!(
+            error(&schema, json!({"model": "m"})),
+            "/: missing required property \"choices\""
+        );
+        assert_eq!(
+            error(
+                &schema,
+                json!({"model": 1, "choices": [{"finish_reason": "stop"}]})
+            ),
+            "/model: expected string, got integer"
+        );
+        assert_eq!(
+            error(&schema, json!({"model": "m", "choices": [], "usage": 1.5})),
+            "/choices: expected at least 1 items, got 0"
+        );
+    }
+
+    #[test]
+    fn reports_nested_failures_with_paths() {
+        let schema = completion_schema();
+        let choices =
+            |choice: Value| json!({"model": "m", "choices": [{"finish_reason": "stop"}, choice]});
+        assert_eq!(
+            error(&schema, choices(json!({"finish_reason": "tool_calls"}))),
+            "/choices/1/finish_reason: \"tool_calls\" is not one of the allowed values"
+        );
+        assert_eq!(
+            error(&schema, choices(json!({}))),
+            "/choices/1: missing required property \"finish_reason\""
+        );
+        assert_eq!(
+            error(
+                &schema,
+                choices(json!({"finish_reason": "stop", "index": 0}))
+            ),
+            "/choices/1: unexpected property \"index\""
+        );
+        // 属性名中的 / 按 JSON Pointer 转义为 ~1
+        assert_eq!(
+            error(
+                &schema,
+                choices(json!({"finish_reason": "stop", "message/content": 1}))
+            ),
+            "/choices/1/message~1content: expected string, got integer"
+        );
+        assert_eq!(
+            error(
+                &schema,
+                json!({"model": "m", "choices": [{"finish_reason": "stop"}], "usage": {"total_tokens": -1}})
+            ),
+            "/usage/total_tokens: -1 is less than the minimum 0"
+        );
+    }
+
+    #[test]
+    fn tuple_items_and_combinators() {
+        let schema = JsonSchema::new(json!({
+            "type": "array",
+            "items": [{"type": "integer"}, {"oneOf": [{"const": "a"}, {"type": "boolean"}]}],
+            "maxItems": 3,
+        }))
+        .unwrap();
+        assert_eq!(schema.validate(&json!([1, "a", "anything"])), Ok(()));
+        assert_eq!(
+            error(&schema, json!([1.5])),
+            "/0: expected integer, got number"
+        );
+        assert_eq!(
+            error(&schema, json!([1, "b"])),
+            "/1: matches 0 schemas in oneOf, expected exactly 1"
+        );
+        assert_eq!(
+            error(&schema, json!([1, true, 2, 3])),
+            "/: expected at most 3 items, got 4"
+        );
+
+        let schema = JsonSchema::new(
+            json!({"not": {"type": "null"}, "anyOf": [{"type": "string"}, {"minimum": 10}]}),
+        )
+        .unwrap();
+        assert_eq!(
+            error(&schema, json!(null)),
+            "/: must not match the schema in not"
+        );
+        assert_eq!(
+            error(&schema, json!(3)),
+            "/: does not match any schema in anyOf"
+        );
+        assert_eq!(schema.validate(&json!(10)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_invalid_schemas_and_refs() {
+        assert!(JsonSchema::new(json!("object")).is_err());
+        assert_eq!(
+            error(&JsonSchema::new(json!(false)).unwrap(), json!(1)),
+            "/: not allowed by schema"
+        );
+        let missing = JsonSchema::new(json!({"$ref": "#/definitions/missing"})).unwrap();
+        assert_eq!(
+            error(&missing, json!(1)),
+            "unresolvable $ref \"#/definitions/missing\""
+        );
+        let recursive = JsonSchema::new(json!({"$ref": "#"})).unwrap();
+        assert_eq!(
+            error(&recursive, json!(1)),
+            "schema nesting too deep (recursive $ref?)"
+        );
+    }
+
+    #[test]
+    fn validates_sse_events() {
+        let schema = JsonSchema::new(json!({"type": "object", "required": ["choices"]})).unwrap();
+        let body = b"data: {\"choices\": []}\n\ndata: {\"error\": 1}\n\ndata: [DONE]\n\n";
+        assert_eq!(
+            schema.validate_response(body, true).unwrap_err(),
+            "event 2: /: missing required property \"choices\""
+        );
+        assert_eq!(schema.validate_response(b"data: [DONE]\n\n", true), Ok(()));
+        assert!(
+            schema
+                .validate_response(b"not json", false)
+                .unwrap_err()
+                .starts_with("response is not valid JSON")
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::stats::{LatencyResult, LatencyStats};
+use crate::util::duration_secs;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 比较延迟漂移时取开头和结尾各这么长的时间段
+const DRIFT_WINDOW: Duration = Duration::from_secs(600);
+
+/// 长时间稳定性测试：持续时长由 `BenchmarkConfig::duration` 决定，
+/// 额外按固定间隔记录时间序列，并在结束时比较首尾延迟
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Soak {
+    /// 每个间隔写一行 JSON 的时间序列文件
+    pub output: Option<PathBuf>,
+    #[serde(with = "duration_secs")]
+    pub interval: Duration,
+}
+
+/// 时间序列中的一个采样间隔
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakWindow {
+    /// 间隔结束时距正式开始的秒数
+    pub elapsed_secs: u64,
+    pub success: usize,
+    pub errors: u64,
+    pub requests_per_sec: f64,
+    pub p50_ttft_ms: Option<f64>,
+    pub p99_ttft_ms: Option<f64>,
+    pub p99_total_ms: Option<f64>,
+    /// 本间隔内新建的连接数
+    pub new_connections: u64,
+    /// 压测进程的常驻内存，仅 Linux 可用
+    pub rss_kb: Option<u64>,
+}
+
+/// 稳定性测试结束时的漂移汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSummary {
+    /// 比较所用首尾时间段的长度（秒），测试较短时取总时长的一半
+    pub drift_window_secs: u64,
+    pub first_p99_ttft_ms: Option<f64>,
+    pub last_p99_ttft_ms: Option<f64>,
+    pub rss_start_kb: Option<u64>,
+    pub rss_end_kb: Option<u64>,
+    pub windows: usize,
+}
+
+impl SoakSummary {
+    /// 结尾 P99 TTFT 相对开头的变化百分比
+    pub fn drift_pct(&self) -> Option<f64> {
+        Some((self.last_p99_ttft_ms? / self.first_p99_ttft_ms? - 1.0) * 100.0)
+    }
+
+    pub(crate) fn print(&self) {
+        println!("\n--- Soak ---");
+        println!("Windows recorded: {}", self.windows);
+        let fmt = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.2} ms", v));
+        let span = match self.drift_window_secs {
+            secs if secs >= 60 && secs % 60 == 0 => format!("{}min", secs / 60),
+            secs => format!("{}s", secs),
+        };
+        println!("P99 TTFT first {}: {}", span, fmt(self.first_p99_ttft_ms));
+        println!("P99 TTFT last {}: {}", span, fmt(self.last_p99_ttft_ms));
+        if let Some(drift) = self.drift_pct() {
+            println!("Latency drift: {:+.1}%", drift);
+        }
+        if let (Some(start), Some(end)) = (self.rss_start_kb, self.rss_end_kb) {
+            println!(
+                "Memory (RSS): {} KB -> {} KB ({:+} KB)",
+                start,
+                end,
+                end as i64 - start as i64
+            );
+        }
+    }
+}
+
+/// 读取 /proc/self/status 中的 VmRSS，其他平台返回 None
+pub(crate) fn rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn percentiles_ms(samples: impl Iterator<Item = Duration>, perc: &[f64]) -> Vec<f64> {
+    LatencyStats::from_samples(samples, perc)
+        .map(|s| s.percentiles.into_iter().map(|(_, v)| v).collect())
+        .unwrap_or_default()
+}
+
+/// 在收集结果的循环中按间隔生成 [`SoakWindow`]
+pub(crate) struct SoakRecorder {
+    output: Option<LineWriter<File>>,
+    interval: Duration,
+    /// 上个间隔结束时的结果数、失败数和连接数
+    last_len: usize,
+    last_errors: u64,
+    last_connections: u64,
+    rss_start: Option<u64>,
+    windows: usize,
+}
+
+impl SoakRecorder {
+    pub(crate) fn new(soak: &Soak) -> std::io::Result<Self> {
+        let output = soak
+            .output
+            .as_ref()
+            .map(File::create)
+            .transpose()?
+            .map(LineWriter::new);
+        Ok(Self {
+            output,
+            interval: soak.interval,
+            last_len: 0,
+            last_errors: 0,
+            last_connections: 0,
+            rss_start: rss_kb(),
+            windows: 0,
+        })
+    }
+
+    /// results 按完成顺序排列，只统计上次调用之后新增的部分
+    pub(crate) fn record(
+        &mut self,
+        results: &[LatencyResult],
+        errors: u64,
+        connections: u64,
+        elapsed: Duration,
+    ) -> std::io::Result<SoakWindow> {
+        let window = &results[self.last_len.min(results.len())..];
+        let ttft = percentiles_ms(window.iter().map(|r| r.ttft), &[50.0, 99.0]);
+        let total = percentiles_ms(window.iter().map(|r| r.total), &[99.0]);
+        let record = SoakWindow {
+            elapsed_secs: elapsed.as_secs(),
+            success: window.len(),
+            errors: errors - self.last_errors,
+            requests_per_sec: window.len() as f64 / self.interval.as_secs_f64(),
+            p50_ttft_ms: ttft.first().copied(),
+            p99_ttft_ms: ttft.get(1).copied(),
+            p99_total_ms: total.first().copied(),
+            new_connections: connections - self.last_connections,
+            rss_kb: rss_kb(),
+        };
+        self.last_len = results.len();
+        self.last_errors = errors;
+        self.last_connections = connections;
+        self.windows += 1;
+        if let Some(output) = &mut self.output {
+            writeln!(output, "{}", serde_json::to_string(&record)?)?;
+        }
+        Ok(record)
+    }
+
+    /// 按请求发出时刻划分首尾时间段，比较两段的 P99 TTFT
+    pub(crate) fn finish(
+        mut self,
+        results: &[LatencyResult],
+        started_at: f64,
+        total_time: Duration,
+    ) -> std::io::Result<SoakSummary> {
+        if let Some(output) = &mut self.output {
+            output.flush()?;
+        }
+        let span = DRIFT_WINDOW.min(total_time / 2).as_secs_f64();
+        let end = started_at + total_time.as_secs_f64();
+        let first = results.iter().filter(|r| r.start_ts < started_at + span);
+        let last = results.iter().filter(|r| r.start_ts >= end - span);
+        Ok(SoakSummary {
+            drift_window_secs: span as u64,
+            first_p99_ttft_ms: percentiles_ms(first.map(|r| r.ttft), &[99.0])
+                .first()
+                .copied(),
+            last_p99_ttft_ms: percentiles_ms(last.map(|r| r.ttft), &[99.0])
+                .first()
+                .copied(),
+            rss_start_kb: self.rss_start,
+            rss_end_kb: rss_kb(),
+            windows: self.windows,
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use crate::stats::{LatencyResult, LatencyStats};
+use crate::util::duration_secs;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 突发阶段之前，只有前 baseline_concurrency 个 worker 发起请求
+pub(crate) const PHASE_PRE: u8 = 0;
+/// 突发阶段，全部 worker 发起请求
+pub(crate) const PHASE_SPIKE: u8 = 1;
+/// 突发结束后回到基础并发
+pub(crate) const PHASE_POST: u8 = 2;
+
+/// 突发负载：以基础并发运行 delay 后，全部 `BenchmarkConfig::concurrency` 个 worker
+/// 同时发起请求并持续 duration，再回到基础并发直到测试结束
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spike {
+    pub baseline_concurrency: usize,
+    #[serde(with = "duration_secs")]
+    pub delay: Duration,
+    #[serde(with = "duration_secs")]
+    pub duration: Duration,
+}
+
+impl Spike {
+    /// 请求发出时刻所处的阶段，started_at 为正式计时开始的时刻
+    pub(crate) fn phase_at(&self, start_ts: f64, started_at: f64) -> u8 {
+        let offset = start_ts - started_at;
+        if offset < self.delay.as_secs_f64() {
+            PHASE_PRE
+        } else if offset < (self.delay + self.duration).as_secs_f64() {
+            PHASE_SPIKE
+        } else {
+            PHASE_POST
+        }
+    }
+}
+
+/// 单个阶段的统计结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseResult {
+    pub phase: String,
+    pub success: usize,
+    pub requests_per_sec: f64,
+    pub ttft: Option<LatencyStats>,
+    pub end_to_end: Option<LatencyStats>,
+}
+
+/// 按请求发出时刻把结果划分为突发前、突发中、突发后三段
+pub(crate) fn split_phases(
+    spike: &Spike,
+    results: &[LatencyResult],
+    started_at: f64,
+    total_time: Duration,
+    percentiles: &[f64],
+) -> Vec<PhaseResult> {
+    let lengths = [
+        spike.delay,
+        spike.duration,
+        total_time.saturating_sub(spike.delay + spike.duration),
+    ];
+    ["pre-spike", "spike", "post-spike"]
+        .into_iter()
+        .zip(lengths)
+        .enumerate()
+        .map(|(phase, (name, length))| {
+            let in_phase: Vec<&LatencyResult> = results
+                .iter()
+                .filter(|r| usize::from(spike.phase_at(r.start_ts, started_at)) == phase)
+                .collect();
+            PhaseResult {
+                phase: name.to_string(),
+                success: in_phase.len(),
+                requests_per_sec: if length.is_zero() {
+                    0.0
+                } else {
+                    in_phase.len() as f64 / length.as_secs_f64()
+                },
+                ttft: LatencyStats::from_samples(in_phase.iter().map(|r| r.ttft), percentiles),
+                end_to_end: LatencyStats::from_samples(
+                    in_phase.iter().map(|r| r.total),
+                    percentiles,
+                ),
+            }
+        })
+        .collect()
+}
+
+impl PhaseResult {
+    pub(crate) fn print(&self, colored: bool) {
+        println!(
+            "\n=== Phase: {} ({} requests, {:.2} requests/sec) ===",
+            self.phase, self.success, self.requests_per_sec
+        );
+        if let Some(ttft) = &self.ttft {
+            ttft.print("TTFT", colored);
+        }
+        if let Some(end_to_end) = &self.end_to_end {
+            end_to_end.print("End-to-End", colored);
+        }
+    }
+}
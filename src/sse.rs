@@ -0,0 +1,176 @@
+use crate::config::SseFormat;
+use crate::stats::TokenUsage;
+
+/// 增量解析 SSE 字节流的状态机：逐行缓存 `event:` / `data:` 字段，遇到空行时得到一个完整事件，
+/// 再按 `format` 判断是否计为 token 事件以及是否为结束标志
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    format: SseFormat,
+    /// 尚未读到换行的半行
+    line: Vec<u8>,
+    /// 当前事件的 `event:` 字段
+    event: Option<String>,
+    /// 当前事件的 `data:` 字段，多行时以换行拼接
+    data: Option<String>,
+}
+
+impl SseParser {
+    pub(crate) fn new(format: SseFormat) -> Self {
+        Self {
+            format,
+            ..Self::default()
+        }
+    }
+
+    /// 追加一个 chunk，返回其中完整事件的数量以及是否遇到结束标志
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> (u32, bool) {
+        let mut events = 0;
+        for &b in chunk {
+            if b != b'\n' {
+                self.line.push(b);
+                continue;
+            }
+            let line = std::mem::take(&mut self.line);
+            let line = String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(&line));
+            if !line.is_empty() {
+                self.field(&line);
+                continue;
+            }
+            let (event, data) = (self.event.take(), self.data.take());
+            let Some(data) = data else {
+                continue;
+            };
+            match self.classify(event.as_deref(), data.trim()) {
+                EventKind::Token => events += 1,
+                EventKind::Done => return (events, true),
+                EventKind::Other => {}
+            }
+        }
+        (events, false)
+    }
+
+    /// 处理一行字段，注释（`:` 开头）和未知字段忽略
+    fn field(&mut self, line: &str) {
+        let (name, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match name {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+    }
+
+    fn classify(&self, event: Option<&str>, data: &str) -> EventKind {
+        match self.format {
+            SseFormat::OpenAi if data == "[DONE]" => EventKind::Done,
+            SseFormat::OpenAi if serde_json::from_str::<serde_json::Value>(data).is_ok() => {
+                EventKind::Token
+            }
+            SseFormat::OpenAi => EventKind::Other,
+            SseFormat::Anthropic => {
+                let value = serde_json::from_str::<serde_json::Value>(data).ok();
+                // 事件类型以 `event:` 行为准，缺省时取 data 中的 type 字段
+                let kind = event.or_else(|| value.as_ref()?.get("type")?.as_str());
+                match kind {
+                    Some("message_stop") => EventKind::Done,
+                    Some("content_block_delta")
+                        if value
+                            .as_ref()
+                            .and_then(|v| v.get("delta")?.get("text")?.as_str())
+                            .is_some() =>
+                    {
+                        EventKind::Token
+                    }
+                    _ => EventKind::Other,
+                }
+            }
+        }
+    }
+}
+
+enum EventKind {
+    /// 计为一个 token 事件（用于 TTFT / ITL）
+    Token,
+    /// 结束标志：OpenAI 的 `[DONE]`，Anthropic 的 `message_stop`
+    Done,
+    /// ping、message_start 等不计入统计的事件
+    Other,
+}
+
+/// 在响应体中查找最后一个能被 extract 解析的 usage 对象，
+/// 兼容整段 JSON 和 SSE `data:` 行
+fn find_last_usage<T>(body: &[u8], extract: impl Fn(&serde_json::Value) -> Option<T>) -> Option<T> {
+    let text = String::from_utf8_lossy(body);
+    if let Ok(v) = serde_json::from_str::<serde_json::Value>(&text) {
+        return extract(v.get("usage")?);
+    }
+    text.lines()
+        .rev()
+        .filter_map(|line| line.trim().strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<serde_json::Value>(data.trim()).ok())
+        .find_map(|v| extract(v.get("usage")?))
+}
+
+/// 在响应体中查找 usage.completion_tokens（Anthropic 为 usage.output_tokens），以最后一次出现为准
+pub(crate) fn parse_completion_tokens(body: &[u8]) -> Option<u32> {
+    find_last_usage(body, |usage| {
+        usage
+            .get("completion_tokens")
+            .or_else(|| usage.get("output_tokens"))?
+            .as_u64()
+            .map(|n| n as u32)
+    })
+}
+
+/// 在响应体中查找 vLLM / OpenAI 格式的 usage（取最后一个带 completion_tokens 的），
+/// 缺少 total_tokens 时按 prompt_tokens + completion_tokens 计算
+pub(crate) fn parse_usage(body: &[u8]) -> Option<TokenUsage> {
+    find_last_usage(body, |usage| {
+        let field = |name: &str| usage.get(name)?.as_u64().map(|n| n as u32);
+        let completion_tokens = field("completion_tokens")?;
+        let prompt_tokens = field("prompt_tokens").unwrap_or(0);
+        Some(TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: field("total_tokens").unwrap_or(prompt_tokens + completion_tokens),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_last_usage() {
+        let json = br#"{"choices":[],"usage":{"prompt_tokens":3,"completion_tokens":4}}"#;
+        assert_eq!(parse_completion_tokens(json), Some(4));
+        assert_eq!(
+            parse_usage(json),
+            Some(TokenUsage {
+                prompt_tokens: 3,
+                completion_tokens: 4,
+                total_tokens: 7,
+            })
+        );
+
+        // 以最后一个能解析的 usage 为准，usage 为 null 的事件跳过
+        let sse = b"data: {\"usage\":{\"completion_tokens\":1,\"total_tokens\":9}}\n\n\
+                    data: {\"usage\":{\"completion_tokens\":2,\"total_tokens\":10}}\n\n\
+                    data: {\"usage\":null}\n\ndata: [DONE]\n\n";
+        assert_eq!(parse_completion_tokens(sse), Some(2));
+        assert_eq!(parse_usage(sse).unwrap().total_tokens, 10);
+
+        // Anthropic 的 output_tokens 只用于 token 计数
+        let anthropic = b"data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":7}}\n\n";
+        assert_eq!(parse_completion_tokens(anthropic), Some(7));
+        assert_eq!(parse_usage(anthropic), None);
+        assert_eq!(parse_completion_tokens(b"{}"), None);
+    }
+}
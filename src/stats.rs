@@ -0,0 +1,1428 @@
+use crate::connect::ConnectSummary;
+use crate::hdr::HdrSummary;
+use crate::jsonpath::JsonPath;
+use crate::soak::SoakSummary;
+use crate::spike::PhaseResult;
+use crate::tdigest::{StreamingStats, TDigest};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// 单个成功请求的测量结果
+#[derive(Debug, Clone)]
+pub struct LatencyResult {
+    pub ttft: Duration,
+    pub total: Duration,
+    /// 相邻 chunk 之间的间隔（inter-token latency），单 chunk 响应为空
+    pub itl: Vec<Duration>,
+    pub tokens: u32,
+    /// 读取到的响应体字节数（SSE 模式下为整个事件流的大小）
+    pub bytes_received: u64,
+    /// 从响应流读到的 chunk 数，与 SSE 事件数无关
+    pub chunks: u32,
+    /// 服务端在 usage 中报告的 token 数（--vllm-usage），响应中没有 usage 时为 None
+    pub usage: Option<TokenUsage>,
+    /// TGI 响应头中的服务端耗时（--tgi-headers），没有相关响应头时为 None
+    pub server_timing: Option<ServerTiming>,
+    /// 每个 --extract 表达式从响应体中取到的值，与 `BenchmarkConfig::extract` 一一对应
+    pub extracted: Vec<Option<serde_json::Value>>,
+    /// 未通过 --assert-field / --assert-status 检查（不影响成功与否）
+    pub assertion_failed: bool,
+    /// 达到 --max-body-size 后停止读取，响应体不完整（仍计为成功）
+    pub truncated: bool,
+    /// --request-id-header 中发送的请求 ID
+    pub request_id: Option<String>,
+    /// HTTP 状态码（2xx）
+    pub status: u16,
+    pub worker_id: usize,
+    /// 目标 URL 在 `BenchmarkConfig::urls` 中的下标，未使用 --url-list 时为 0
+    pub url_index: usize,
+    /// 请求发出时刻（Unix 时间戳，秒）
+    pub start_ts: f64,
+}
+
+/// 服务端报告的单个请求 token 数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// TGI 在响应头中报告的服务端耗时，单位 ms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerTiming {
+    /// x-inference-time
+    pub inference_ms: Option<f64>,
+    /// x-queue-time
+    pub queue_ms: Option<f64>,
+    /// x-prefill-time
+    pub prefill_ms: Option<f64>,
+    /// x-decode-time
+    pub decode_ms: Option<f64>,
+}
+
+impl ServerTiming {
+    /// 读取 TGI 的耗时响应头，一个都没有时返回 None
+    pub(crate) fn from_headers(headers: &reqwest::header::HeaderMap) -> Option<Self> {
+        let ms = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok();
+        let timing = Self {
+            inference_ms: ms("x-inference-time"),
+            queue_ms: ms("x-queue-time"),
+            prefill_ms: ms("x-prefill-time"),
+            decode_ms: ms("x-decode-time"),
+        };
+        (timing != Self::default()).then_some(timing)
+    }
+
+    fn fields(&self) -> [Option<f64>; 4] {
+        [
+            self.inference_ms,
+            self.queue_ms,
+            self.prefill_ms,
+            self.decode_ms,
+        ]
+    }
+}
+
+/// 逐个累计 ServerTiming，每项分别按带该响应头的请求数求平均
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServerTimingAccumulator {
+    requests: usize,
+    sums: [f64; 4],
+    counts: [usize; 4],
+}
+
+impl ServerTimingAccumulator {
+    pub(crate) fn record(&mut self, timing: &ServerTiming) {
+        self.requests += 1;
+        for (i, value) in timing.fields().into_iter().enumerate() {
+            if let Some(value) = value {
+                self.sums[i] += value;
+                self.counts[i] += 1;
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self) -> Option<ServerTimingStats> {
+        let avg = |i: usize| (self.counts[i] > 0).then(|| self.sums[i] / self.counts[i] as f64);
+        (self.requests > 0).then(|| ServerTimingStats {
+            requests: self.requests,
+            avg_inference_ms: avg(0),
+            avg_queue_ms: avg(1),
+            avg_prefill_ms: avg(2),
+            avg_decode_ms: avg(3),
+        })
+    }
+}
+
+/// 失败请求的原因分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 目标端口未监听（连接被拒绝）
+    ConnectionRefused,
+    /// 连接、请求或等待首个响应数据超时
+    Timeout,
+    DnsResolution,
+    TlsError,
+    Http4xx,
+    Http5xx,
+    /// 读取响应体失败，或响应结束时没有任何数据
+    StreamError,
+    /// 2xx 响应不符合 --response-schema（如返回了 `{"error": ...}`）
+    SchemaValidation,
+    Unknown,
+}
+
+impl ErrorKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorKind::ConnectionRefused => "Connection refused",
+            ErrorKind::Timeout => "Timeout",
+            ErrorKind::DnsResolution => "DNS resolution",
+            ErrorKind::TlsError => "TLS error",
+            ErrorKind::Http4xx => "HTTP 4xx",
+            ErrorKind::Http5xx => "HTTP 5xx",
+            ErrorKind::StreamError => "Stream error",
+            ErrorKind::SchemaValidation => "Schema validation",
+            ErrorKind::Unknown => "Unknown",
+        }
+    }
+
+    /// 非 2xx 响应按状态码分类，1xx / 3xx 归为 Unknown
+    pub(crate) fn from_status(status: reqwest::StatusCode) -> Self {
+        if status.is_client_error() {
+            ErrorKind::Http4xx
+        } else if status.is_server_error() {
+            ErrorKind::Http5xx
+        } else {
+            ErrorKind::Unknown
+        }
+    }
+
+    /// 未收到响应或读取响应体时的错误；连接错误沿 source 链查找底层原因
+    pub(crate) fn from_error(e: &reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return ErrorKind::Timeout;
+        }
+        if e.is_connect() {
+            let mut source = std::error::Error::source(e);
+            while let Some(err) = source {
+                if let Some(io) = err.downcast_ref::<std::io::Error>()
+                    && io.kind() == std::io::ErrorKind::ConnectionRefused
+                {
+                    return ErrorKind::ConnectionRefused;
+                }
+                let message = err.to_string().to_lowercase();
+                if message.contains("dns error") || message.contains("lookup address") {
+                    return ErrorKind::DnsResolution;
+                }
+                if message.contains("tls") || message.contains("certificate") {
+                    return ErrorKind::TlsError;
+                }
+                source = err.source();
+            }
+        } else if e.is_body() || e.is_decode() {
+            return ErrorKind::StreamError;
+        }
+        ErrorKind::Unknown
+    }
+}
+
+/// worker 发给主线程的单个请求结果
+#[derive(Debug, Clone)]
+pub enum RequestOutcome {
+    Success(Box<LatencyResult>),
+    /// status 为收到的 HTTP 状态码，未收到响应时为 None
+    Failure {
+        kind: ErrorKind,
+        status: Option<u16>,
+        url_index: usize,
+    },
+}
+
+/// 百分位的显示名，如 99.9 -> "P99.9"
+pub(crate) fn percentile_label(perc: f64) -> String {
+    format!("P{}", perc)
+}
+
+/// JSON 中的百分位字段名，小数点换成下划线，如 99.9 -> "p99_9"、5.5 -> "p5_5"
+fn percentile_key(perc: f64) -> String {
+    format!("p{perc}").replace('.', "_")
+}
+
+fn serialize_percentiles<S: serde::Serializer>(
+    percentiles: &[(f64, f64)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        percentiles
+            .iter()
+            .map(|(perc, value)| (percentile_key(*perc), value)),
+    )
+}
+
+/// serialize_percentiles 的逆过程，按百分位升序排列
+fn deserialize_percentiles<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(f64, f64)>, D::Error> {
+    let fields = BTreeMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    let mut percentiles: Vec<(f64, f64)> = fields
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let digits = key.strip_prefix('p')?;
+            let (int, frac) = digits.split_once('_').unwrap_or((digits, "0"));
+            let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+            if !is_number(int) || !is_number(frac) {
+                return None;
+            }
+            let perc = format!("{int}.{frac}").parse().ok()?;
+            Some((perc, value.as_f64()?))
+        })
+        .collect();
+    percentiles.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(percentiles)
+}
+
+/// 统计行使用的 ANSI 样式
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Style {
+    Plain,
+    Green,
+    Yellow,
+    Red,
+    Bold,
+}
+
+impl Style {
+    /// 百分位越高越靠近长尾：P90 以下绿色，P90~P99 黄色，P99 及以上红色
+    fn for_percentile(perc: f64) -> Self {
+        if perc < 90.0 {
+            Style::Green
+        } else if perc < 99.0 {
+            Style::Yellow
+        } else {
+            Style::Red
+        }
+    }
+
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Style::Plain => "",
+            Style::Green => "\x1b[32m",
+            Style::Yellow => "\x1b[33m",
+            Style::Red => "\x1b[31m",
+            Style::Bold => "\x1b[1m",
+        }
+    }
+}
+
+/// 打印一行 "label: value"，colored 为 false 时不输出任何转义序列
+fn print_stat(label: &str, value: &str, style: Style, colored: bool) {
+    if colored && !matches!(style, Style::Plain) {
+        println!("{}{}: {}\x1b[0m", style.code(), label, value);
+    } else {
+        println!("{}: {}", label, value);
+    }
+}
+
+/// 排好序的样本按百分位取值
+fn percentile(sorted: &[f64], perc: f64) -> f64 {
+    let idx = ((sorted.len() as f64) * perc).min(sorted.len() as f64 - 1.0) as usize;
+    sorted[idx]
+}
+
+/// 变异系数超过该值时高亮提示
+const CV_WARN_PCT: f64 = 30.0;
+
+/// 一组延迟样本的统计值，单位 ms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// 样本数；旧版本保存的结果中没有该字段，读回时为 0
+    #[serde(default)]
+    pub count: usize,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 总体标准差
+    pub std_dev: f64,
+    /// 变异系数 std_dev / avg × 100，超过约 30% 时分布可能是双峰的
+    #[serde(default)]
+    pub cv_pct: f64,
+    /// (百分位, 取值)，按 --percentiles 的顺序排列
+    #[serde(
+        flatten,
+        serialize_with = "serialize_percentiles",
+        deserialize_with = "deserialize_percentiles"
+    )]
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl LatencyStats {
+    /// 样本为空时返回 None
+    ///
+    /// ```
+    /// use interface_performance_test::LatencyStats;
+    /// use std::time::Duration;
+    ///
+    /// let samples = (1..=100).map(Duration::from_millis);
+    /// let stats = LatencyStats::from_samples(samples, &[50.0, 99.0]).unwrap();
+    /// assert_eq!(stats.min, 1.0);
+    /// assert_eq!(stats.max, 100.0);
+    /// assert_eq!(stats.percentiles, vec![(50.0, 51.0), (99.0, 100.0)]);
+    /// ```
+    pub fn from_samples(
+        samples: impl Iterator<Item = Duration>,
+        percentiles: &[f64],
+    ) -> Option<Self> {
+        let mut data: Vec<f64> = samples.map(|d| d.as_nanos() as f64 / 1_000_000.0).collect();
+        if data.is_empty() {
+            return None;
+        }
+        data.sort_unstable_by(f64::total_cmp);
+        let avg = data.iter().sum::<f64>() / data.len() as f64;
+        let variance = data.iter().map(|x| (x - avg).powi(2)).sum::<f64>() / data.len() as f64;
+        let std_dev = variance.sqrt();
+        Some(Self {
+            count: data.len(),
+            avg,
+            min: data[0],
+            max: data[data.len() - 1],
+            std_dev,
+            cv_pct: if avg > 0.0 {
+                std_dev / avg * 100.0
+            } else {
+                0.0
+            },
+            percentiles: percentiles
+                .iter()
+                .map(|&perc| (perc, percentile(&data, perc / 100.0)))
+                .collect(),
+        })
+    }
+
+    /// 由 t-digest 估算，样本为空时返回 None
+    pub(crate) fn from_digest(digest: &mut TDigest, percentiles: &[f64]) -> Option<Self> {
+        if digest.is_empty() {
+            return None;
+        }
+        let avg = digest.avg();
+        let std_dev = digest.std_dev();
+        Some(Self {
+            count: digest.count(),
+            avg,
+            min: digest.min(),
+            max: digest.max(),
+            std_dev,
+            cv_pct: if avg > 0.0 {
+                std_dev / avg * 100.0
+            } else {
+                0.0
+            },
+            percentiles: percentiles
+                .iter()
+                .map(|&perc| (perc, digest.percentile(perc)))
+                .collect(),
+        })
+    }
+
+    /// 合并两组样本（如多台压测机各自的结果）的统计值。样本数、平均值、min / max 与标准差
+    /// 是精确的；百分位为近似值：两组的分布各自由已知百分位分段线性插值，取加权混合后的分位点。
+    /// 结果的百分位列表与 a 相同
+    ///
+    /// ```
+    /// use interface_performance_test::LatencyStats;
+    /// use std::time::Duration;
+    ///
+    /// let stats = |ms: std::ops::RangeInclusive<u64>| {
+    ///     LatencyStats::from_samples(ms.map(Duration::from_millis), &[50.0, 99.0]).unwrap()
+    /// };
+    /// let merged = LatencyStats::merge(&stats(1..=40), &stats(41..=100));
+    /// let exact = stats(1..=100);
+    /// assert_eq!(merged.count, 100);
+    /// assert_eq!((merged.min, merged.max), (1.0, 100.0));
+    /// assert!((merged.avg - exact.avg).abs() < 1e-9);
+    /// assert!((merged.std_dev - exact.std_dev).abs() < 1e-9);
+    /// assert!((merged.percentiles[0].1 - exact.percentiles[0].1).abs() < 2.0);
+    /// ```
+    pub fn merge(a: &LatencyStats, b: &LatencyStats) -> LatencyStats {
+        // 没有样本数时（旧版本的结果）按等权合并
+        let (wa, wb) = match a.count + b.count {
+            0 => (1.0, 1.0),
+            _ => (a.count as f64, b.count as f64),
+        };
+        let total = wa + wb;
+        let avg = (wa * a.avg + wb * b.avg) / total;
+        // 总体方差 = 组内方差 + 组均值相对总均值的偏差，按样本数加权
+        let variance = (wa * (a.std_dev.powi(2) + (a.avg - avg).powi(2))
+            + wb * (b.std_dev.powi(2) + (b.avg - avg).powi(2)))
+            / total;
+        let std_dev = variance.sqrt();
+        let (min, max) = (a.min.min(b.min), a.max.max(b.max));
+        let mixed_cdf = |x: f64| (wa * a.cdf(x) + wb * b.cdf(x)) / total;
+        let percentiles = a
+            .percentiles
+            .iter()
+            .map(|&(perc, _)| {
+                // 二分求 mixed_cdf(x) 达到 perc 的最小 x
+                let (mut lo, mut hi) = (min, max);
+                for _ in 0..64 {
+                    let mid = (lo + hi) / 2.0;
+                    if mixed_cdf(mid) < perc / 100.0 {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                (perc, hi)
+            })
+            .collect();
+        LatencyStats {
+            count: a.count + b.count,
+            avg,
+            min,
+            max,
+            std_dev,
+            cv_pct: if avg > 0.0 {
+                std_dev / avg * 100.0
+            } else {
+                0.0
+            },
+            percentiles,
+        }
+    }
+
+    /// 经过 (min, 0)、各百分位和 (max, 1) 分段线性插值得到的累积分布函数
+    fn cdf(&self, x: f64) -> f64 {
+        if x < self.min {
+            return 0.0;
+        }
+        let mut points: Vec<(f64, f64)> = self
+            .percentiles
+            .iter()
+            .map(|&(perc, value)| (value, perc / 100.0))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.push((self.max, 1.0));
+        let mut prev = (self.min, 0.0);
+        for (value, p) in points {
+            if x < value {
+                return prev.1 + (p - prev.1) * (x - prev.0) / (value - prev.0);
+            }
+            prev = (value, p);
+        }
+        1.0
+    }
+
+    pub(crate) fn print(&self, title: &str, colored: bool) {
+        let ms = |v: f64| format!("{:.2} ms", v);
+        println!("\n--- {} ---", title);
+        print_stat("Avg", &ms(self.avg), Style::Plain, colored);
+        print_stat("Min", &ms(self.min), Style::Plain, colored);
+        for &(perc, value) in &self.percentiles {
+            print_stat(
+                &percentile_label(perc),
+                &ms(value),
+                Style::for_percentile(perc),
+                colored,
+            );
+        }
+        print_stat("Max", &ms(self.max), Style::Plain, colored);
+        print_stat("Std Dev", &ms(self.std_dev), Style::Plain, colored);
+        let cv_style = if self.cv_pct > CV_WARN_PCT {
+            Style::Yellow
+        } else {
+            Style::Plain
+        };
+        print_stat("CV%", &format!("{:.2}", self.cv_pct), cv_style, colored);
+    }
+}
+
+/// 单请求生成吞吐的统计值，单位 tokens/s
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThroughputStats {
+    pub avg: f64,
+    pub p50: f64,
+    pub p99: f64,
+}
+
+/// 成功请求的响应体传输量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferStats {
+    pub total_bytes: u64,
+    /// 平均每个响应的字节数
+    pub avg_bytes: f64,
+    /// 按总耗时计算的有效带宽，1 MB = 10^6 字节
+    pub mb_per_sec: f64,
+    /// 平均每个 token 的字节数，没有 token 时为 None
+    pub bytes_per_token: Option<f64>,
+}
+
+impl TransferStats {
+    pub(crate) fn new(
+        total_bytes: u64,
+        tokens: u64,
+        success: usize,
+        total_time: Duration,
+    ) -> Option<Self> {
+        (success > 0).then(|| Self {
+            total_bytes,
+            avg_bytes: total_bytes as f64 / success as f64,
+            mb_per_sec: total_bytes as f64 / 1e6 / total_time.as_secs_f64(),
+            bytes_per_token: (tokens > 0).then(|| total_bytes as f64 / tokens as f64),
+        })
+    }
+
+    fn print(&self) {
+        println!("\n--- Transfer ---");
+        println!("Avg response size: {}", format_bytes(self.avg_bytes));
+        println!(
+            "Total bytes transferred: {}",
+            format_bytes(self.total_bytes as f64)
+        );
+        println!("Effective throughput: {:.3} MB/s", self.mb_per_sec);
+        if let Some(bytes_per_token) = self.bytes_per_token {
+            println!("Bytes per token: {:.1}", bytes_per_token);
+        }
+    }
+}
+
+/// 每个成功响应的 chunk 数；分布呈两极（如有的 1 个、有的上百个）时，
+/// 说明服务端有时没有按流式返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkStats {
+    pub avg: f64,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl ChunkStats {
+    pub(crate) fn new(total: u64, min: u32, max: u32, success: usize) -> Option<Self> {
+        (success > 0).then(|| Self {
+            avg: total as f64 / success as f64,
+            min,
+            max,
+        })
+    }
+
+    fn from_counts(counts: impl Iterator<Item = u32> + Clone, success: usize) -> Option<Self> {
+        Self::new(
+            counts.clone().map(u64::from).sum(),
+            counts.clone().min().unwrap_or(0),
+            counts.max().unwrap_or(0),
+            success,
+        )
+    }
+
+    fn print(&self) {
+        println!("\n--- Chunks ---");
+        println!("Avg chunks/response: {:.1}", self.avg);
+        println!("Min chunks: {}", self.min);
+        println!("Max chunks: {}", self.max);
+    }
+}
+
+/// 服务端报告的 token 数统计，只包含响应中带 usage 的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// 带 usage 的请求数
+    pub requests: usize,
+    pub avg_prompt_tokens: f64,
+    pub avg_completion_tokens: f64,
+    /// 单请求 completion_tokens / 端到端耗时的平均值，按服务端分词器计数，比按 chunk 估算更准确
+    pub server_tokens_per_sec: f64,
+}
+
+impl UsageStats {
+    /// 由各项累计值生成，tps_sum 为单请求 tokens/s 之和
+    pub(crate) fn new(requests: usize, prompt: u64, completion: u64, tps_sum: f64) -> Option<Self> {
+        (requests > 0).then(|| Self {
+            requests,
+            avg_prompt_tokens: prompt as f64 / requests as f64,
+            avg_completion_tokens: completion as f64 / requests as f64,
+            server_tokens_per_sec: tps_sum / requests as f64,
+        })
+    }
+
+    fn from_results(results: &[LatencyResult]) -> Option<Self> {
+        let with_usage: Vec<(TokenUsage, Duration)> = results
+            .iter()
+            .filter_map(|r| Some((r.usage?, r.total)))
+            .collect();
+        Self::new(
+            with_usage.len(),
+            with_usage
+                .iter()
+                .map(|(u, _)| u64::from(u.prompt_tokens))
+                .sum(),
+            with_usage
+                .iter()
+                .map(|(u, _)| u64::from(u.completion_tokens))
+                .sum(),
+            with_usage
+                .iter()
+                .map(|(u, total)| u.completion_tokens as f64 / total.as_secs_f64())
+                .sum(),
+        )
+    }
+
+    fn print(&self, success: usize) {
+        println!("\n--- Server Usage ---");
+        if self.requests < success {
+            println!("Requests with usage: {}/{}", self.requests, success);
+        }
+        println!("Avg prompt tokens: {:.1}", self.avg_prompt_tokens);
+        println!("Avg completion tokens: {:.1}", self.avg_completion_tokens);
+        println!("Server-side tokens/sec: {:.2}", self.server_tokens_per_sec);
+    }
+}
+
+/// TGI 服务端耗时的平均值，单位 ms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTimingStats {
+    /// 带耗时响应头的请求数
+    pub requests: usize,
+    pub avg_inference_ms: Option<f64>,
+    pub avg_queue_ms: Option<f64>,
+    pub avg_prefill_ms: Option<f64>,
+    pub avg_decode_ms: Option<f64>,
+}
+
+impl ServerTimingStats {
+    fn from_results(results: &[LatencyResult]) -> Option<Self> {
+        let mut timing = ServerTimingAccumulator::default();
+        for t in results.iter().filter_map(|r| r.server_timing.as_ref()) {
+            timing.record(t);
+        }
+        timing.finish()
+    }
+
+    /// 与客户端测得的平均 TTFT / 端到端延迟并列打印，便于比较两者的差距
+    fn print(&self, ttft: Option<&LatencyStats>, end_to_end: Option<&LatencyStats>) {
+        println!("\n--- Server Timing (TGI) ---");
+        let rows = [
+            ("Server inference time", self.avg_inference_ms),
+            ("Queue wait time", self.avg_queue_ms),
+            ("Prefill time", self.avg_prefill_ms),
+            ("Decode time", self.avg_decode_ms),
+            ("Client TTFT", ttft.map(|s| s.avg)),
+            ("Client end-to-end", end_to_end.map(|s| s.avg)),
+        ];
+        for (label, value) in rows {
+            if let Some(value) = value {
+                println!("{:<22} {:>10.2} ms", format!("{label}:"), value);
+            }
+        }
+        // TGI 的 inference 时间不含排队，两者之和之外的部分为网络与请求校验等开销
+        if let (Some(inference), Some(end_to_end)) = (self.avg_inference_ms, end_to_end) {
+            println!(
+                "{:<22} {:>10.2} ms",
+                "Client-server gap:",
+                end_to_end.avg - inference - self.avg_queue_ms.unwrap_or(0.0)
+            );
+        }
+    }
+}
+
+/// 按 token 数归一化的吞吐（RPS × 每请求 token 数），用于比较不同 prompt 长度的测试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRates {
+    pub input_tokens_per_sec: Option<f64>,
+    /// 为 true 时按 --input-tokens-estimate 估算，否则来自服务端报告的 usage
+    pub input_estimated: bool,
+    pub output_tokens_per_sec: Option<f64>,
+    pub output_estimated: bool,
+}
+
+impl TokenRates {
+    /// 指定了估算值时以估算值为准，否则使用 usage 的平均值；两者都没有时返回 None
+    pub(crate) fn new(
+        requests_per_sec: f64,
+        usage: Option<&UsageStats>,
+        input_estimate: Option<u32>,
+        output_estimate: Option<u32>,
+    ) -> Option<Self> {
+        let rate = |estimate: Option<u32>, actual: Option<f64>| match (estimate, actual) {
+            (Some(estimate), _) => (Some(requests_per_sec * f64::from(estimate)), true),
+            (None, actual) => (actual.map(|tokens| requests_per_sec * tokens), false),
+        };
+        let (input, input_estimated) = rate(input_estimate, usage.map(|u| u.avg_prompt_tokens));
+        let (output, output_estimated) =
+            rate(output_estimate, usage.map(|u| u.avg_completion_tokens));
+        (input.is_some() || output.is_some()).then_some(Self {
+            input_tokens_per_sec: input,
+            input_estimated,
+            output_tokens_per_sec: output,
+            output_estimated,
+        })
+    }
+
+    fn print(&self) {
+        println!("\n--- Token Rates ---");
+        let qualifier = |estimated: bool| if estimated { " (estimated)" } else { "" };
+        if let Some(input) = self.input_tokens_per_sec {
+            println!(
+                "Effective input tokens/sec: {:.2}{}",
+                input,
+                qualifier(self.input_estimated)
+            );
+        }
+        if let Some(output) = self.output_tokens_per_sec {
+            println!(
+                "Effective output tokens/sec: {:.2}{}",
+                output,
+                qualifier(self.output_estimated)
+            );
+        }
+    }
+}
+
+/// 以 B / KB / MB / GB（1000 进制）显示字节数
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} B", value)
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+/// 单个 worker 的成功请求统计，单位 ms
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStats {
+    pub worker_id: usize,
+    pub requests: usize,
+    pub avg_ttft: f64,
+    pub avg_total: f64,
+}
+
+/// --url-list 中单个 URL 的统计，延迟单位 ms，没有成功请求时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlStats {
+    pub url: String,
+    /// 成功请求数
+    pub requests: usize,
+    pub errors: usize,
+    pub avg_ttft: Option<f64>,
+    pub p99_ttft: Option<f64>,
+    pub avg_total: Option<f64>,
+}
+
+impl UrlStats {
+    /// 按 `LatencyResult::url_index` 分组，errors 为每个 URL 的失败数
+    pub(crate) fn from_results(
+        urls: &[String],
+        results: &[LatencyResult],
+        errors: &BTreeMap<usize, usize>,
+    ) -> Vec<Self> {
+        let mut ttfts: Vec<Vec<f64>> = vec![Vec::new(); urls.len()];
+        let mut totals = vec![0.0; urls.len()];
+        for r in results.iter().filter(|r| r.url_index < urls.len()) {
+            ttfts[r.url_index].push(r.ttft.as_secs_f64() * 1000.0);
+            totals[r.url_index] += r.total.as_secs_f64() * 1000.0;
+        }
+        urls.iter()
+            .zip(ttfts)
+            .zip(totals)
+            .enumerate()
+            .map(|(idx, ((url, mut ttft), total))| {
+                ttft.sort_unstable_by(f64::total_cmp);
+                let requests = ttft.len();
+                let non_empty = requests > 0;
+                Self {
+                    url: url.clone(),
+                    requests,
+                    errors: errors.get(&idx).copied().unwrap_or(0),
+                    avg_ttft: non_empty.then(|| ttft.iter().sum::<f64>() / requests as f64),
+                    p99_ttft: non_empty.then(|| percentile(&ttft, 0.99)),
+                    avg_total: non_empty.then(|| total / requests as f64),
+                }
+            })
+            .collect()
+    }
+}
+
+/// 一个 --extract 表达式在所有成功请求中取到的值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractStats {
+    pub path: String,
+    /// 取到非 null 值的请求数
+    pub found: usize,
+    /// 没有匹配或值为 null 的请求数
+    pub missing: usize,
+    /// 数值的统计，没有数值时为 None
+    pub numeric: Option<NumericSummary>,
+    /// 出现次数最多的 5 个非数值（字符串按原文，其余按 JSON）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_values: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumericSummary {
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ExtractStats {
+    pub(crate) fn from_results(paths: &[JsonPath], results: &[LatencyResult]) -> Vec<Self> {
+        paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let mut missing = 0;
+                let mut numbers = Vec::new();
+                let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+                for value in results
+                    .iter()
+                    .map(|r| r.extracted.get(i).cloned().flatten())
+                {
+                    match value {
+                        None | Some(serde_json::Value::Null) => missing += 1,
+                        Some(serde_json::Value::Number(n)) => numbers.extend(n.as_f64()),
+                        Some(serde_json::Value::String(s)) => *counts.entry(s).or_default() += 1,
+                        Some(other) => *counts.entry(other.to_string()).or_default() += 1,
+                    }
+                }
+                let numeric = (!numbers.is_empty()).then(|| NumericSummary {
+                    count: numbers.len(),
+                    mean: numbers.iter().sum::<f64>() / numbers.len() as f64,
+                    min: numbers.iter().copied().fold(f64::INFINITY, f64::min),
+                    max: numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                });
+                let mut top_values: Vec<(String, usize)> = counts.into_iter().collect();
+                // 次数相同时按取值排序，保证输出稳定
+                top_values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                top_values.truncate(5);
+                Self {
+                    path: path.source().to_string(),
+                    found: results.len() - missing,
+                    missing,
+                    numeric,
+                    top_values,
+                }
+            })
+            .collect()
+    }
+
+    fn print(&self) {
+        println!(
+            "{} ({} found, {} missing or null)",
+            self.path, self.found, self.missing
+        );
+        if let Some(n) = &self.numeric {
+            println!("  Mean: {:.2}  Min: {}  Max: {}", n.mean, n.min, n.max);
+        }
+        for (value, count) in &self.top_values {
+            let share = *count as f64 / (self.found + self.missing).max(1) as f64 * 100.0;
+            println!("  {:<40} {:>8} {:>7.1}%", value, count, share);
+        }
+    }
+}
+
+/// 按请求发出时刻划分的一个时间段，延迟单位 ms
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeBucket {
+    /// 相对正式开始时刻的秒数
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub success: usize,
+    pub requests_per_sec: f64,
+    pub ttft_p50: Option<f64>,
+    pub ttft_p99: Option<f64>,
+}
+
+/// 满足某个延迟 SLO 的请求占比，失败的请求计为不达标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloCompliance {
+    pub threshold_ms: f64,
+    pub met: usize,
+    pub total: usize,
+}
+
+impl SloCompliance {
+    pub(crate) fn new(
+        samples: impl Iterator<Item = Duration>,
+        threshold: Duration,
+        total: usize,
+    ) -> Self {
+        Self {
+            threshold_ms: threshold.as_secs_f64() * 1000.0,
+            met: samples.filter(|d| *d <= threshold).count(),
+            total,
+        }
+    }
+
+    /// 达标百分比，没有请求时为 0
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.met as f64 / self.total as f64 * 100.0
+        }
+    }
+
+    fn print(&self, name: &str) {
+        println!(
+            "{} SLO (≤{}ms): {:.1}% compliant ({}/{} requests)",
+            name,
+            self.threshold_ms,
+            self.percent(),
+            self.met,
+            self.total
+        );
+    }
+}
+
+/// 百分位的计算方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuantileEstimator {
+    /// 保留全部样本并排序，结果精确
+    #[default]
+    Exact,
+    /// t-digest 近似估算，内存占用与请求数无关
+    TDigest,
+}
+
+/// 一次压测的全部统计结果，可直接序列化为 JSON（不含逐请求数据），
+/// 也可以从 `--output-file` 保存的 JSON 读回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub total: usize,
+    pub success: usize,
+    pub errors: usize,
+    pub total_time_secs: f64,
+    pub ttft: Option<LatencyStats>,
+    pub end_to_end: Option<LatencyStats>,
+    pub itl: Option<LatencyStats>,
+    pub throughput: Option<ThroughputStats>,
+    /// 成功请求的响应体传输量
+    pub transfer: Option<TransferStats>,
+    /// 每个成功响应的 chunk 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<ChunkStats>,
+    /// --vllm-usage 时服务端报告的 token 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageStats>,
+    /// --tgi-headers 时服务端报告的耗时
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_timing: Option<ServerTimingStats>,
+    /// 按 token 数归一化的吞吐
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_rates: Option<TokenRates>,
+    /// --url-list 时按 URL 分组的统计，只有一个 URL 时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<UrlStats>,
+    pub requests_per_sec: f64,
+    #[serde(default)]
+    pub quantile_estimator: QuantileEstimator,
+    /// 新建连接的耗时（含预热阶段），连接全部复用时为 None
+    pub connect: Option<ConnectSummary>,
+    /// 平均 TTFT 减去 --expected-server-latency，近似为网络与连接开销
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_overhead_ms: Option<f64>,
+    /// --slo-ttft 的达标情况
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo_ttft: Option<SloCompliance>,
+    /// --slo-total 的达标情况
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo_total: Option<SloCompliance>,
+    /// --hdr-histogram 记录的高百分位
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdr: Option<HdrSummary>,
+    /// --soak 模式下的漂移汇总
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soak: Option<SoakSummary>,
+    /// --spike 模式下按阶段划分的统计
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<PhaseResult>,
+    /// 每个 --extract 表达式的取值统计（--streaming-quantiles 下为空）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extractions: Vec<ExtractStats>,
+    /// 按最终 HTTP 状态码统计的请求数（重试只计最后一次）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub status_codes: BTreeMap<u16, usize>,
+    /// 按原因分类的失败请求数，合计等于 `errors`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures: BTreeMap<ErrorKind, usize>,
+    /// 未通过 --response-schema 校验的请求数，未指定模式时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_errors: Option<usize>,
+    /// 未通过 --assert-field / --assert-status 的成功请求数，未指定这些检查时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assertion_failures: Option<usize>,
+    /// 因 --max-body-size 提前停止读取的成功请求数，未设置上限时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated_responses: Option<usize>,
+    /// 是否因 --max-errors 提前中止
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub aborted: bool,
+    /// 是否被外部中断（如 Ctrl-C）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+    /// 是否以 --connection-per-request 运行（每个请求新建连接）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub connection_reuse_disabled: bool,
+    /// 运行标签，来自 `BenchmarkConfig::tags`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 正式计时开始的时刻（Unix 时间戳，秒）
+    #[serde(skip)]
+    pub started_at: f64,
+    /// 每个成功请求的测量结果，按完成顺序排列；使用 t-digest 时为空
+    #[serde(skip)]
+    pub results: Vec<LatencyResult>,
+}
+
+impl BenchmarkResult {
+    pub(crate) fn new(
+        results: Vec<LatencyResult>,
+        total: usize,
+        total_time: Duration,
+        started_at: f64,
+        percentiles: &[f64],
+    ) -> Self {
+        let success = results.len();
+
+        let mut tps: Vec<f64> = results
+            .iter()
+            .map(|r| r.tokens as f64 / r.total.as_secs_f64())
+            .collect();
+        tps.sort_unstable_by(f64::total_cmp);
+        let throughput = (!tps.is_empty()).then(|| ThroughputStats {
+            avg: tps.iter().sum::<f64>() / tps.len() as f64,
+            p50: percentile(&tps, 0.5),
+            p99: percentile(&tps, 0.99),
+        });
+
+        Self {
+            total,
+            success,
+            errors: total - success,
+            total_time_secs: total_time.as_secs_f64(),
+            ttft: LatencyStats::from_samples(results.iter().map(|r| r.ttft), percentiles),
+            end_to_end: LatencyStats::from_samples(results.iter().map(|r| r.total), percentiles),
+            // 单 chunk 响应不贡献 ITL 样本
+            itl: LatencyStats::from_samples(
+                results.iter().flat_map(|r| r.itl.iter().copied()),
+                percentiles,
+            ),
+            throughput,
+            transfer: TransferStats::new(
+                results.iter().map(|r| r.bytes_received).sum(),
+                results.iter().map(|r| u64::from(r.tokens)).sum(),
+                success,
+                total_time,
+            ),
+            chunks: ChunkStats::from_counts(results.iter().map(|r| r.chunks), success),
+            usage: UsageStats::from_results(&results),
+            server_timing: ServerTimingStats::from_results(&results),
+            token_rates: None,
+            urls: Vec::new(),
+            requests_per_sec: success as f64 / total_time.as_secs_f64(),
+            quantile_estimator: QuantileEstimator::Exact,
+            connect: None,
+            connect_overhead_ms: None,
+            slo_ttft: None,
+            slo_total: None,
+            hdr: None,
+            soak: None,
+            phases: Vec::new(),
+            extractions: Vec::new(),
+            status_codes: BTreeMap::new(),
+            failures: BTreeMap::new(),
+            schema_errors: None,
+            assertion_failures: None,
+            truncated_responses: None,
+            aborted: false,
+            interrupted: false,
+            connection_reuse_disabled: false,
+            tags: Vec::new(),
+            started_at,
+            results,
+        }
+    }
+
+    /// 由 --streaming-quantiles 的汇总生成，不含逐请求结果
+    pub(crate) fn from_streaming(
+        mut stats: StreamingStats,
+        total: usize,
+        total_time: Duration,
+        started_at: f64,
+        percentiles: &[f64],
+    ) -> Self {
+        let success = stats.ttft.count();
+        let mut result = Self::new(Vec::new(), total, total_time, started_at, percentiles);
+        result.success = success;
+        result.errors = total - success;
+        result.requests_per_sec = success as f64 / total_time.as_secs_f64();
+        result.quantile_estimator = QuantileEstimator::TDigest;
+        result.ttft = LatencyStats::from_digest(&mut stats.ttft, percentiles);
+        result.end_to_end = LatencyStats::from_digest(&mut stats.total, percentiles);
+        result.itl = LatencyStats::from_digest(&mut stats.itl, percentiles);
+        let tps = &mut stats.throughput;
+        result.throughput = (!tps.is_empty()).then(|| ThroughputStats {
+            avg: tps.avg(),
+            p50: tps.percentile(50.0),
+            p99: tps.percentile(99.0),
+        });
+        result.transfer = TransferStats::new(stats.bytes, stats.tokens, success, total_time);
+        let (min, max) = stats.chunk_range.unwrap_or_default();
+        result.chunks = ChunkStats::new(stats.chunks, min, max, success);
+        result.usage = UsageStats::new(
+            stats.usage_requests,
+            stats.prompt_tokens,
+            stats.completion_tokens,
+            stats.server_tps_sum,
+        );
+        result.server_timing = stats.server_timing.finish();
+        result
+    }
+
+    /// 按 worker_id 分组的统计，没有成功请求的 worker 不出现在结果中
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        let mut by_worker: BTreeMap<usize, (usize, Duration, Duration)> = BTreeMap::new();
+        for r in &self.results {
+            let entry = by_worker.entry(r.worker_id).or_default();
+            entry.0 += 1;
+            entry.1 += r.ttft;
+            entry.2 += r.total;
+        }
+        by_worker
+            .into_iter()
+            .map(|(worker_id, (requests, ttft, total))| WorkerStats {
+                worker_id,
+                requests,
+                avg_ttft: ttft.as_secs_f64() * 1000.0 / requests as f64,
+                avg_total: total.as_secs_f64() * 1000.0 / requests as f64,
+            })
+            .collect()
+    }
+
+    /// 按 (start_ts - started_at) / bucket 分组，返回从 0 开始的连续时间段；
+    /// 正式开始前发出的请求计入第一段
+    pub fn time_buckets(&self, bucket: Duration) -> Vec<TimeBucket> {
+        if bucket.is_zero() {
+            return Vec::new();
+        }
+        let width = bucket.as_secs_f64();
+        let count = (self.total_time_secs / width).ceil().max(1.0) as usize;
+        let mut ttfts: Vec<Vec<Duration>> = vec![Vec::new(); count];
+        for r in &self.results {
+            let idx = ((r.start_ts - self.started_at).max(0.0) / width) as usize;
+            ttfts[idx.min(count - 1)].push(r.ttft);
+        }
+        ttfts
+            .into_iter()
+            .enumerate()
+            .map(|(idx, samples)| {
+                let start_secs = idx as f64 * width;
+                let end_secs = (start_secs + width).min(self.total_time_secs.max(width));
+                let success = samples.len();
+                let stats = LatencyStats::from_samples(samples.into_iter(), &[50.0, 99.0]);
+                TimeBucket {
+                    start_secs,
+                    end_secs,
+                    success,
+                    requests_per_sec: success as f64 / (end_secs - start_secs).max(f64::EPSILON),
+                    ttft_p50: stats.as_ref().map(|s| s.percentiles[0].1),
+                    ttft_p99: stats.as_ref().map(|s| s.percentiles[1].1),
+                }
+            })
+            .collect()
+    }
+
+    /// 逐行打印各时间段的吞吐与 TTFT，观察预热、扩容或 KV cache 饱和等随时间的变化
+    pub fn print_time_buckets(&self, bucket: Duration) {
+        let ms = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.0}ms", v));
+        println!("\n--- Time Buckets ({:?}) ---", bucket);
+        for b in self.time_buckets(bucket) {
+            println!(
+                "t={}-{}s: rps={:.1} ttft_p50={} ttft_p99={}",
+                b.start_secs,
+                b.end_secs.round(),
+                b.requests_per_sec,
+                ms(b.ttft_p50),
+                ms(b.ttft_p99)
+            );
+        }
+    }
+
+    /// 打印每个 worker 的请求数与平均延迟，便于发现负载不均
+    pub fn print_worker_table(&self) {
+        println!("\n--- Per-Worker ---");
+        println!(
+            "{:>6} {:>9} {:>14} {:>14}",
+            "Worker", "Requests", "Mean TTFT", "Mean Total"
+        );
+        for w in self.worker_stats() {
+            println!(
+                "{:>6} {:>9} {:>11.2} ms {:>11.2} ms",
+                w.worker_id, w.requests, w.avg_ttft, w.avg_total
+            );
+        }
+    }
+
+    /// 按目标 URL 分组的统计，用于发现较慢的副本
+    fn print_url_table(&self) {
+        println!("\n--- Per-URL ---");
+        println!(
+            "{:>9} {:>7} {:>14} {:>14} {:>14}  URL",
+            "Requests", "Errors", "Mean TTFT", "P99 TTFT", "Mean Total"
+        );
+        let ms = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.2} ms", v));
+        for u in &self.urls {
+            println!(
+                "{:>9} {:>7} {:>14} {:>14} {:>14}  {}",
+                u.requests,
+                u.errors,
+                ms(u.avg_ttft),
+                ms(u.p99_ttft),
+                ms(u.avg_total),
+                u.url
+            );
+        }
+    }
+
+    /// 状态码分布与失败原因，没有数据的部分不输出
+    fn print_status_codes(&self) {
+        if !self.status_codes.is_empty() {
+            println!("\n--- Status Codes ---");
+            for (code, count) in &self.status_codes {
+                println!("{}: {}", code, count);
+            }
+        }
+        if self.failures.is_empty() {
+            return;
+        }
+        println!("\n--- Failures ---");
+        println!("{:<20} {:>8} {:>8}", "Kind", "Count", "Share");
+        for (kind, count) in &self.failures {
+            println!(
+                "{:<20} {:>8} {:>7.1}%",
+                kind.label(),
+                count,
+                *count as f64 / self.errors.max(1) as f64 * 100.0
+            );
+        }
+    }
+
+    /// 以文本表格打印到 stdout，colored 控制是否输出 ANSI 颜色
+    pub fn print_text(&self, colored: bool) {
+        if self.interrupted {
+            println!(
+                "\n⚠️  Interrupted after {} requests",
+                self.success + self.errors
+            );
+        }
+        println!("\n=== Results ===");
+        if !self.tags.is_empty() {
+            println!("Tags: {}", self.tags.join(", "));
+        }
+        if self.connection_reuse_disabled {
+            println!("Connection reuse: disabled (cold path)");
+        }
+        println!(
+            "Total: {}, Success: {}, Failed: {}",
+            self.total, self.success, self.errors
+        );
+        if let Some(schema_errors) = self.schema_errors {
+            println!("Schema validation errors: {schema_errors}");
+        }
+        if let Some(assertion_failures) = self.assertion_failures {
+            println!("Assertion failures: {assertion_failures}");
+        }
+        if let Some(truncated) = self.truncated_responses {
+            println!("Truncated responses (--max-body-size): {truncated}");
+        }
+        println!(
+            "Total time: {:.2?}",
+            Duration::from_secs_f64(self.total_time_secs)
+        );
+        if self.quantile_estimator == QuantileEstimator::TDigest {
+            println!("Quantiles: t-digest (approximate)");
+        }
+
+        if self.success == 0 {
+            self.print_status_codes();
+            return;
+        }
+        if let Some(ttft) = &self.ttft {
+            ttft.print("TTFT", colored);
+        }
+        if let Some(end_to_end) = &self.end_to_end {
+            end_to_end.print("End-to-End", colored);
+        }
+        if let Some(itl) = &self.itl {
+            itl.print("Inter-Token Latency", colored);
+        }
+        if let Some(throughput) = &self.throughput {
+            println!("\n--- Throughput ---");
+            println!("Avg: {:.2} tokens/s", throughput.avg);
+            println!("P50: {:.2} tokens/s", throughput.p50);
+            println!("P99: {:.2} tokens/s", throughput.p99);
+        }
+        if let Some(transfer) = &self.transfer {
+            transfer.print();
+        }
+        if let Some(chunks) = &self.chunks {
+            chunks.print();
+        }
+        if let Some(usage) = &self.usage {
+            usage.print(self.success);
+        }
+        if let Some(server_timing) = &self.server_timing {
+            server_timing.print(self.ttft.as_ref(), self.end_to_end.as_ref());
+        }
+        if let Some(token_rates) = &self.token_rates {
+            token_rates.print();
+        }
+        if !self.extractions.is_empty() {
+            println!("\n--- Extracted Values ---");
+            for extraction in &self.extractions {
+                extraction.print();
+            }
+        }
+        self.print_status_codes();
+        if !self.urls.is_empty() {
+            self.print_url_table();
+        }
+        if self.connect.is_some() || self.connect_overhead_ms.is_some() {
+            println!("\n--- Connections ---");
+        }
+        if let Some(connect) = &self.connect {
+            println!("New connections: {}", connect.connections);
+            println!("Avg connect time: {:.2} ms", connect.avg_ms);
+            println!("Max connect time: {:.2} ms", connect.max_ms);
+        }
+        if let Some(overhead) = self.connect_overhead_ms {
+            println!("Avg connect overhead: {:.2} ms", overhead);
+        }
+        // 仅作参考，不影响退出码（对比 --assert-*）
+        if self.slo_ttft.is_some() || self.slo_total.is_some() {
+            println!("\n--- SLO ---");
+        }
+        if let Some(slo) = &self.slo_ttft {
+            slo.print("TTFT");
+        }
+        if let Some(slo) = &self.slo_total {
+            slo.print("End-to-End");
+        }
+        if let Some(hdr) = &self.hdr {
+            hdr.print();
+        }
+        if let Some(soak) = &self.soak {
+            soak.print();
+        }
+        for phase in &self.phases {
+            phase.print(colored);
+        }
+
+        println!();
+        print_stat(
+            "Requests/sec",
+            &format!("{:.2}", self.requests_per_sec),
+            Style::Bold,
+            colored,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_keys_round_trip() {
+        let samples = (1..=1000).map(Duration::from_millis);
+        let percentiles = [5.5, 55.0, 99.0, 99.9, 99.99, 100.0];
+        let stats = LatencyStats::from_samples(samples, &percentiles).unwrap();
+
+        let json = serde_json::to_value(&stats).unwrap();
+        for key in ["p5_5", "p55", "p99", "p99_9", "p99_99", "p100"] {
+            assert!(json.get(key).is_some(), "missing {key} in {json}");
+        }
+        let parsed: LatencyStats = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.percentiles, stats.percentiles);
+        assert_eq!(parsed.count, 1000);
+    }
+
+    #[test]
+    fn ignores_unrelated_keys() {
+        let json = serde_json::json!({
+            "avg": 1.0, "min": 1.0, "max": 1.0, "std_dev": 0.0,
+            "p90": 2.0, "p_5": 3.0, "p9_": 4.0, "peak": 5.0, "p1_2_3": 6.0,
+        });
+        let parsed: LatencyStats = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.percentiles, vec![(90.0, 2.0)]);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::BoxError;
+use crate::config::BenchmarkConfig;
+use crate::runner::BenchmarkRunner;
+use crate::stats::{BenchmarkResult, LatencyStats};
+use serde::Serialize;
+use std::time::Duration;
+
+/// 阶梯加压：并发数从 start 开始每步增加 step，直到 max，每步持续 duration
+#[derive(Debug, Clone, Copy)]
+pub struct StepLoad {
+    pub start: usize,
+    pub step: usize,
+    pub max: usize,
+    pub duration: Duration,
+}
+
+/// 阶梯加压中一步的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub concurrency: usize,
+    #[serde(flatten)]
+    pub result: BenchmarkResult,
+}
+
+impl BenchmarkRunner {
+    /// 依次以每个并发级别运行一次独立的压测，每步各自统计
+    pub async fn run_steps(
+        config: BenchmarkConfig,
+        steps: StepLoad,
+        mut on_step: impl FnMut(&StepResult),
+    ) -> Result<Vec<StepResult>, BoxError> {
+        if steps.start == 0 || steps.step == 0 {
+            return Err("step concurrency must be greater than 0".into());
+        }
+        let mut results = Vec::new();
+        let mut concurrency = steps.start;
+        while concurrency <= steps.max {
+            let mut step_config = config.clone();
+            step_config.concurrency = concurrency;
+            step_config.requests = None;
+            step_config.duration = Some(steps.duration);
+            let step = StepResult {
+                concurrency,
+                result: BenchmarkRunner::run(step_config).await?,
+            };
+            on_step(&step);
+            let interrupted = step.result.interrupted;
+            results.push(step);
+            if interrupted {
+                break;
+            }
+            concurrency += steps.step;
+        }
+        Ok(results)
+    }
+}
+
+/// 每步一行的汇总表，便于找出延迟开始恶化的并发级别
+pub fn print_step_table(title: &str, steps: &[StepResult]) {
+    let ms = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.2}", v));
+    println!("\n=== {} ===", title);
+    println!(
+        "{:>12} {:>10} {:>10} {:>16} {:>15}",
+        "Concurrency", "Success", "RPS", "Mean TTFT (ms)", "P99 TTFT (ms)"
+    );
+    for step in steps {
+        // P99 不一定在 --percentiles 中，单独按样本计算
+        let ttft = LatencyStats::from_samples(step.result.results.iter().map(|r| r.ttft), &[99.0]);
+        let ttft = ttft.as_ref();
+        let p99 = ttft.map(|s| s.percentiles[0].1);
+        println!(
+            "{:>12} {:>10} {:>10.2} {:>16} {:>15}",
+            step.concurrency,
+            step.result.success,
+            step.result.requests_per_sec,
+            ms(ttft.map(|s| s.avg)),
+            ms(p99)
+        );
+    }
+}
+
+/// 拐点搜索：并发数从 1 开始翻倍，直到 P99 TTFT 超过目标值，再在最后一个达标值与超标值之间二分
+#[derive(Debug, Clone, Copy)]
+pub struct Breakpoint {
+    /// P99 TTFT 的上限
+    pub latency_target: Duration,
+    /// 每个并发级别完成的请求数（不少于并发数）
+    pub requests: usize,
+    /// 超标后最多进行的二分次数
+    pub iterations: usize,
+}
+
+/// 翻倍阶段的并发上限，防止服务端始终达标时无限加压
+const BREAKPOINT_MAX_CONCURRENCY: usize = 4096;
+
+/// 拐点搜索的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakpointResult {
+    /// P99 TTFT 仍在目标内的最大并发数，并发为 1 时即已超标则为 None
+    pub max_concurrency: Option<usize>,
+    /// 该并发级别下的 Requests/sec
+    pub requests_per_sec: Option<f64>,
+    /// 按执行顺序排列的每次尝试
+    pub steps: Vec<StepResult>,
+}
+
+/// P99 TTFT（ms），没有成功请求时为 None
+fn p99_ttft(result: &BenchmarkResult) -> Option<f64> {
+    LatencyStats::from_samples(result.results.iter().map(|r| r.ttft), &[99.0])
+        .map(|s| s.percentiles[0].1)
+}
+
+impl BenchmarkRunner {
+    /// 搜索 P99 TTFT 不超过目标值的最大并发数
+    pub async fn find_breakpoint(
+        config: BenchmarkConfig,
+        search: Breakpoint,
+        mut on_step: impl FnMut(&StepResult, bool),
+    ) -> Result<BreakpointResult, BoxError> {
+        let target_ms = search.latency_target.as_secs_f64() * 1000.0;
+        let mut steps = Vec::new();
+        let mut probe = async |concurrency: usize, steps: &mut Vec<StepResult>| {
+            let mut step_config = config.clone();
+            step_config.concurrency = concurrency;
+            step_config.requests = Some(search.requests.max(concurrency));
+            step_config.duration = None;
+            let step = StepResult {
+                concurrency,
+                result: BenchmarkRunner::run(step_config).await?,
+            };
+            // 全部失败视为超标
+            let within = step.result.errors == 0
+                && p99_ttft(&step.result).is_some_and(|p99| p99 <= target_ms);
+            on_step(&step, within);
+            let interrupted = step.result.interrupted;
+            steps.push(step);
+            Ok::<_, BoxError>((within, interrupted))
+        };
+
+        // (并发数, 在 steps 中的下标)
+        let mut best: Option<(usize, usize)> = None;
+        let mut over = None;
+        let mut concurrency = 1;
+        while concurrency <= BREAKPOINT_MAX_CONCURRENCY {
+            let (within, interrupted) = probe(concurrency, &mut steps).await?;
+            if interrupted {
+                over = None;
+                break;
+            }
+            if within {
+                best = Some((concurrency, steps.len() - 1));
+                concurrency *= 2;
+            } else {
+                over = Some(concurrency);
+                break;
+            }
+        }
+
+        if let Some(mut hi) = over {
+            let mut lo = best.map_or(0, |(c, _)| c);
+            for _ in 0..search.iterations {
+                if hi - lo <= 1 {
+                    break;
+                }
+                let mid = lo + (hi - lo) / 2;
+                let (within, interrupted) = probe(mid, &mut steps).await?;
+                if interrupted {
+                    break;
+                }
+                if within {
+                    lo = mid;
+                    best = Some((mid, steps.len() - 1));
+                } else {
+                    hi = mid;
+                }
+            }
+        }
+
+        Ok(BreakpointResult {
+            max_concurrency: best.map(|(c, _)| c),
+            requests_per_sec: best.map(|(_, i)| steps[i].result.requests_per_sec),
+            steps,
+        })
+    }
+}
+
+/// 自适应并发：从 1 个 worker 开始，每 interval 加一个，直到 RPS 达到目标或 P95 TTFT 超过上限
+#[derive(Debug, Clone, Copy)]
+pub struct AutoConcurrency {
+    /// 目标 Requests/sec
+    pub target_rps: f64,
+    /// P95 TTFT 的上限，None 为不限
+    pub max_latency: Option<Duration>,
+    /// 每个并发级别的持续时间
+    pub interval: Duration,
+    /// 并发数上限
+    pub max_concurrency: usize,
+}
+
+/// 自适应并发停止的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoStopReason {
+    /// RPS 达到目标
+    TargetReached,
+    /// P95 TTFT 超过上限或全部请求失败
+    LatencyExceeded,
+    /// 达到并发上限仍未达到目标 RPS
+    MaxConcurrency,
+    Interrupted,
+}
+
+impl AutoStopReason {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::TargetReached => "target RPS reached",
+            Self::LatencyExceeded => "P95 TTFT exceeded the latency cap",
+            Self::MaxConcurrency => "maximum concurrency reached below the target RPS",
+            Self::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// 自适应并发的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoConcurrencyResult {
+    /// 延迟仍在上限内时 RPS 最高的并发数，并发为 1 时即已超标则为 None
+    pub optimal_concurrency: Option<usize>,
+    /// 该并发级别下的 Requests/sec
+    pub requests_per_sec: Option<f64>,
+    pub stop_reason: AutoStopReason,
+    /// 按执行顺序排列的每个并发级别
+    pub steps: Vec<StepResult>,
+}
+
+/// P95 TTFT（ms），没有成功请求时为 None
+fn p95_ttft(result: &BenchmarkResult) -> Option<f64> {
+    LatencyStats::from_samples(result.results.iter().map(|r| r.ttft), &[95.0])
+        .map(|s| s.percentiles[0].1)
+}
+
+impl BenchmarkRunner {
+    /// 按加性增长逐步增加并发数，寻找达到目标 RPS 所需的最小并发数（容量规划）
+    pub async fn auto_concurrency(
+        config: BenchmarkConfig,
+        tune: AutoConcurrency,
+        mut on_step: impl FnMut(&StepResult, Option<f64>),
+    ) -> Result<AutoConcurrencyResult, BoxError> {
+        if !(tune.target_rps > 0.0 && tune.target_rps.is_finite()) {
+            return Err("--target-rps must be a positive number".into());
+        }
+        if tune.max_concurrency == 0 {
+            return Err("maximum concurrency must be greater than 0".into());
+        }
+        let cap_ms = tune.max_latency.map(|d| d.as_secs_f64() * 1000.0);
+        let mut steps: Vec<StepResult> = Vec::new();
+        // 延迟达标的最后一步在 steps 中的下标
+        let mut best = None;
+        let mut concurrency = 1;
+        let stop_reason = loop {
+            let mut step_config = config.clone();
+            step_config.concurrency = concurrency;
+            step_config.requests = None;
+            step_config.iterations = None;
+            step_config.duration = Some(tune.interval);
+            let step = StepResult {
+                concurrency,
+                result: BenchmarkRunner::run(step_config).await?,
+            };
+            let p95 = p95_ttft(&step.result);
+            on_step(&step, p95);
+            let interrupted = step.result.interrupted;
+            let rps = step.result.requests_per_sec;
+            steps.push(step);
+            if interrupted {
+                break AutoStopReason::Interrupted;
+            }
+            let over = match (p95, cap_ms) {
+                (None, _) => true,
+                (Some(p95), Some(cap)) => p95 > cap,
+                (Some(_), None) => false,
+            };
+            if over {
+                break AutoStopReason::LatencyExceeded;
+            }
+            best = Some(steps.len() - 1);
+            if rps >= tune.target_rps {
+                break AutoStopReason::TargetReached;
+            }
+            if concurrency >= tune.max_concurrency {
+                break AutoStopReason::MaxConcurrency;
+            }
+            concurrency += 1;
+        };
+
+        Ok(AutoConcurrencyResult {
+            optimal_concurrency: best.map(|i| steps[i].concurrency),
+            requests_per_sec: best.map(|i| steps[i].result.requests_per_sec),
+            stop_reason,
+            steps,
+        })
+    }
+}
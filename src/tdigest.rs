@@ -0,0 +1,286 @@
+use crate::stats::{LatencyResult, ServerTimingAccumulator};
+use std::time::Duration;
+
+/// 压缩参数：质心数量约为 COMPRESSION 的量级，P99 的误差通常在 0.1% 以内
+const COMPRESSION: f64 = 100.0;
+/// 缓冲区满后与已有质心合并一次
+const BUFFER_SIZE: usize = 512;
+
+/// 合并式 t-digest：内存占用与样本数无关，两端分位的精度高于中间
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TDigest {
+    /// (均值, 权重)，按均值升序
+    centroids: Vec<(f64, f64)>,
+    buffer: Vec<f64>,
+    count: f64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl TDigest {
+    pub(crate) fn record(&mut self, x: f64) {
+        if self.count == 0.0 {
+            self.min = x;
+            self.max = x;
+        } else {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+        self.count += 1.0;
+        self.sum += x;
+        self.sum_sq += x * x;
+        self.buffer.push(x);
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.merge();
+        }
+    }
+
+    fn merge(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut points: Vec<(f64, f64)> = self.centroids.drain(..).collect();
+        points.extend(self.buffer.drain(..).map(|x| (x, 1.0)));
+        points.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut merged = Vec::with_capacity(points.len().min(COMPRESSION as usize * 4));
+        let mut points = points.into_iter();
+        let Some(mut current) = points.next() else {
+            return;
+        };
+        let mut weight_before = 0.0;
+        for next in points {
+            let combined = current.1 + next.1;
+            // 质心大小上限 4·N·q·(1-q)/δ：越靠近两端越小
+            let q = (weight_before + combined / 2.0) / self.count;
+            if combined <= 4.0 * self.count * q * (1.0 - q) / COMPRESSION {
+                current.0 += (next.0 - current.0) * next.1 / combined;
+                current.1 = combined;
+            } else {
+                weight_before += current.1;
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// 在相邻质心的中心之间线性插值，q 取值 [0, 1]
+    fn quantile(&mut self, q: f64) -> f64 {
+        self.merge();
+        let target = q * self.count;
+        let mut cumulative = 0.0;
+        let mut prev = (self.min, 0.0);
+        for &(mean, weight) in &self.centroids {
+            let center = cumulative + weight / 2.0;
+            if target < center {
+                let span = center - prev.1;
+                let t = if span > 0.0 {
+                    (target - prev.1) / span
+                } else {
+                    0.0
+                };
+                return prev.0 + (mean - prev.0) * t;
+            }
+            cumulative += weight;
+            prev = (mean, center);
+        }
+        let span = self.count - prev.1;
+        let t = if span > 0.0 {
+            (target - prev.1) / span
+        } else {
+            1.0
+        };
+        prev.0 + (self.max - prev.0) * t.clamp(0.0, 1.0)
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.count == 0.0
+    }
+
+    pub(crate) fn avg(&self) -> f64 {
+        self.sum / self.count
+    }
+
+    pub(crate) fn min(&self) -> f64 {
+        self.min
+    }
+
+    pub(crate) fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// 总体标准差
+    pub(crate) fn std_dev(&self) -> f64 {
+        (self.sum_sq / self.count - self.avg().powi(2))
+            .max(0.0)
+            .sqrt()
+    }
+
+    /// perc 取值 (0, 100)
+    pub(crate) fn percentile(&mut self, perc: f64) -> f64 {
+        self.quantile(perc / 100.0).clamp(self.min, self.max)
+    }
+}
+
+/// --streaming-quantiles 时代替逐请求结果的汇总，延迟单位 ms
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamingStats {
+    pub(crate) ttft: TDigest,
+    pub(crate) total: TDigest,
+    pub(crate) itl: TDigest,
+    /// 单请求生成吞吐，单位 tokens/s
+    pub(crate) throughput: TDigest,
+    pub(crate) bytes: u64,
+    pub(crate) tokens: u64,
+    pub(crate) chunks: u64,
+    /// 单个响应 chunk 数的最小值和最大值
+    pub(crate) chunk_range: Option<(u32, u32)>,
+    /// 带 usage 的请求数及其 token 数、单请求 tokens/s 之和
+    pub(crate) usage_requests: usize,
+    pub(crate) prompt_tokens: u64,
+    pub(crate) completion_tokens: u64,
+    pub(crate) server_tps_sum: f64,
+    pub(crate) server_timing: ServerTimingAccumulator,
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_nanos() as f64 / 1_000_000.0
+}
+
+impl StreamingStats {
+    pub(crate) fn record(&mut self, r: &LatencyResult) {
+        self.ttft.record(ms(r.ttft));
+        self.total.record(ms(r.total));
+        for &itl in &r.itl {
+            self.itl.record(ms(itl));
+        }
+        self.throughput
+            .record(r.tokens as f64 / r.total.as_secs_f64());
+        self.bytes += r.bytes_received;
+        self.tokens += u64::from(r.tokens);
+        self.chunks += u64::from(r.chunks);
+        let (min, max) = self.chunk_range.get_or_insert((r.chunks, r.chunks));
+        *min = (*min).min(r.chunks);
+        *max = (*max).max(r.chunks);
+        if let Some(usage) = r.usage {
+            self.usage_requests += 1;
+            self.prompt_tokens += u64::from(usage.prompt_tokens);
+            self.completion_tokens += u64::from(usage.completion_tokens);
+            self.server_tps_sum += usage.completion_tokens as f64 / r.total.as_secs_f64();
+        }
+        if let Some(timing) = &r.server_timing {
+            self.server_timing.record(timing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 固定种子的 (0, 1) 均匀分布序列
+    fn uniform(n: usize) -> Vec<f64> {
+        let mut state = 0x853c_49e6_748f_ea9b_u64;
+        (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+            })
+            .collect()
+    }
+
+    fn digest(values: &[f64]) -> TDigest {
+        let mut digest = TDigest::default();
+        for &x in values {
+            digest.record(x);
+        }
+        digest
+    }
+
+    /// 估计值在精确排序中的分位与目标分位之差的绝对值
+    fn rank_error(sorted: &[f64], estimate: f64, q: f64) -> f64 {
+        let below = sorted.partition_point(|&x| x < estimate);
+        let at_or_below = sorted.partition_point(|&x| x <= estimate);
+        let n = sorted.len() as f64;
+        // 估计值落在相同取值的区间内时取离目标最近的秩
+        let lo = below as f64 / n;
+        let hi = at_or_below as f64 / n;
+        if q < lo {
+            lo - q
+        } else if q > hi {
+            q - hi
+        } else {
+            0.0
+        }
+    }
+
+    fn check_quantiles(values: &[f64]) {
+        let mut digest = digest(values);
+        let mut sorted = values.to_vec();
+        sorted.sort_unstable_by(f64::total_cmp);
+        for (perc, max_error) in [
+            (1.0, 0.001),
+            (25.0, 0.005),
+            (50.0, 0.005),
+            (75.0, 0.005),
+            (99.0, 0.001),
+            (99.9, 0.0002),
+        ] {
+            let estimate = digest.percentile(perc);
+            let error = rank_error(&sorted, estimate, perc / 100.0);
+            assert!(error <= max_error, "p{perc}: rank error {error}");
+        }
+        assert_eq!(digest.count(), values.len());
+        assert_eq!(digest.min(), sorted[0]);
+        assert_eq!(digest.max(), sorted[sorted.len() - 1]);
+    }
+
+    #[test]
+    fn matches_exact_quantiles_on_uniform_input() {
+        let values: Vec<f64> = uniform(50_000).iter().map(|u| u * 1000.0).collect();
+        check_quantiles(&values);
+    }
+
+    #[test]
+    fn matches_exact_quantiles_on_skewed_input() {
+        // 指数分布的长尾，另有 1% 的离群值
+        let values: Vec<f64> = uniform(50_000)
+            .iter()
+            .enumerate()
+            .map(|(i, u)| {
+                let x = -u.ln() * 20.0;
+                if i % 100 == 0 { x * 50.0 } else { x }
+            })
+            .collect();
+        check_quantiles(&values);
+    }
+
+    #[test]
+    fn single_sample() {
+        let mut digest = digest(&[42.0]);
+        for perc in [0.1, 50.0, 99.9] {
+            assert_eq!(digest.percentile(perc), 42.0);
+        }
+        assert_eq!(digest.avg(), 42.0);
+        assert_eq!(digest.std_dev(), 0.0);
+    }
+
+    #[test]
+    fn empty_digest() {
+        let mut digest = TDigest::default();
+        assert!(digest.is_empty());
+        assert_eq!(digest.count(), 0);
+        // 没有样本时不会 panic，调用方先检查 is_empty
+        assert_eq!(digest.percentile(50.0), 0.0);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::util::{random_u64, random_uuid};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 自定义变量展开的最大层数，超过时视为循环引用
+const MAX_DEPTH: usize = 8;
+
+/// `{{RANDOM_WORD}}` 的候选词
+const WORDS: &[&str] = &[
+    "apple",
+    "river",
+    "mountain",
+    "galaxy",
+    "piano",
+    "volcano",
+    "library",
+    "ocean",
+    "robot",
+    "forest",
+    "bridge",
+    "coffee",
+    "desert",
+    "rainbow",
+    "castle",
+    "engine",
+    "glacier",
+    "harbor",
+    "island",
+    "jungle",
+    "lantern",
+    "meteor",
+    "orchard",
+    "pyramid",
+    "satellite",
+    "thunder",
+    "tunnel",
+    "violin",
+    "whale",
+    "zebra",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    RequestId,
+    WorkerId,
+    TimestampMs,
+    RandomUuid,
+    RandomWord,
+    /// --prompt-csv 中的一行，按 JSON 字符串转义
+    Prompt,
+    /// 闭区间 [min, max]
+    RandomInt(i64, i64),
+}
+
+/// 请求体模板：`{{NAME}}` 在发送每个请求时替换为对应的值
+///
+/// 内置变量：`{{REQUEST_ID}}`（递增序号）、`{{WORKER_ID}}`、`{{TIMESTAMP_MS}}`、
+/// `{{RANDOM_UUID}}`、`{{RANDOM_WORD}}`、`{{RANDOM_INT:1:100}}`，以及 `{{PROMPT}}`：
+/// 按请求序号轮流取 [`with_prompts`](Self::with_prompts) 提供的文本，转义后可直接放在 JSON 字符串中；
+/// 自定义变量（`--var NAME=VALUE`）在解析时展开，其值中也可以引用变量。
+///
+/// ```
+/// use interface_performance_test::BodyTemplate;
+///
+/// let vars = [("MSG".to_string(), "hello {{WORKER_ID}}".to_string())];
+/// let template = r#"{"id": {{REQUEST_ID}}, "msg": "{{MSG}}"}"#;
+/// let template = BodyTemplate::parse(template, &vars).unwrap();
+/// assert_eq!(template.render(7, 2), r#"{"id": 7, "msg": "hello 2"}"#);
+///
+/// assert!(BodyTemplate::parse("{{UNKNOWN}}", &[]).is_err());
+/// ```
+///
+/// 序列化为模板原文、自定义变量与 prompts，反序列化时重新解析。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "TemplateRepr", try_from = "TemplateRepr")]
+pub struct BodyTemplate {
+    source: String,
+    vars: Vec<(String, String)>,
+    segments: Vec<Segment>,
+    prompts: Arc<[String]>,
+}
+
+/// BodyTemplate 的序列化形式
+#[derive(Serialize, Deserialize)]
+struct TemplateRepr {
+    source: String,
+    #[serde(default)]
+    vars: Vec<(String, String)>,
+    #[serde(default)]
+    prompts: Vec<String>,
+}
+
+impl From<BodyTemplate> for TemplateRepr {
+    fn from(template: BodyTemplate) -> Self {
+        Self {
+            source: template.source,
+            vars: template.vars,
+            prompts: template.prompts.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<TemplateRepr> for BodyTemplate {
+    type Error = String;
+
+    fn try_from(repr: TemplateRepr) -> Result<Self, String> {
+        Ok(BodyTemplate::parse(&repr.source, &repr.vars)?.with_prompts(repr.prompts))
+    }
+}
+
+impl BodyTemplate {
+    /// 解析模板并展开自定义变量，未知变量或未闭合的 `{{` 返回错误
+    pub fn parse(template: &str, vars: &[(String, String)]) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        parse_into(template, vars, 0, &mut segments)?;
+        Ok(Self {
+            source: template.to_string(),
+            vars: vars.to_vec(),
+            segments,
+            prompts: Arc::from([]),
+        })
+    }
+
+    /// `{{PROMPT}}` 依次取用的文本，用完后从头开始
+    pub fn with_prompts(mut self, prompts: Vec<String>) -> Self {
+        self.prompts = prompts.into();
+        self
+    }
+
+    /// 模板（含展开后的自定义变量）中是否引用了 `{{PROMPT}}`
+    pub fn uses_prompt(&self) -> bool {
+        self.segments.contains(&Segment::Prompt)
+    }
+
+    /// 未展开的模板原文
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 生成一个请求的请求体，request_id 为全局请求序号
+    pub fn render(&self, request_id: usize, worker_id: usize) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            let _ = match segment {
+                Segment::Literal(s) => out.write_str(s),
+                Segment::RequestId => write!(out, "{request_id}"),
+                Segment::WorkerId => write!(out, "{worker_id}"),
+                Segment::TimestampMs => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default();
+                    write!(out, "{}", now.as_millis())
+                }
+                Segment::RandomUuid => out.write_str(&random_uuid()),
+                Segment::RandomWord => {
+                    out.write_str(WORDS[(random_u64() % WORDS.len() as u64) as usize])
+                }
+                Segment::Prompt if self.prompts.is_empty() => Ok(()),
+                Segment::Prompt => {
+                    let prompt = &self.prompts[request_id % self.prompts.len()];
+                    // 去掉 JSON 字符串两端的引号，引号由模板提供
+                    let quoted = serde_json::Value::from(prompt.as_str()).to_string();
+                    out.write_str(&quoted[1..quoted.len() - 1])
+                }
+                Segment::RandomInt(min, max) => {
+                    let span = max.abs_diff(*min).wrapping_add(1);
+                    // span 为 0 表示覆盖整个 i64 范围
+                    let offset = if span == 0 {
+                        random_u64()
+                    } else {
+                        random_u64() % span
+                    };
+                    write!(out, "{}", min.wrapping_add_unsigned(offset))
+                }
+            };
+        }
+        out
+    }
+}
+
+fn parse_into(
+    template: &str,
+    vars: &[(String, String)],
+    depth: usize,
+    segments: &mut Vec<Segment>,
+) -> Result<(), String> {
+    let literal = |segments: &mut Vec<Segment>, s: &str| {
+        if s.is_empty() {
+            return;
+        }
+        match segments.last_mut() {
+            Some(Segment::Literal(last)) => last.push_str(s),
+            _ => segments.push(Segment::Literal(s.to_string())),
+        }
+    };
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        literal(segments, &rest[..open]);
+        let after = &rest[open + 2..];
+        let close = after
+            .find("}}")
+            .ok_or_else(|| format!("unclosed {{{{ in template: {}", &rest[open..]))?;
+        let name = after[..close].trim();
+        rest = &after[close + 2..];
+
+        if let Some((_, value)) = vars.iter().rev().find(|(var, _)| var == name) {
+            if depth >= MAX_DEPTH {
+                return Err(format!("template variable {name} is defined recursively"));
+            }
+            parse_into(value, vars, depth + 1, segments)?;
+            continue;
+        }
+        let segment = match name {
+            "REQUEST_ID" => Segment::RequestId,
+            "WORKER_ID" => Segment::WorkerId,
+            "TIMESTAMP_MS" => Segment::TimestampMs,
+            "RANDOM_UUID" => Segment::RandomUuid,
+            "RANDOM_WORD" => Segment::RandomWord,
+            "PROMPT" => Segment::Prompt,
+            _ => match name.strip_prefix("RANDOM_INT:") {
+                Some(range) => parse_random_int(range)?,
+                None => return Err(format!("unknown template variable {{{{{name}}}}}")),
+            },
+        };
+        segments.push(segment);
+    }
+    literal(segments, rest);
+    Ok(())
+}
+
+/// 解析 `RANDOM_INT:MIN:MAX` 中的 "MIN:MAX"
+fn parse_random_int(range: &str) -> Result<Segment, String> {
+    let invalid = || format!("invalid {{{{RANDOM_INT:{range}}}}} (expected RANDOM_INT:MIN:MAX)");
+    let (min, max) = range.split_once(':').ok_or_else(invalid)?;
+    let min: i64 = min.trim().parse().map_err(|_| invalid())?;
+    let max: i64 = max.trim().parse().map_err(|_| invalid())?;
+    if min > max {
+        return Err(format!("RANDOM_INT range is empty: {min} > {max}"));
+    }
+    Ok(Segment::RandomInt(min, max))
+}
+
+/// 解析 --var 的 "NAME=VALUE"
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid variable (expected NAME=VALUE): {s}"))?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("invalid variable name: {name:?}"));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
@@ -0,0 +1,549 @@
+//! 集成测试用的本地模拟 LLM 服务（`testing` feature），不依赖真实的推理服务
+
+use crate::config::SseFormat;
+use crate::stats::ErrorKind;
+use crate::transport::{BenchmarkRequest, BenchmarkResponse, Transport, TransportError};
+use crate::util::random_f64;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::{Method, StatusCode, Url};
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// 请求头部分的大小上限，超过时断开连接
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// 模拟错误时的响应体
+const ERROR_BODY: &str = r#"{"error":{"message":"simulated error","type":"server_error"}}"#;
+
+/// [`MockLLMServer`] 的参数
+#[derive(Debug, Clone)]
+pub struct MockLLMServerBuilder {
+    ttft_delay: Duration,
+    chunk_delay: Duration,
+    chunk_count: usize,
+    events_per_chunk: usize,
+    error_rate: f64,
+    error_status: u16,
+    usage: bool,
+    status: u16,
+    body: Option<String>,
+    sse_format: SseFormat,
+    unterminated: bool,
+    max_concurrency: Option<usize>,
+    /// 正在处理的请求数，每个服务或 transport 各自计数
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl MockLLMServerBuilder {
+    /// 发出响应头到第一个事件之间的等待时间，默认为 0
+    pub fn ttft_delay(&mut self, delay: Duration) -> &mut Self {
+        self.ttft_delay = delay;
+        self
+    }
+
+    /// 相邻两个事件之间的等待时间，默认为 0
+    pub fn chunk_delay(&mut self, delay: Duration) -> &mut Self {
+        self.chunk_delay = delay;
+        self
+    }
+
+    /// 每个响应中的内容事件数（不含 usage 事件和 `[DONE]`），默认为 5
+    pub fn chunk_count(&mut self, count: usize) -> &mut Self {
+        self.chunk_count = count;
+        self
+    }
+
+    /// 合并到同一个数据块中一起发出的事件数，默认为 1（每个事件单独发送）
+    pub fn events_per_chunk(&mut self, count: usize) -> &mut Self {
+        self.events_per_chunk = count.max(1);
+        self
+    }
+
+    /// 以该概率返回 error_status 而不是事件流，取值 [0, 1]，默认为 0
+    pub fn error_rate(&mut self, rate: f64) -> &mut Self {
+        self.error_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 在 `[DONE]` 前发送 `choices` 为空、带 usage 的事件（同 vLLM 的
+    /// `stream_options.include_usage`），默认不发送
+    pub fn usage(&mut self, enabled: bool) -> &mut Self {
+        self.usage = enabled;
+        self
+    }
+
+    /// 模拟错误时的状态码，默认为 500
+    pub fn error_status(&mut self, status: u16) -> &mut Self {
+        self.error_status = status;
+        self
+    }
+
+    /// 非模拟错误时响应的状态码，默认为 200；非 2xx 时响应体仍为事件流
+    pub fn status(&mut self, status: u16) -> &mut Self {
+        self.status = status;
+        self
+    }
+
+    /// 以 body 作为整个响应体、在 ttft_delay 后一次发出，代替生成的事件流
+    pub fn body(&mut self, body: impl Into<String>) -> &mut Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// 生成的事件流格式，默认为 OpenAI
+    pub fn sse_format(&mut self, format: SseFormat) -> &mut Self {
+        self.sse_format = format;
+        self
+    }
+
+    /// 发完响应体后既不结束 chunked 响应也不关闭连接，客户端只能依靠结束事件判断响应完成
+    pub fn unterminated(&mut self, enabled: bool) -> &mut Self {
+        self.unterminated = enabled;
+        self
+    }
+
+    /// 同时处理的请求超过 max 个时，多出的请求立即返回 error_status（模拟过载），默认不限制。
+    /// 请求在最后一个数据块发出前即结束计数，客户端收到完整响应后发出的下一个请求不会被误判
+    pub fn max_concurrency(&mut self, max: usize) -> &mut Self {
+        self.max_concurrency = Some(max);
+        self
+    }
+
+    /// 在 127.0.0.1 的随机端口上启动服务
+    pub async fn build(&self) -> io::Result<MockLLMServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let options = Arc::new(self.with_own_counter());
+        let task = tokio::spawn({
+            let bodies = bodies.clone();
+            async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(serve_connection(socket, options.clone(), bodies.clone()));
+                }
+            }
+        });
+        Ok(MockLLMServer { addr, bodies, task })
+    }
+
+    /// 以同样的参数在进程内生成响应的 [`MockTransport`]，不经过网络
+    pub fn transport(&self) -> MockTransport {
+        MockTransport {
+            options: Arc::new(self.with_own_counter()),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn with_own_counter(&self) -> Self {
+        Self {
+            in_flight: Arc::default(),
+            ..self.clone()
+        }
+    }
+
+    /// 开始处理一个请求，返回计数守卫及本次是否模拟错误（随机错误或过载）
+    fn begin_request(&self) -> (InFlight, bool) {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        let overloaded = self.max_concurrency.is_some_and(|max| in_flight > max);
+        let error = overloaded || (self.error_rate > 0.0 && random_f64() < self.error_rate);
+        (InFlight(self.in_flight.clone()), error)
+    }
+
+    /// 一个成功响应的全部 SSE 事件及发送前的等待时间。OpenAI 格式依次为内容事件、
+    /// usage 事件（可选）和 `[DONE]`；Anthropic 格式为 `message_start`、`content_block_delta`、
+    /// 带 usage 的 `message_delta`（可选）和 `message_stop`。内容事件之外的事件不等待
+    fn sse_events(&self) -> Vec<(Duration, String)> {
+        let n = self.chunk_count;
+        let anthropic = self.sse_format == SseFormat::Anthropic;
+        let mut events = Vec::new();
+        if anthropic {
+            events.push((
+                Duration::ZERO,
+                "event: message_start\n\
+                 data: {\"type\":\"message_start\",\"message\":{\"model\":\"mock\"}}\n\n"
+                    .to_string(),
+            ));
+        }
+        for i in 0..n {
+            let delay = if i == 0 {
+                self.ttft_delay
+            } else {
+                self.chunk_delay
+            };
+            let event = if anthropic {
+                format!(
+                    "event: content_block_delta\n\
+                     data: {{\"type\":\"content_block_delta\",\"index\":0,\
+                     \"delta\":{{\"type\":\"text_delta\",\"text\":\"tok{i}\"}}}}\n\n"
+                )
+            } else {
+                format!(
+                    "data: {{\"object\":\"chat.completion.chunk\",\"model\":\"mock\",\
+                     \"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"tok{i}\"}}}}]}}\n\n"
+                )
+            };
+            events.push((delay, event));
+        }
+        if self.usage {
+            let event = if anthropic {
+                format!(
+                    "event: message_delta\n\
+                     data: {{\"type\":\"message_delta\",\"usage\":{{\"output_tokens\":{n}}}}}\n\n"
+                )
+            } else {
+                format!(
+                    "data: {{\"object\":\"chat.completion.chunk\",\"model\":\"mock\",\
+                     \"choices\":[],\"usage\":{{\"prompt_tokens\":8,\"completion_tokens\":{n},\
+                     \"total_tokens\":{}}}}}\n\n",
+                    n + 8
+                )
+            };
+            events.push((Duration::ZERO, event));
+        }
+        let end = if anthropic {
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"
+        } else {
+            "data: [DONE]\n\n"
+        };
+        events.push((Duration::ZERO, end.to_string()));
+        events
+    }
+
+    /// 成功响应依次发出的数据块及发送前的等待时间：指定了 body 时为整个 body，
+    /// 否则每块含 events_per_chunk 个事件，等待时间为块内各事件的等待时间之和
+    fn response_chunks(&self) -> Vec<(Duration, String)> {
+        if let Some(body) = &self.body {
+            return vec![(self.ttft_delay, body.clone())];
+        }
+        self.sse_events()
+            .chunks(self.events_per_chunk)
+            .map(|chunk| {
+                let delay = chunk.iter().map(|(delay, _)| *delay).sum();
+                let data = chunk.iter().map(|(_, event)| event.as_str()).collect();
+                (delay, data)
+            })
+            .collect()
+    }
+}
+
+/// 兼容 OpenAI chat completions 流式接口的模拟服务：每个请求返回 `chunk_count` 个
+/// `choices[].delta.content` 事件和 `data: [DONE]`（HEAD 请求只返回空的 200），
+/// 也可改为 Anthropic 格式或固定的响应体；连接保持复用，服务在 drop 时停止
+///
+/// ```
+/// use interface_performance_test::{BenchmarkBuilder, BenchmarkRunner, MockLLMServer};
+/// use std::time::Duration;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let server = MockLLMServer::builder()
+///     .ttft_delay(Duration::from_millis(20))
+///     .chunk_count(3)
+///     .build()
+///     .await
+///     .unwrap();
+/// let mut builder = BenchmarkBuilder::new(server.url());
+/// builder.body("{}").requests(4).sse(true);
+/// let result = BenchmarkRunner::run(builder.build().unwrap()).await.unwrap();
+/// assert_eq!(result.success, 4);
+/// assert!(result.results.iter().all(|r| r.tokens == 3));
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct MockLLMServer {
+    addr: SocketAddr,
+    bodies: Arc<Mutex<Vec<String>>>,
+    task: JoinHandle<()>,
+}
+
+impl MockLLMServer {
+    pub fn builder() -> MockLLMServerBuilder {
+        MockLLMServerBuilder {
+            ttft_delay: Duration::ZERO,
+            chunk_delay: Duration::ZERO,
+            chunk_count: 5,
+            events_per_chunk: 1,
+            error_rate: 0.0,
+            error_status: 500,
+            usage: false,
+            status: 200,
+            body: None,
+            sse_format: SseFormat::OpenAi,
+            unterminated: false,
+            max_concurrency: None,
+            in_flight: Arc::default(),
+        }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// chat completions 接口的地址，服务对任意路径的响应相同
+    pub fn url(&self) -> String {
+        format!("http://{}/v1/chat/completions", self.addr)
+    }
+
+    /// 到目前为止收到的请求体，按到达顺序排列
+    pub fn received_bodies(&self) -> Vec<String> {
+        self.bodies.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockLLMServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 依次处理同一连接上的请求，直到客户端关闭连接或请求无法解析
+async fn serve_connection(
+    mut socket: TcpStream,
+    options: Arc<MockLLMServerBuilder>,
+    bodies: Arc<Mutex<Vec<String>>>,
+) {
+    let mut buf = Vec::new();
+    loop {
+        let Some((method, body)) = read_request(&mut socket, &mut buf).await else {
+            return;
+        };
+        if method != "HEAD" {
+            bodies.lock().unwrap().push(body);
+        }
+        if respond(&mut socket, &method, &options).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// 读取一个请求，返回方法与请求体；buf 中保留已读到的下一个请求的数据
+async fn read_request(socket: &mut TcpStream, buf: &mut Vec<u8>) -> Option<(String, String)> {
+    let header_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() > MAX_HEADER_SIZE || !read_more(socket, buf).await {
+            return None;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let method = head.split(' ').next()?.to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        if !read_more(socket, buf).await {
+            return None;
+        }
+    }
+    let body = String::from_utf8_lossy(&buf[header_end..header_end + content_length]).into_owned();
+    buf.drain(..header_end + content_length);
+    Some((method, body))
+}
+
+async fn read_more(socket: &mut TcpStream, buf: &mut Vec<u8>) -> bool {
+    let mut chunk = [0u8; 4096];
+    match socket.read(&mut chunk).await {
+        Ok(0) | Err(_) => false,
+        Ok(n) => {
+            buf.extend_from_slice(&chunk[..n]);
+            true
+        }
+    }
+}
+
+async fn respond(
+    socket: &mut TcpStream,
+    method: &str,
+    options: &MockLLMServerBuilder,
+) -> io::Result<()> {
+    if method == "HEAD" {
+        return socket
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .await;
+    }
+    let (in_flight, error) = options.begin_request();
+    if error {
+        drop(in_flight);
+        let response = format!(
+            "HTTP/1.1 {} Error\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            options.error_status,
+            ERROR_BODY.len()
+        );
+        socket.write_all(response.as_bytes()).await?;
+        return socket.write_all(ERROR_BODY.as_bytes()).await;
+    }
+
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: text/event-stream\r\n\
+         Transfer-Encoding: chunked\r\n\r\n",
+        options.status
+    );
+    socket.write_all(head.as_bytes()).await?;
+    let chunks = options.response_chunks();
+    let last = chunks.len() - 1;
+    let mut in_flight = Some(in_flight);
+    for (i, (delay, chunk)) in chunks.into_iter().enumerate() {
+        tokio::time::sleep(delay).await;
+        if i == last {
+            in_flight.take();
+        }
+        write_chunk(socket, &chunk).await?;
+    }
+    if options.unterminated {
+        return std::future::pending().await;
+    }
+    socket.write_all(b"0\r\n\r\n").await
+}
+
+/// 正在处理的请求计数，drop 时减一
+#[derive(Debug)]
+struct InFlight(Arc<AtomicUsize>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 以 chunked 编码写出一段数据并立即发送
+async fn write_chunk(socket: &mut TcpStream, data: &str) -> io::Result<()> {
+    socket
+        .write_all(format!("{:x}\r\n{data}\r\n", data.len()).as_bytes())
+        .await?;
+    socket.flush().await
+}
+
+/// 在进程内模拟 [`MockLLMServer`] 的 [`Transport`]：不建立连接，响应内容与延迟和模拟服务相同，
+/// 由 [`MockLLMServerBuilder::transport`] 创建
+///
+/// ```
+/// use interface_performance_test::{BenchmarkBuilder, BenchmarkRunner, MockLLMServer};
+/// use std::time::Duration;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let transport = MockLLMServer::builder().ttft_delay(Duration::from_millis(20)).transport();
+/// let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+/// builder.body("{}").requests(4).sse(true);
+/// let config = builder.build().unwrap();
+/// let result = BenchmarkRunner::run_with_transport(config, transport.clone()).await.unwrap();
+/// assert_eq!(result.success, 4);
+/// assert!(result.ttft.unwrap().min >= 20.0);
+/// assert!(transport.received_bodies().len() >= 4);
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockTransport {
+    options: Arc<MockLLMServerBuilder>,
+    requests: Arc<Mutex<Vec<BenchmarkRequest>>>,
+}
+
+impl MockTransport {
+    /// 到目前为止收到的请求（不含预热的 HEAD 请求），按发送顺序排列
+    pub fn received_requests(&self) -> Vec<BenchmarkRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 到目前为止收到的请求体，按发送顺序排列
+    pub fn received_bodies(&self) -> Vec<String> {
+        let requests = self.requests.lock().unwrap();
+        requests
+            .iter()
+            .map(|req| req.body.clone().unwrap_or_default())
+            .collect()
+    }
+}
+
+impl Transport for MockTransport {
+    async fn send_request(
+        &self,
+        req: BenchmarkRequest,
+    ) -> Result<BenchmarkResponse, TransportError> {
+        let url = Url::parse(&req.url)
+            .map_err(|e| TransportError::new(ErrorKind::Unknown, format!("invalid URL: {e}")))?;
+        type Chunk = BoxFuture<'static, Result<Bytes, TransportError>>;
+        let response = |status: StatusCode, content_type: &'static str, chunks: Vec<Chunk>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            BenchmarkResponse {
+                status,
+                headers,
+                url: url.clone(),
+                body: futures::stream::iter(chunks).then(|chunk| chunk).boxed(),
+            }
+        };
+        if req.method == Method::HEAD {
+            return Ok(response(StatusCode::OK, "text/plain", Vec::new()));
+        }
+        self.requests.lock().unwrap().push(req);
+
+        let options = &self.options;
+        let (in_flight, error) = options.begin_request();
+        if error {
+            let status = StatusCode::from_u16(options.error_status)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let body = futures::future::ready(Ok(Bytes::from_static(ERROR_BODY.as_bytes())));
+            return Ok(response(status, "application/json", vec![body.boxed()]));
+        }
+        let chunks = options.response_chunks();
+        let last = chunks.len() - 1;
+        let mut in_flight = Some(in_flight);
+        let chunks = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (delay, chunk))| {
+                let in_flight = if i == last { in_flight.take() } else { None };
+                async move {
+                    tokio::time::sleep(delay).await;
+                    drop(in_flight);
+                    Ok(Bytes::from(chunk))
+                }
+                .boxed()
+            })
+            .collect();
+        let status = StatusCode::from_u16(options.status).unwrap_or(StatusCode::OK);
+        let mut response = response(status, "text/event-stream", chunks);
+        if options.unterminated {
+            response.body = response.body.chain(futures::stream::pending()).boxed();
+        }
+        Ok(response)
+    }
+}
+
+/// 测试用的临时文件路径，位于系统临时目录、按进程区分；drop 时删除该文件（断言失败时也会删除）
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// 只生成路径，不创建文件
+    pub fn new(name: &str) -> Self {
+        Self {
+            path: std::env::temp_dir()
+                .join(format!("interface-perf-test-{}-{name}", std::process::id())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
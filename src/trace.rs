@@ -0,0 +1,113 @@
+use crate::har::{MAX_RECORDED_BODY, RecordedRequest, RecordedResponse, format_iso8601};
+use crate::util::write_atomic;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// --trace-requests：以类似 curl -v 的格式记录前 limit 个请求的完整内容，
+/// 发送的行以 `> ` 开头，收到的行以 `< ` 开头，耗时与错误以 `* ` 开头
+#[derive(Debug)]
+pub(crate) struct RequestTracer {
+    limit: usize,
+    traces: Mutex<Vec<(usize, String)>>,
+}
+
+impl RequestTracer {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            traces: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 是否记录该全局请求序号的请求
+    pub(crate) fn wants(&self, request_id: usize) -> bool {
+        request_id < self.limit
+    }
+
+    pub(crate) fn record(
+        &self,
+        request_id: usize,
+        worker_id: usize,
+        start_ts: f64,
+        request: &RecordedRequest,
+        response: &RecordedResponse,
+    ) {
+        if !self.wants(request_id) {
+            return;
+        }
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "=== Request {request_id} (worker {worker_id}) at {} ===",
+            format_iso8601(start_ts)
+        );
+        let _ = writeln!(out, "> {} {} HTTP/1.1", request.method, request.url);
+        for (name, value) in &request.headers {
+            let _ = writeln!(out, "> {name}: {value}");
+        }
+        out.push_str(">\n");
+        if let Some(body) = &request.body {
+            write_body(&mut out, "> ", body.as_bytes());
+        }
+
+        if response.status == 0 {
+            out.push_str("* no response received\n");
+        } else {
+            let reason = reqwest::StatusCode::from_u16(response.status)
+                .ok()
+                .and_then(|s| s.canonical_reason())
+                .unwrap_or("");
+            let _ = writeln!(out, "< HTTP/1.1 {} {reason}", response.status);
+            for (name, value) in &response.headers {
+                let _ = writeln!(out, "< {name}: {value}");
+            }
+            out.push_str("<\n");
+            write_body(&mut out, "< ", response.body);
+        }
+        let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        let _ = writeln!(
+            out,
+            "* wait {:.2} ms, receive {:.2} ms, total {:.2} ms",
+            ms(response.wait),
+            ms(response.receive),
+            ms(response.wait + response.receive)
+        );
+        if let Some(error) = &response.error {
+            let _ = writeln!(out, "* error: {error}");
+        }
+        self.traces.lock().unwrap().push((request_id, out));
+    }
+
+    /// 按请求序号排序后写入 path，返回写入的请求数
+    pub(crate) fn write(&self, path: &Path) -> Result<usize, String> {
+        let mut traces = std::mem::take(&mut *self.traces.lock().unwrap());
+        traces.sort_by_key(|(request_id, _)| *request_id);
+        let text = traces
+            .iter()
+            .map(|(_, trace)| trace.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        write_atomic(path, text)
+            .map_err(|e| format!("failed to write --trace-file {}: {e}", path.display()))?;
+        Ok(traces.len())
+    }
+}
+
+/// 逐行加前缀写出请求体 / 响应体，超过 MAX_RECORDED_BODY 的部分截断
+fn write_body(out: &mut String, prefix: &str, body: &[u8]) {
+    let recorded = &body[..body.len().min(MAX_RECORDED_BODY)];
+    for line in String::from_utf8_lossy(recorded).lines() {
+        let _ = match line {
+            "" => writeln!(out, "{}", prefix.trim_end()),
+            line => writeln!(out, "{prefix}{line}"),
+        };
+    }
+    if recorded.len() < body.len() {
+        let _ = writeln!(
+            out,
+            "* body truncated to {MAX_RECORDED_BODY} of {} bytes",
+            body.len()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use crate::BoxError;
+use crate::config::BenchmarkConfig;
+use crate::connect::{ConnectStats, ConnectTimingLayer};
+use crate::stats::ErrorKind;
+use bytes::Bytes;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method, StatusCode, Url};
+use std::future::Future;
+use std::sync::Arc;
+
+/// 发往被测服务的一个请求，请求头中已包含 traceparent、Cookie 等按请求生成的值
+#[derive(Debug, Clone)]
+pub struct BenchmarkRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+}
+
+/// 被测服务的响应：收到状态码和响应头即返回，响应体按到达的数据块逐块读取
+pub struct BenchmarkResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// 最终的 URL（跟随重定向后），Set-Cookie 按它记录
+    pub url: Url,
+    pub body: BoxStream<'static, Result<Bytes, TransportError>>,
+}
+
+impl BenchmarkResponse {
+    /// 读完整个响应体
+    pub async fn bytes(mut self) -> Result<Vec<u8>, TransportError> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.body.next().await {
+            body.extend_from_slice(&chunk?);
+        }
+        Ok(body)
+    }
+}
+
+/// 未收到响应或读取响应体失败，kind 决定该请求计入哪一类失败
+#[derive(Debug, Clone)]
+pub struct TransportError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl TransportError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::new(ErrorKind::from_error(&e), e.to_string())
+    }
+}
+
+/// 压测的传输层：runner 负责调度、重试、计时与统计，transport 只负责发出请求并返回响应流。
+///
+/// 默认的 [`HttpTransport`] 基于 reqwest；为 gRPC、WebSocket 等协议实现该 trait 后
+/// 交给 [`BenchmarkRunner::run_with_transport`](crate::BenchmarkRunner::run_with_transport)，
+/// 即可复用全部测量逻辑。响应体中的每个数据块在非 SSE 模式下计为一个 token 事件。
+pub trait Transport: Send + Sync + 'static {
+    fn send_request(
+        &self,
+        req: BenchmarkRequest,
+    ) -> impl Future<Output = Result<BenchmarkResponse, TransportError>> + Send;
+}
+
+/// 基于 reqwest 的 HTTP/1.1 传输层，连接池、代理、超时、重定向等按 [`BenchmarkConfig`] 设置
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    client: Client,
+    /// --connection-per-request 时每个请求按该配置新建客户端
+    cold: Option<(Arc<BenchmarkConfig>, Arc<ConnectStats>)>,
+}
+
+impl HttpTransport {
+    /// https URL（未启用 TLS 后端）或代理地址无效时返回错误
+    pub fn new(config: &BenchmarkConfig) -> Result<Self, BoxError> {
+        Self::with_stats(config, Arc::default())
+    }
+
+    /// 新建连接的耗时记入 connect_stats
+    pub(crate) fn with_stats(
+        config: &BenchmarkConfig,
+        connect_stats: Arc<ConnectStats>,
+    ) -> Result<Self, BoxError> {
+        // reqwest 未启用任何 TLS 后端（default-features = false），https 请求只会不断失败，
+        // 客户端证书、自定义 CA 等 TLS 配置也无从设置，启动时直接报错
+        let mut urls = std::iter::once(&config.url).chain(&config.urls);
+        if let Some(url) = urls.find(|url| url.starts_with("https://")) {
+            return Err(format!(
+                "https URLs are not supported: this build has no TLS backend enabled ({url})"
+            )
+            .into());
+        }
+        // reqwest 的错误只有 "builder error"，这里带上代理地址
+        if let Some(proxy) = &config.proxy {
+            proxy
+                .to_proxy()
+                .map_err(|e| format!("invalid proxy URL {:?}: {e}", proxy.url))?;
+        }
+        // --connection-per-request 时每个请求另建客户端，这里仍构建一次以便尽早发现配置错误
+        let client = build_client(config, &connect_stats)?;
+        Ok(Self {
+            client,
+            cold: config
+                .connection_per_request
+                .then(|| (Arc::new(config.clone()), connect_stats)),
+        })
+    }
+}
+
+impl Transport for HttpTransport {
+    async fn send_request(
+        &self,
+        req: BenchmarkRequest,
+    ) -> Result<BenchmarkResponse, TransportError> {
+        // 新客户端没有连接池，也不缓存 DNS，每个请求都重新解析、建连
+        let cold_client;
+        let client = match &self.cold {
+            Some((config, connect_stats)) => {
+                cold_client = build_client(config, connect_stats)?;
+                &cold_client
+            }
+            None => &self.client,
+        };
+        // 注意：不设置 Accept 头（适配 TGI/vLLM）
+        let mut builder = client.request(req.method, &req.url).headers(req.headers);
+        if let Some(body) = req.body {
+            builder = builder.body(body);
+        }
+        let resp = builder.send().await?;
+        Ok(BenchmarkResponse {
+            status: resp.status(),
+            headers: resp.headers().clone(),
+            url: resp.url().clone(),
+            body: resp
+                .bytes_stream()
+                .map(|chunk| chunk.map_err(TransportError::from))
+                .boxed(),
+        })
+    }
+}
+
+/// 按配置构建 HTTP 客户端，建连耗时记入 connect_stats
+fn build_client(
+    config: &BenchmarkConfig,
+    connect_stats: &Arc<ConnectStats>,
+) -> reqwest::Result<Client> {
+    let mut client_builder = Client::builder()
+        .user_agent(&config.user_agent)
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .connector_layer(ConnectTimingLayer {
+            stats: connect_stats.clone(),
+        });
+    if let Some(proxy) = &config.proxy {
+        client_builder = client_builder.proxy(proxy.to_proxy()?);
+    }
+    if let Some(family) = config.ip_family {
+        client_builder = client_builder.local_address(family.unspecified());
+    }
+    // 连接池中不保留空闲连接即不复用连接
+    let max_idle = if config.keepalive {
+        config.pool_max_idle_per_host
+    } else {
+        Some(0)
+    };
+    if let Some(max_idle) = max_idle {
+        client_builder = client_builder.pool_max_idle_per_host(max_idle);
+    }
+    if !config.keepalive {
+        client_builder = client_builder.tcp_keepalive(None);
+    } else if let Some(interval) = config.tcp_keepalive {
+        client_builder = client_builder.tcp_keepalive(interval);
+    }
+    client_builder = client_builder.tcp_nodelay(config.tcp_nodelay);
+    if let Some(timeout) = config.pool_idle_timeout {
+        client_builder = client_builder.pool_idle_timeout(timeout);
+    }
+    client_builder = client_builder.redirect(if config.follow_redirects {
+        Policy::limited(config.max_redirects)
+    } else {
+        Policy::none()
+    });
+    client_builder.build()
+}
@@ -0,0 +1,207 @@
+use crate::progress::{Progress, sparkline};
+use crate::stats::{LatencyResult, LatencyStats};
+use crate::util::terminal_size;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+
+/// 计算百分位柱状图时保留的最近样本数
+const LATENCY_WINDOW: usize = 10_000;
+/// 请求日志保留的条数，实际显示多少取决于终端高度
+const LOG_LINES: usize = 200;
+const ENTER: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE: &str = "\x1b[?25h\x1b[?1049l";
+
+/// 是否处于全屏界面，panic 时据此恢复终端
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// 恢复主屏幕和光标，未进入全屏时什么也不做
+pub fn restore_terminal() {
+    if ACTIVE.swap(false, Ordering::Relaxed) {
+        let mut out = std::io::stdout();
+        write!(out, "{}", LEAVE).ok();
+        out.flush().ok();
+    }
+}
+
+/// 收集结果的循环写入、界面任务读取的共享数据
+#[derive(Debug, Default)]
+pub(crate) struct TuiFeed {
+    ttft: VecDeque<Duration>,
+    log: VecDeque<String>,
+}
+
+impl TuiFeed {
+    pub(crate) fn record(&mut self, r: &LatencyResult) {
+        if self.ttft.len() == LATENCY_WINDOW {
+            self.ttft.pop_front();
+        }
+        self.ttft.push_back(r.ttft);
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(format!(
+            "[w={}] ttft={:.1}ms total={:.1}ms tokens={}",
+            r.worker_id,
+            r.ttft.as_secs_f64() * 1000.0,
+            r.total.as_secs_f64() * 1000.0,
+            r.tokens
+        ));
+    }
+}
+
+/// 界面的固定信息
+pub(crate) struct TuiOptions {
+    pub(crate) url: String,
+    pub(crate) concurrency: usize,
+    pub(crate) limit: Option<usize>,
+    pub(crate) duration: Option<Duration>,
+    pub(crate) percentiles: Vec<f64>,
+}
+
+/// 全屏界面：进度、RPS 走势、TTFT 百分位柱状图和最近的请求，每 250ms 重绘；
+/// `progress.done` 置位后退出并恢复终端
+pub(crate) fn spawn_tui(
+    progress: Arc<Progress>,
+    feed: Arc<Mutex<TuiFeed>>,
+    options: TuiOptions,
+    start: Instant,
+) -> tokio::task::JoinHandle<()> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            previous(info);
+        }));
+    });
+
+    tokio::spawn(async move {
+        let mut out = std::io::stdout();
+        ACTIVE.store(true, Ordering::Relaxed);
+        write!(out, "{}", ENTER).ok();
+
+        let mut rps_history = VecDeque::new();
+        let mut last_count = 0;
+        let mut last_sample = Instant::now();
+        let mut interval = tokio::time::interval(Duration::from_millis(250));
+        while !progress.done.load(Ordering::Relaxed) {
+            interval.tick().await;
+            let (columns, rows) = terminal_size();
+            let completed = progress.completed();
+            if last_sample.elapsed() >= Duration::from_secs(1) {
+                let rps = (completed - last_count) as f64 / last_sample.elapsed().as_secs_f64();
+                if rps_history.len() >= columns.saturating_sub(20).max(10) {
+                    rps_history.pop_front();
+                }
+                rps_history.push_back(rps);
+                last_count = completed;
+                last_sample = Instant::now();
+            }
+            let frame = render(
+                &progress,
+                &feed,
+                &options,
+                &rps_history,
+                start.elapsed(),
+                columns,
+                rows,
+            );
+            write!(out, "\x1b[H{}\x1b[J", frame).ok();
+            out.flush().ok();
+        }
+        restore_terminal();
+    })
+}
+
+fn render(
+    progress: &Progress,
+    feed: &Mutex<TuiFeed>,
+    options: &TuiOptions,
+    rps_history: &VecDeque<f64>,
+    elapsed: Duration,
+    columns: usize,
+    rows: usize,
+) -> String {
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "\x1b[1mconcurrency_testing\x1b[0m  {}  concurrency={}",
+        options.url, options.concurrency
+    ));
+    lines.push(String::new());
+
+    let fraction = progress.fraction(options.limit, options.duration, elapsed);
+    let gauge_width = columns.saturating_sub(40).clamp(10, 60);
+    let filled = (fraction * gauge_width as f64) as usize;
+    let count = match options.limit {
+        Some(n) => format!("{}/{}", progress.completed(), n),
+        None => progress.completed().to_string(),
+    };
+    lines.push(format!(
+        "Progress [{}{}] {:>3.0}%  {} requests  {:.1?}",
+        "█".repeat(filled),
+        "░".repeat(gauge_width - filled),
+        fraction * 100.0,
+        count,
+        elapsed
+    ));
+    let current_rps = rps_history.back().copied().unwrap_or(0.0);
+    lines.push(format!(
+        "RPS      {} {:.1}/s",
+        sparkline(rps_history),
+        current_rps
+    ));
+    lines.push(String::new());
+
+    let feed = feed.lock().unwrap();
+    lines.push("\x1b[1mTTFT percentiles\x1b[0m".to_string());
+    let stats = LatencyStats::from_samples(feed.ttft.iter().copied(), &options.percentiles);
+    match stats {
+        Some(stats) => {
+            let bars: Vec<(String, f64)> = options
+                .percentiles
+                .iter()
+                .map(|p| format!("P{}", p))
+                .zip(stats.percentiles.iter().map(|(_, v)| *v))
+                .chain([("Max".to_string(), stats.max)])
+                .collect();
+            let bar_width = columns.saturating_sub(24).max(10);
+            for (label, value) in bars {
+                let len = ((value / stats.max) * bar_width as f64).round() as usize;
+                lines.push(format!(
+                    "  {:<6} {:<bar_width$} {:>9.1} ms",
+                    label,
+                    "▇".repeat(len),
+                    value
+                ));
+            }
+        }
+        None => lines.push("  waiting for results…".to_string()),
+    }
+    lines.push(String::new());
+
+    lines.push("\x1b[1mRecent requests\x1b[0m".to_string());
+    let room = rows.saturating_sub(lines.len() + 1);
+    let skip = feed.log.len().saturating_sub(room);
+    lines.extend(feed.log.iter().skip(skip).map(|l| format!("  {}", l)));
+
+    // 每行截断到终端宽度并清除行尾残留
+    lines
+        .iter()
+        .map(|l| format!("{}\x1b[K", truncate(l, columns)))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// 按字符数截断，转义序列不占显示宽度，近似处理即可
+fn truncate(line: &str, columns: usize) -> &str {
+    match line
+        .char_indices()
+        .nth(columns + line.matches('\x1b').count() * 4)
+    {
+        Some((idx, _)) => &line[..idx],
+        None => line,
+    }
+}
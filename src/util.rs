@@ -0,0 +1,209 @@
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 当前 Unix 时间戳（秒）
+pub(crate) fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Unix 时间戳（秒）格式化为 UTC 的 ISO 8601 字符串，如 "2024-01-01T00:00:00Z"
+pub(crate) fn format_utc(ts: f64) -> String {
+    let secs = ts.max(0.0) as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // 按公历将天数换算为年月日（Howard Hinnant 的 civil_from_days 算法）
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// 先写入同目录下的临时文件再重命名，进程中途被杀时不会留下写了一半的文件
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// 解析 "500ms"、"30s"、"5m"、"1h" 形式的时长，纯数字按秒处理
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let value: f64 = num.parse().map_err(|_| format!("invalid duration: {s}"))?;
+    let secs = match unit.trim() {
+        "" | "s" => value,
+        "ms" => value / 1000.0,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        other => return Err(format!("unknown duration unit: {other}")),
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| format!("duration out of range: {s}"))
+}
+
+/// `#[serde(with = "duration_secs")]`：Duration 以秒数（f64）序列化
+pub(crate) mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_f64(d.as_secs_f64())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        Duration::try_from_secs_f64(f64::deserialize(d)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 同 [`duration_secs`]，用于 `Option<Duration>`，None 序列化为 null
+pub(crate) mod option_duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(crate) fn serialize<S: Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+        match d {
+            Some(d) => s.serialize_some(&d.as_secs_f64()),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<f64>::deserialize(d)?
+            .map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// [0, 1) 区间均匀分布的伪随机数
+pub(crate) fn random_f64() -> f64 {
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 随机的版本 4 UUID（RFC 4122 变体），如 "3f2c6a1e-8b4d-4c9a-9e1f-0a7b5c3d2e1f"
+pub(crate) fn random_uuid() -> String {
+    let hi = random_u64() & !0xf000 | 0x4000;
+    let lo = random_u64() & !(0b11 << 62) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xffff,
+        hi & 0xffff,
+        lo >> 48,
+        lo & 0xffff_ffff_ffff
+    )
+}
+
+/// 不依赖外部 crate 的伪随机数（splitmix64），用于生成 trace/span ID 等非安全场景
+pub(crate) fn random_u64() -> u64 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    static SEED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    let seed = *SEED.get_or_init(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+            ^ u64::from(std::process::id()) << 32
+    });
+    let mut z = seed.wrapping_add(
+        STATE
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// 终端的 (列数, 行数)：优先查询 stdout 所在终端，其次读取 COLUMNS/LINES，默认 80×24
+pub(crate) fn terminal_size() -> (usize, usize) {
+    #[cfg(unix)]
+    {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        for fd in [libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+            // SAFETY: TIOCGWINSZ 只写入传入的 winsize 结构体
+            if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+                return (usize::from(size.ws_col), usize::from(size.ws_row));
+            }
+        }
+    }
+    let env = |name: &str, default: usize| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    (env("COLUMNS", 80), env("LINES", 24))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let parse = |s: &str| parse_duration(s).unwrap();
+        assert_eq!(parse("30"), Duration::from_secs(30));
+        assert_eq!(parse("1.5s"), Duration::from_millis(1500));
+        assert_eq!(parse("500ms"), Duration::from_millis(500));
+        assert_eq!(parse(" 5m "), Duration::from_secs(300));
+        assert_eq!(parse("2h"), Duration::from_secs(7200));
+        assert_eq!(parse("0"), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert_eq!(parse_duration("").unwrap_err(), "invalid duration: ");
+        assert_eq!(parse_duration("-1s").unwrap_err(), "invalid duration: -1s");
+        assert_eq!(
+            parse_duration("1.2.3s").unwrap_err(),
+            "invalid duration: 1.2.3s"
+        );
+        assert_eq!(
+            parse_duration("3d").unwrap_err(),
+            "unknown duration unit: d"
+        );
+        assert_eq!(
+            parse_duration("99999999999999999999999h").unwrap_err(),
+            "duration out of range: 99999999999999999999999h"
+        );
+    }
+}
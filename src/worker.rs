@@ -0,0 +1,922 @@
+use crate::breaker::CircuitBreaker;
+use crate::config::{BenchmarkConfig, MaxErrorsMode};
+use crate::cookie::CookieJar;
+use crate::har::{HarRecorder, RecordedRequest, RecordedResponse, header_pairs};
+use crate::metrics::Metrics;
+use crate::otlp::{OtlpExporter, SpanRecord};
+use crate::progress::Progress;
+use crate::spike::PHASE_SPIKE;
+use crate::sse::{SseParser, parse_completion_tokens, parse_usage};
+use crate::stats::{ErrorKind, LatencyResult, RequestOutcome, ServerTiming};
+use crate::trace::RequestTracer;
+use crate::transport::{BenchmarkRequest, BenchmarkResponse, Transport, TransportError};
+use crate::util::{random_f64, random_u64, random_uuid, unix_now};
+use bytes::Bytes;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, mpsc, watch};
+
+/// 按固定速率发放令牌的限流器（桶容量为 1），所有 worker 共享
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    /// rate 须为正数，且两个请求的间隔不超出 Duration 的范围
+    pub(crate) fn new(rate: f64) -> Option<Self> {
+        if rate <= 0.0 || !rate.is_finite() {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::try_from_secs_f64(1.0 / rate).ok()?,
+            next: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        })
+    }
+
+    /// 预约下一个可用时间片并等待到该时刻
+    async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(tokio::time::Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// --max-errors 使用的失败计数：consecutive 模式下每个成功请求清零，达到上限时通知主线程
+pub(crate) struct ErrorCounter {
+    pub(crate) count: AtomicUsize,
+    pub(crate) mode: MaxErrorsMode,
+    pub(crate) max: Option<usize>,
+    pub(crate) tripped: Notify,
+}
+
+impl ErrorCounter {
+    fn record_error(&self) {
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.max.is_some_and(|max| count >= max) {
+            self.tripped.notify_one();
+        }
+    }
+
+    fn record_success(&self) {
+        if self.mode == MaxErrorsMode::Consecutive {
+            self.count.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// worker 上报请求结果的共享状态：结果发给主线程，同时更新进度、Prometheus 指标、
+/// --max-errors 计数与熔断器
+#[derive(Clone)]
+pub(crate) struct OutcomeSink {
+    pub(crate) sender: mpsc::Sender<RequestOutcome>,
+    pub(crate) progress: Arc<Progress>,
+    pub(crate) metrics: Arc<Metrics>,
+    pub(crate) error_counter: Arc<ErrorCounter>,
+    pub(crate) breaker: Option<CircuitBreaker>,
+    /// 爬坡结束前为 false
+    pub(crate) ramped_up: Arc<AtomicBool>,
+    pub(crate) warmup_remaining: Arc<AtomicUsize>,
+    pub(crate) warmup_done: Arc<Notify>,
+}
+
+impl OutcomeSink {
+    /// 爬坡与预热阶段完成的请求不计入统计，返回 false。预热请求无论成败都消耗一个预热名额，
+    /// 服务端持续失败时预热也能结束；最后一个预热请求完成时通知主线程
+    fn admit(&self) -> bool {
+        if !self.ramped_up.load(Ordering::Relaxed) {
+            return false;
+        }
+        match self
+            .warmup_remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+        {
+            Ok(1) => {
+                self.warmup_done.notify_one();
+                false
+            }
+            Ok(_) => false,
+            Err(_) => true,
+        }
+    }
+
+    async fn record_success(&self, result: LatencyResult) {
+        let (ttft, total) = (result.ttft, result.total);
+        // 主线程已停止收集时结果被丢弃，也不再更新计数
+        if self
+            .sender
+            .send(RequestOutcome::Success(Box::new(result)))
+            .await
+            .is_ok()
+        {
+            self.progress.record(ttft);
+            self.metrics.record_success(ttft, total);
+            self.error_counter.record_success();
+            if let Some(breaker) = &self.breaker {
+                breaker.lock().unwrap().record(true);
+            }
+        }
+    }
+
+    /// 记录重试用尽后仍然失败的请求
+    async fn record_failure(&self, kind: ErrorKind, status: Option<u16>, url_index: usize) {
+        // 与成功请求一样，爬坡与预热期间的失败既不计入统计，也不触发 --max-errors 和熔断
+        if !self.admit() {
+            return;
+        }
+        // 先发送结果再计数，--max-errors 中止时主线程能收到触发中止的失败
+        let failure = RequestOutcome::Failure {
+            kind,
+            status,
+            url_index,
+        };
+        let _ = self.sender.send(failure).await;
+        self.metrics.record_error();
+        self.error_counter.record_error();
+        if let Some(breaker) = &self.breaker {
+            breaker.lock().unwrap().record(false);
+        }
+    }
+}
+
+/// 所有 worker 共享的 URL 选择器，保证请求在各 URL 间按比例分配：未指定权重时轮询，
+/// 否则按平滑加权轮询（nginx 的算法）逐个选取，每个下标出现的次数与权重成正比且尽量交错分布
+pub(crate) struct UrlSchedule {
+    urls: usize,
+    counter: AtomicUsize,
+    weights: Vec<i64>,
+    total: i64,
+    /// 各 URL 的当前权重
+    current: Mutex<Vec<i64>>,
+}
+
+impl UrlSchedule {
+    pub(crate) fn new(urls: usize, weights: &[u32]) -> Self {
+        let weights: Vec<i64> = weights.iter().map(|&w| i64::from(w)).collect();
+        Self {
+            urls,
+            counter: AtomicUsize::new(0),
+            total: weights.iter().sum(),
+            current: Mutex::new(vec![0; weights.len()]),
+            weights,
+        }
+    }
+
+    /// 下一个请求的 URL 下标，未使用 --url-list 时总是 0
+    fn next(&self) -> usize {
+        if self.weights.is_empty() {
+            if self.urls == 0 {
+                return 0;
+            }
+            return self.counter.fetch_add(1, Ordering::Relaxed) % self.urls;
+        }
+        let mut current = self.current.lock().unwrap();
+        for (c, w) in current.iter_mut().zip(&self.weights) {
+            *c += w;
+        }
+        // 取当前值最大的下标，相等时取靠前的
+        let best = (0..current.len()).fold(
+            0,
+            |best, i| {
+                if current[i] > current[best] { i } else { best }
+            },
+        );
+        current[best] -= self.total;
+        best
+    }
+}
+
+/// --assert-status / --assert-field 检查，返回第一个不符合项的说明
+fn check_assertions(config: &BenchmarkConfig, status: u16, body: &[u8]) -> Result<(), String> {
+    if let Some(expected) = config
+        .expected_status
+        .filter(|&expected| expected != status)
+    {
+        return Err(format!("expected status {expected}, got {status}"));
+    }
+    for (path, expected) in &config.field_assertions {
+        let Some(value) = path.extract(body) else {
+            return Err(format!("{} not found, expected {expected}", path.source()));
+        };
+        let matches = match &value {
+            serde_json::Value::String(s) => s == expected,
+            value => serde_json::from_str::<serde_json::Value>(expected).is_ok_and(|e| e == *value),
+        };
+        if !matches {
+            return Err(format!("{} = {value}, expected {expected}", path.source()));
+        }
+    }
+    Ok(())
+}
+
+/// 重试等待时间的上限
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 第 attempt 次重试前的等待时间：base * 2^(attempt-1)，附加 ±10% 抖动，不超过 MAX_RETRY_BACKOFF
+fn retry_backoff(base: Duration, attempt: u32) -> Duration {
+    let jitter = 0.9 + random_f64() * 0.2;
+    // 2^32 倍后任何非零 base 都已超过上限，再大的指数只会溢出
+    let exponent = attempt.saturating_sub(1).min(32) as i32;
+    Duration::try_from_secs_f64(base.as_secs_f64() * 2f64.powi(exponent) * jitter)
+        .map_or(MAX_RETRY_BACKOFF, |delay| delay.min(MAX_RETRY_BACKOFF))
+}
+
+/// 所有 worker 共享的状态；主线程启动全部 worker 后释放自己的引用，
+/// worker 全部退出时结果通道随之关闭
+pub(crate) struct WorkerShared<T> {
+    pub(crate) config: Arc<BenchmarkConfig>,
+    pub(crate) transport: Arc<T>,
+    pub(crate) sink: OutcomeSink,
+    /// 公共请求头，traceparent、Cookie 等按请求生成的值发送前再追加
+    pub(crate) headers: HeaderMap,
+    pub(crate) body_counter: AtomicUsize,
+    pub(crate) url_schedule: UrlSchedule,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    /// 保证 --print-response 只打印一次
+    pub(crate) printed: AtomicBool,
+    /// 到达限制后由主线程置位，worker 退出循环
+    pub(crate) stop: Arc<AtomicBool>,
+    /// --verbose 输出中的全局请求序号
+    pub(crate) request_seq: AtomicUsize,
+    pub(crate) spike_phase: Arc<AtomicU8>,
+    pub(crate) trace_id: Option<u128>,
+    pub(crate) otlp: Option<Arc<OtlpExporter>>,
+    pub(crate) har: Option<Arc<HarRecorder>>,
+    pub(crate) tracer: Option<Arc<RequestTracer>>,
+}
+
+/// 一个并发 worker：循环发起请求直到主线程要求停止，每个请求的结果交给 OutcomeSink
+pub(crate) struct Worker<T> {
+    id: usize,
+    shared: Arc<WorkerShared<T>>,
+    /// 每个 worker 独立记录 Set-Cookie
+    cookies: Option<CookieJar>,
+    /// 失败过多时由主线程发出取消信号，不再发起新请求，重试等待也立即结束
+    cancel: watch::Receiver<bool>,
+    /// 已发起的请求数（--iterations）
+    issued: usize,
+    /// 上一个请求结束后需要等待的思考时间
+    think_pending: bool,
+    idle_until: Option<Instant>,
+}
+
+/// 一个请求的标识与记录信息，重试时沿用
+struct RequestMeta {
+    req_id: usize,
+    span_id: u64,
+    url_index: usize,
+    /// --request-id-header 的值
+    request_uuid: Option<String>,
+    /// --verbose 输出中的请求标识
+    log_id: String,
+    /// 需要交给 --record-har / --trace-requests 时记录的请求
+    recorded: Option<RecordedRequest>,
+}
+
+/// 最后一次发送的时间与此前的重试次数
+struct Attempt {
+    start: Instant,
+    start_ts: f64,
+    retries: u32,
+}
+
+impl Attempt {
+    /// --verbose 输出的重试次数后缀
+    fn retried(&self) -> String {
+        if self.retries > 0 {
+            format!(" retries={}", self.retries)
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// 读取成功响应的响应体得到的测量结果
+struct ResponseBody {
+    ttft: Option<Duration>,
+    itl: Vec<Duration>,
+    events: u32,
+    bytes_received: u64,
+    chunks: u32,
+    /// 仅在需要解析 usage、校验或记录响应时保留
+    body: Vec<u8>,
+    /// 读到 [DONE] 或达到 --max-body-size 的时刻
+    done_at: Option<Instant>,
+    error: Option<(ErrorKind, String)>,
+    truncated: bool,
+}
+
+impl<T: Transport> Worker<T> {
+    pub(crate) fn new(
+        id: usize,
+        shared: Arc<WorkerShared<T>>,
+        cancel: watch::Receiver<bool>,
+    ) -> Self {
+        Self {
+            id,
+            cookies: shared.config.cookie_jar.clone(),
+            shared,
+            cancel,
+            issued: 0,
+            think_pending: false,
+            idle_until: None,
+        }
+    }
+
+    pub(crate) async fn run(mut self) {
+        let shared = self.shared.clone();
+        let config = &shared.config;
+        let shutdown = config.shutdown.clone().unwrap_or_default();
+        while !shared.stop.load(Ordering::Relaxed)
+            && !shutdown.load(Ordering::Relaxed)
+            && !*self.cancel.borrow()
+        {
+            if let Some(pause) = self.pause() {
+                tokio::time::sleep(pause).await;
+                continue;
+            }
+            // --iterations：发满后退出，sender 全部释放时主线程结束收集；
+            // 先于限流检查，已发满的 worker 不再占用时间片
+            if config.iterations.is_some_and(|n| self.issued >= n) {
+                break;
+            }
+            if let Some(limiter) = &shared.rate_limiter {
+                limiter.acquire().await;
+                // 等待时间片期间可能已到达时长限制，此时不再发出请求
+                if shared.stop.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+            self.issued += 1;
+            self.think_pending = true;
+
+            let (request, meta) = self.build_request();
+            let (result, attempt) = self.send_with_retry(request, &meta.log_id).await;
+            match result {
+                Ok(resp) => {
+                    if let Some(jar) = &mut self.cookies {
+                        jar.store_response(&resp.url, &resp.headers);
+                    }
+                    if resp.status.is_success() {
+                        self.finish_success(&meta, &attempt, resp).await;
+                    } else {
+                        self.finish_status_failure(&meta, &attempt, resp).await;
+                    }
+                }
+                Err(e) => {
+                    if config.verbose {
+                        eprintln!(
+                            "[w={} req={}] error={}{}",
+                            self.id,
+                            meta.log_id,
+                            e,
+                            attempt.retried()
+                        );
+                    }
+                    self.record_exchange(
+                        &meta,
+                        &attempt,
+                        &RecordedResponse {
+                            status: 0,
+                            headers: Vec::new(),
+                            body: &[],
+                            wait: attempt.start.elapsed(),
+                            receive: Duration::ZERO,
+                            error: Some(e.to_string()),
+                        },
+                    );
+                    shared
+                        .sink
+                        .record_failure(e.kind, None, meta.url_index)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// 思考时间、熔断或突发阶段之外需要等待时，返回这一次 sleep 的时长；
+    /// 每次最多等待 100ms，保证测试结束时能及时退出
+    fn pause(&mut self) -> Option<Duration> {
+        let config = &self.shared.config;
+        if let Some(think_time) = config.think_time.filter(|_| self.think_pending) {
+            let jitter = config.think_time_jitter.mul_f64(random_f64());
+            self.idle_until = Some(Instant::now() + think_time + jitter);
+            self.think_pending = false;
+        }
+        if let Some(until) = self.idle_until.filter(|&until| until > Instant::now()) {
+            return Some((until - Instant::now()).min(Duration::from_millis(100)));
+        }
+        if let Some(breaker) = &self.shared.sink.breaker {
+            let pause = breaker.lock().unwrap().pause();
+            if let Some(pause) = pause {
+                return Some(pause.min(Duration::from_millis(100)));
+            }
+        }
+        let spike_worker = config
+            .spike
+            .as_ref()
+            .is_some_and(|spike| self.id >= spike.baseline_concurrency);
+        if spike_worker && self.shared.spike_phase.load(Ordering::Relaxed) != PHASE_SPIKE {
+            return Some(Duration::from_millis(100));
+        }
+        None
+    }
+
+    /// 选择 URL 与请求体，并附上 traceparent、请求 ID 与 Cookie
+    fn build_request(&self) -> (BenchmarkRequest, RequestMeta) {
+        let shared = &self.shared;
+        let config = &shared.config;
+        let req_id = shared.request_seq.fetch_add(1, Ordering::Relaxed);
+        let span_id = random_u64();
+
+        let url_index = shared.url_schedule.next();
+        let url = config.urls.get(url_index).unwrap_or(&config.url);
+        let body = if let Some(template) = &config.body_template {
+            Some(template.render(req_id, self.id))
+        } else if !config.bodies.is_empty() {
+            let idx = shared.body_counter.fetch_add(1, Ordering::Relaxed) % config.bodies.len();
+            Some(config.bodies[idx].clone())
+        } else {
+            None
+        };
+        let traceparent = shared
+            .trace_id
+            .map(|trace_id| format!("00-{:032x}-{:016x}-01", trace_id, span_id));
+        let cookie = self.cookies.as_ref().and_then(|jar| jar.header_for(url));
+        // --request-id-header：重试沿用同一个 ID
+        let request_uuid = config.request_id_header.as_ref().map(|_| random_uuid());
+        let log_id = match &request_uuid {
+            Some(id) if config.verbose => format!("{req_id} id={id}"),
+            _ => req_id.to_string(),
+        };
+        let mut headers = shared.headers.clone();
+        let per_request = [
+            traceparent.map(|v| (HeaderName::from_static("traceparent"), v)),
+            config.request_id_header.clone().zip(request_uuid.clone()),
+            cookie.map(|v| (COOKIE, v)),
+        ];
+        for (name, value) in per_request.into_iter().flatten() {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.append(name, value);
+            }
+        }
+        let request = BenchmarkRequest {
+            method: config.method.clone(),
+            url: url.clone(),
+            headers,
+            body,
+        };
+
+        let record =
+            shared.har.is_some() || shared.tracer.as_ref().is_some_and(|t| t.wants(req_id));
+        let recorded = record.then(|| {
+            let mut headers = header_pairs(&request.headers);
+            if !headers.iter().any(|(name, _)| name == "user-agent") {
+                headers.push(("user-agent".into(), config.user_agent.clone()));
+            }
+            RecordedRequest {
+                method: config.method.to_string(),
+                url: url.clone(),
+                headers,
+                body: request.body.clone(),
+            }
+        });
+        let meta = RequestMeta {
+            req_id,
+            span_id,
+            url_index,
+            request_uuid,
+            log_id,
+            recorded,
+        };
+        (request, meta)
+    }
+
+    /// 非 2xx 或网络错误时按指数退避重试，只有最后一次的结果计入统计
+    async fn send_with_retry(
+        &mut self,
+        mut request: BenchmarkRequest,
+        log_id: &str,
+    ) -> (Result<BenchmarkResponse, TransportError>, Attempt) {
+        let config = &self.shared.config;
+        let mut attempt = Attempt {
+            start: Instant::now(),
+            start_ts: unix_now(),
+            retries: 0,
+        };
+        loop {
+            let retry = (attempt.retries < config.retries).then(|| request.clone());
+            let res = self.shared.transport.send_request(request).await;
+            let failed = res.as_ref().map_or(true, |resp| !resp.status.is_success());
+            let Some(next) = retry.filter(|_| failed) else {
+                return (res, attempt);
+            };
+            attempt.retries += 1;
+            let delay = retry_backoff(config.retry_delay, attempt.retries);
+            if config.verbose {
+                let reason = match &res {
+                    Ok(resp) => format!("status={}", resp.status.as_u16()),
+                    Err(e) => format!("error={}", e),
+                };
+                eprintln!(
+                    "[w={} req={}] attempt {} failed ({}), retrying in {:.0?}",
+                    self.id, log_id, attempt.retries, reason, delay
+                );
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.cancel.changed() => return (res, attempt),
+            }
+            request = next;
+            attempt.start = Instant::now();
+            attempt.start_ts = unix_now();
+        }
+    }
+
+    /// 读取成功响应的响应体，记录 TTFT 与 token 间隔。
+    /// 普通模式下第一个 chunk 即为 TTFT，之后以 100ms 无数据视为结束；
+    /// SSE 模式下第一个有效事件为 TTFT，读到 [DONE] 或流结束为止
+    async fn read_response(
+        &self,
+        mut stream: BoxStream<'static, Result<Bytes, TransportError>>,
+        meta: &RequestMeta,
+        start: Instant,
+    ) -> ResponseBody {
+        let shared = &self.shared;
+        let config = &shared.config;
+        // 检查是否需要打印（仅第一个成功请求）
+        let should_print = config.print_response && !shared.printed.swap(true, Ordering::Relaxed);
+        let keep_body = config.parse_usage_tokens
+            || config.vllm_usage
+            || config.response_schema.is_some()
+            || !config.extract.is_empty()
+            || !config.field_assertions.is_empty()
+            || meta.recorded.is_some();
+        let mut sse = config.sse.then(|| SseParser::new(config.sse_format));
+        // --openai-done-sentinel：非 SSE 模式下也检查 `data: [DONE]`
+        let mut done_scan = (config.done_sentinel && sse.is_none()).then(SseParser::default);
+
+        let mut read = ResponseBody {
+            ttft: None,
+            itl: Vec::new(),
+            events: 0,
+            bytes_received: 0,
+            chunks: 0,
+            body: Vec::new(),
+            done_at: None,
+            error: None,
+            truncated: false,
+        };
+        let mut last_event = start;
+        loop {
+            let wait = if read.ttft.is_none() || sse.is_some() {
+                config.timeout
+            } else {
+                Duration::from_millis(100)
+            };
+            let chunk = match tokio::time::timeout(wait, stream.next()).await {
+                Ok(Some(Ok(chunk))) => chunk,
+                Ok(Some(Err(e))) => {
+                    read.error = Some((e.kind, e.message));
+                    break;
+                }
+                Err(_) if read.ttft.is_none() => {
+                    read.error = Some((ErrorKind::Timeout, "no data before timeout".into()));
+                    break;
+                }
+                _ => break,
+            };
+            let now = Instant::now();
+            read.bytes_received += chunk.len() as u64;
+            read.chunks += 1;
+            if keep_body {
+                read.body.extend_from_slice(&chunk);
+            }
+            if should_print {
+                // 安全地将 bytes 转为字符串（忽略非法 UTF-8）
+                print!("{}", String::from_utf8_lossy(&chunk));
+                std::io::stdout().flush().ok();
+            }
+
+            let (new_events, done) = match (&mut sse, &mut done_scan) {
+                (Some(parser), _) => parser.push(&chunk),
+                // 只含 [DONE] 的 chunk 不计为 token 事件
+                (None, Some(parser)) => match parser.push(&chunk) {
+                    (0, true) => (0, true),
+                    (_, done) => (1, done),
+                },
+                (None, None) => (1, false),
+            };
+            // 同一个 chunk 中的多个事件同时到达，只记录一个间隔，token 数仍按事件计
+            if new_events > 0 {
+                match read.ttft {
+                    None => read.ttft = Some(now - start),
+                    Some(_) => read.itl.push(now - last_event),
+                }
+                last_event = now;
+            }
+            read.events += new_events;
+            if done {
+                read.done_at = Some(now);
+                break;
+            }
+            // 达到上限即丢弃响应流，连接随之关闭，不再等待剩余数据
+            if config
+                .max_body_size
+                .is_some_and(|max| read.bytes_received >= max)
+            {
+                read.truncated = true;
+                read.done_at = Some(now);
+                break;
+            }
+        }
+        if should_print {
+            println!("\n--- RESPONSE END ---\n");
+        }
+        // 读取响应体出错时即使已收到部分数据也计为失败
+        if read.ttft.is_none() && read.error.is_none() {
+            read.error = Some((ErrorKind::StreamError, "no data received".into()));
+        }
+        read
+    }
+
+    /// 2xx 响应：读取响应体，校验通过后作为成功请求上报
+    async fn finish_success(&self, meta: &RequestMeta, attempt: &Attempt, resp: BenchmarkResponse) {
+        let shared = &self.shared;
+        let config = &shared.config;
+        let status = resp.status.as_u16();
+        let response_headers = meta
+            .recorded
+            .as_ref()
+            .map(|_| header_pairs(&resp.headers))
+            .unwrap_or_default();
+        let server_timing = config
+            .tgi_headers
+            .then(|| ServerTiming::from_headers(&resp.headers))
+            .flatten();
+        let mut read = self.read_response(resp.body, meta, attempt.start).await;
+
+        // 截断的响应体不完整，不做模式校验
+        if read.error.is_none()
+            && !read.truncated
+            && let Some(schema) = &config.response_schema
+            && let Err(e) = schema.validate_response(&read.body, config.sse)
+        {
+            read.error = Some((ErrorKind::SchemaValidation, e));
+        }
+        let end = read.done_at.unwrap_or_else(Instant::now) - attempt.start;
+        let wait = read.ttft.unwrap_or(end);
+        self.record_exchange(
+            meta,
+            attempt,
+            &RecordedResponse {
+                status,
+                headers: response_headers,
+                body: &read.body,
+                wait,
+                receive: end - wait,
+                error: read.error.as_ref().map(|(_, reason)| reason.clone()),
+            },
+        );
+        let (Some(ttft), None) = (read.ttft, &read.error) else {
+            let (kind, reason) = read.error.unwrap();
+            if config.verbose {
+                eprintln!(
+                    "[w={} req={}] status={} error={}{}",
+                    self.id,
+                    meta.log_id,
+                    status,
+                    reason,
+                    attempt.retried()
+                );
+            }
+            shared
+                .sink
+                .record_failure(kind, Some(status), meta.url_index)
+                .await;
+            return;
+        };
+        // 读到 [DONE] 时以其到达时刻为结束时间
+        let total = read.done_at.unwrap_or_else(Instant::now) - attempt.start;
+        if config.verbose {
+            eprintln!(
+                "[w={} req={}] status={} ttft={:.1}ms total={:.1}ms{}",
+                self.id,
+                meta.log_id,
+                status,
+                ttft.as_secs_f64() * 1000.0,
+                total.as_secs_f64() * 1000.0,
+                attempt.retried()
+            );
+        }
+        let tokens = config
+            .parse_usage_tokens
+            .then(|| parse_completion_tokens(&read.body))
+            .flatten()
+            .unwrap_or(read.events * config.tokens_per_chunk);
+        let usage = config.vllm_usage.then(|| parse_usage(&read.body)).flatten();
+        let extracted = config
+            .extract
+            .iter()
+            .map(|path| path.extract(&read.body))
+            .collect();
+        let assertion = check_assertions(config, status, &read.body);
+        if config.verbose
+            && let Err(reason) = &assertion
+        {
+            eprintln!(
+                "[w={} req={}] assertion failed: {reason}",
+                self.id, meta.log_id
+            );
+        }
+        if !shared.sink.admit() {
+            return;
+        }
+
+        shared
+            .sink
+            .record_success(LatencyResult {
+                ttft,
+                total,
+                itl: read.itl,
+                tokens,
+                bytes_received: read.bytes_received,
+                chunks: read.chunks,
+                usage,
+                server_timing,
+                extracted,
+                assertion_failed: assertion.is_err(),
+                truncated: read.truncated,
+                request_id: meta.request_uuid.clone(),
+                status,
+                worker_id: self.id,
+                url_index: meta.url_index,
+                start_ts: attempt.start_ts,
+            })
+            .await;
+        self.export_span(
+            meta,
+            attempt,
+            attempt.start_ts + total.as_secs_f64(),
+            status,
+            Some(ttft),
+        );
+    }
+
+    /// 重试用尽后仍为非 2xx 的响应。失败响应不读取响应体，--trace-requests 记录的请求除外
+    async fn finish_status_failure(
+        &self,
+        meta: &RequestMeta,
+        attempt: &Attempt,
+        resp: BenchmarkResponse,
+    ) {
+        let shared = &self.shared;
+        let status = resp.status;
+        if meta.recorded.is_some() {
+            let headers = header_pairs(&resp.headers);
+            let body = match &shared.tracer {
+                Some(tracer) if tracer.wants(meta.req_id) => resp.bytes().await.unwrap_or_default(),
+                _ => Default::default(),
+            };
+            self.record_exchange(
+                meta,
+                attempt,
+                &RecordedResponse {
+                    status: status.as_u16(),
+                    headers,
+                    body: &body,
+                    wait: attempt.start.elapsed(),
+                    receive: Duration::ZERO,
+                    error: None,
+                },
+            );
+        }
+        if shared.config.verbose {
+            eprintln!(
+                "[w={} req={}] status={}{}",
+                self.id,
+                meta.log_id,
+                status.as_u16(),
+                attempt.retried()
+            );
+        }
+        let kind = ErrorKind::from_status(status);
+        shared
+            .sink
+            .record_failure(kind, Some(status.as_u16()), meta.url_index)
+            .await;
+        self.export_span(meta, attempt, unix_now(), status.as_u16(), None);
+    }
+
+    /// 将一次请求交给 --record-har 与 --trace-requests 记录
+    fn record_exchange(&self, meta: &RequestMeta, attempt: &Attempt, response: &RecordedResponse) {
+        let Some(request) = &meta.recorded else {
+            return;
+        };
+        if let Some(har) = &self.shared.har {
+            har.record(attempt.start_ts, request, response);
+        }
+        if let Some(tracer) = &self.shared.tracer {
+            tracer.record(meta.req_id, self.id, attempt.start_ts, request, response);
+        }
+    }
+
+    fn export_span(
+        &self,
+        meta: &RequestMeta,
+        attempt: &Attempt,
+        end_ts: f64,
+        status: u16,
+        ttft: Option<Duration>,
+    ) {
+        if let Some(otlp) = &self.shared.otlp {
+            otlp.record(SpanRecord {
+                span_id: meta.span_id,
+                start_ts: attempt.start_ts,
+                end_ts,
+                worker_id: self.id,
+                status,
+                ttft,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picks(schedule: &UrlSchedule, n: usize) -> Vec<usize> {
+        (0..n).map(|_| schedule.next()).collect()
+    }
+
+    #[test]
+    fn caps_retry_backoff() {
+        let base = Duration::from_millis(100);
+        let first = retry_backoff(base, 1);
+        assert!(first >= Duration::from_millis(90) && first <= Duration::from_millis(110));
+        let third = retry_backoff(base, 3);
+        assert!(third >= Duration::from_millis(360) && third <= Duration::from_millis(440));
+        assert_eq!(retry_backoff(base, 20), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(base, u32::MAX), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(Duration::MAX, 1), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(Duration::ZERO, u32::MAX), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_unusable_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e-30] {
+            assert!(RateLimiter::new(rate).is_none(), "{rate}");
+        }
+        assert_eq!(
+            RateLimiter::new(4.0).unwrap().interval,
+            Duration::from_millis(250)
+        );
+    }
+
+    #[tokio::test]
+    async fn spaces_out_rate_limited_slots() {
+        let limiter = RateLimiter::new(50.0).unwrap();
+        let start = Instant::now();
+        // 第一个时间片立即可用，之后每 20ms 一个
+        limiter.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(15));
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn interleaves_weighted_urls() {
+        let schedule = UrlSchedule::new(3, &[5, 1, 1]);
+        assert_eq!(picks(&schedule, 7), [0, 0, 1, 0, 2, 0, 0]);
+        // 下一个周期重复同样的序列
+        assert_eq!(picks(&schedule, 7), [0, 0, 1, 0, 2, 0, 0]);
+
+        let schedule = UrlSchedule::new(2, &[2, 2]);
+        assert_eq!(picks(&schedule, 4), [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn round_robin_without_weights() {
+        assert_eq!(picks(&UrlSchedule::new(3, &[]), 5), [0, 1, 2, 0, 1]);
+        assert_eq!(picks(&UrlSchedule::new(0, &[]), 2), [0, 0]);
+    }
+
+    #[test]
+    fn handles_huge_weights_without_allocating_a_cycle() {
+        let schedule = UrlSchedule::new(2, &[u32::MAX, 1]);
+        let picked = picks(&schedule, 1000);
+        assert!(picked.iter().all(|&i| i == 0));
+    }
+}
@@ -0,0 +1,118 @@
+use interface_performance_test::{
+    BenchmarkConfig, BodyTemplate, CookieJar, IpFamily, JsonPath, JsonSchema, LogTarget,
+    MaxErrorsMode, ProxyConfig, Soak, Spike, SseFormat, TempFile, build_header_map, parse_cookie,
+};
+use reqwest::Method;
+use reqwest::header::HeaderName;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[test]
+fn config_round_trips_through_json() {
+    let mut config = BenchmarkConfig::new("http://127.0.0.1:8000/v1/chat/completions");
+    config.urls = vec![
+        config.url.clone(),
+        "http://127.0.0.1:8001/v1/chat/completions".into(),
+    ];
+    config.url_weights = vec![3, 1];
+    config.method = Method::PUT;
+    config.bodies = vec![r#"{"stream": true}"#.to_string()];
+    let vars = [("MSG".to_string(), "hi {{WORKER_ID}}".to_string())];
+    config.body_template = Some(
+        BodyTemplate::parse(
+            r#"{"id": {{REQUEST_ID}}, "msg": "{{MSG}} {{PROMPT}}"}"#,
+            &vars,
+        )
+        .unwrap()
+        .with_prompts(vec!["first".to_string(), "second \"quoted\"".to_string()]),
+    );
+    let header = |name: &str, value: &str| (name.to_string(), value.to_string());
+    config.headers = build_header_map(&[
+        header("Authorization", "Bearer secret"),
+        header("X-Tag", "a"),
+        header("X-Tag", "b"),
+    ])
+    .unwrap();
+    config.concurrency = 4;
+    config.requests = Some(100);
+    config.duration = Some(Duration::from_millis(1500));
+    config.timeout = Duration::from_millis(100);
+    config.retry_delay = Duration::from_micros(250);
+    config.max_errors = Some(5);
+    config.max_errors_mode = MaxErrorsMode::Total;
+    config.proxy = Some(ProxyConfig {
+        url: "http://proxy.example.com:8080".to_string(),
+        basic_auth: Some(("user".to_string(), "pass".to_string())),
+        no_proxy: Some("localhost".to_string()),
+    });
+    config.ip_family = Some(IpFamily::V6);
+    config.tcp_keepalive = Some(Duration::from_secs(30));
+    config.max_body_size = Some(1 << 20);
+    config.response_schema = Some(JsonSchema::new(json!({"type": "object"})).unwrap());
+    config.extract = vec![JsonPath::parse("$.usage.total_tokens").unwrap()];
+    config.field_assertions = vec![(JsonPath::parse("$.model").unwrap(), "mock".to_string())];
+    config.sse = true;
+    config.sse_format = SseFormat::Anthropic;
+    config.percentiles = vec![50.0, 99.9];
+    config.raw_output = Some(PathBuf::from("raw.jsonl"));
+    config.cookie_jar = Some(CookieJar::new(vec![
+        parse_cookie("session=abc; path=/v1").unwrap(),
+    ]));
+    config.request_id_header = Some(HeaderName::from_static("x-request-id"));
+    config.think_time_jitter = Duration::from_secs_f64(0.123);
+    config.soak = Some(Soak {
+        output: Some(PathBuf::from("soak.jsonl")),
+        interval: Duration::from_secs(60),
+    });
+    config.spike = Some(Spike {
+        baseline_concurrency: 2,
+        delay: Duration::from_secs(10),
+        duration: Duration::from_millis(2500),
+    });
+    config.tags = vec!["v1.2".to_string()];
+    config.log = LogTarget::Stderr;
+
+    let file = TempFile::new("round-trip.json");
+    config.to_json_file(file.path()).unwrap();
+    let loaded = BenchmarkConfig::from_json_file(file.path()).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&loaded).unwrap(),
+        serde_json::to_value(&config).unwrap()
+    );
+    assert_eq!(loaded.method, Method::PUT);
+    assert_eq!(loaded.headers, config.headers);
+    assert_eq!(loaded.body_template, config.body_template);
+    let body = loaded.body_template.as_ref().unwrap().render(1, 0);
+    assert_eq!(body, r#"{"id": 1, "msg": "hi 0 second \"quoted\""}"#);
+    assert_eq!(loaded.duration, Some(Duration::from_millis(1500)));
+    assert_eq!(loaded.timeout, Duration::from_millis(100));
+    assert_eq!(loaded.retry_delay, Duration::from_micros(250));
+    assert_eq!(loaded.think_time_jitter, config.think_time_jitter);
+    assert_eq!(loaded.spike.unwrap().duration, Duration::from_millis(2500));
+    assert_eq!(loaded.cookie_jar, config.cookie_jar);
+    assert_eq!(loaded.proxy, config.proxy);
+    assert_eq!(loaded.extract, config.extract);
+    assert_eq!(loaded.response_schema, config.response_schema);
+}
+
+#[test]
+fn partial_config_uses_defaults() {
+    let file = TempFile::new("partial.json");
+    std::fs::write(
+        file.path(),
+        r#"{"url": "http://localhost/v1", "concurrency": 3, "timeout": 2.5}"#,
+    )
+    .unwrap();
+    let config = BenchmarkConfig::from_json_file(file.path()).unwrap();
+    std::fs::write(file.path(), r#"{"concurrency": 3}"#).unwrap();
+    let missing_url = BenchmarkConfig::from_json_file(file.path());
+
+    assert_eq!(config.url, "http://localhost/v1");
+    assert_eq!(config.concurrency, 3);
+    assert_eq!(config.timeout, Duration::from_millis(2500));
+    assert_eq!(config.method, Method::POST);
+    assert_eq!(config.percentiles, BenchmarkConfig::new("").percentiles);
+    assert!(missing_url.unwrap_err().contains("url"));
+}
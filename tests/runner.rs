@@ -0,0 +1,944 @@
+use interface_performance_test::{
+    BenchmarkBuilder, BenchmarkConfig, BenchmarkRunner, BodyTemplate, Breakpoint, JsonPath,
+    MockLLMServer, ProxyConfig, Soak, Spike, SseFormat, StepLoad, TempFile, load_har_bodies,
+};
+use reqwest::header::HeaderName;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn counts_sse_events_as_tokens() {
+    let server = MockLLMServer::builder()
+        .chunk_count(3)
+        .build()
+        .await
+        .unwrap();
+    let config = BenchmarkBuilder::new(server.url())
+        .body(r#"{"stream": true}"#)
+        .requests(8)
+        .concurrency(2)
+        .sse(true)
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let result = BenchmarkRunner::run(config).await.unwrap();
+    assert_eq!(result.total, 8);
+    assert_eq!(result.success, 8);
+    assert!(result.results.iter().all(|r| r.tokens == 3));
+    assert!(result.ttft.is_some());
+}
+
+#[tokio::test]
+async fn reports_non_2xx_as_errors() {
+    let server = MockLLMServer::builder().status(503).build().await.unwrap();
+    let mut config = BenchmarkConfig::new(server.url());
+    config.duration = Some(Duration::from_millis(300));
+    config.concurrency = 2;
+
+    let result = BenchmarkRunner::run(config).await.unwrap();
+    assert_eq!(result.success, 0);
+    assert!(result.ttft.is_none());
+}
+
+#[tokio::test]
+async fn parses_anthropic_stream() {
+    let body = concat!(
+        "event: message_start\ndata: {\"type\":\"message_start\"}\n\n",
+        "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"!\"}}\n\n",
+        "event: message_delta\ndata: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":7}}\n\n",
+        "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"ignored\"}}\n\n",
+    );
+    let server = MockLLMServer::builder().body(body).build().await.unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    builder
+        .body(r#"{"stream": true}"#)
+        .requests(4)
+        .concurrency(2)
+        .sse_format(SseFormat::Anthropic)
+        .timeout(Duration::from_secs(5));
+
+    let result = BenchmarkRunner::run(builder.build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(result.success, 4);
+    assert!(result.results.iter().all(|r| r.tokens == 2));
+
+    let result = BenchmarkRunner::run(builder.parse_usage_tokens(true).build().unwrap())
+        .await
+        .unwrap();
+    assert!(result.results.iter().all(|r| r.tokens == 7));
+}
+
+#[tokio::test]
+async fn measures_mock_server_ttft() {
+    let server = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(50))
+        .chunk_count(20)
+        .build()
+        .await
+        .unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    builder
+        .body(r#"{"stream": true}"#)
+        .requests(6)
+        .concurrency(3)
+        .sse(true);
+
+    let result = BenchmarkRunner::run(builder.build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(result.success, 6);
+    assert!(result.results.iter().all(|r| r.tokens == 20));
+    assert!(result.ttft.unwrap().min >= 50.0);
+    assert!(server.received_bodies().len() >= 6);
+}
+
+#[tokio::test]
+async fn records_one_itl_sample_per_chunk() {
+    // 6 个内容事件分两块到达，第三块只有 [DONE]
+    let transport = MockLLMServer::builder()
+        .chunk_count(6)
+        .events_per_chunk(3)
+        .chunk_delay(Duration::from_millis(20))
+        .transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder.body("{}").requests(2).concurrency(1).sse(true);
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport)
+        .await
+        .unwrap();
+    assert_eq!(result.success, 2);
+    for r in &result.results {
+        assert_eq!(r.tokens, 6);
+        assert_eq!(r.itl.len(), 1, "{:?}", r.itl);
+        assert!(r.itl[0] >= Duration::from_millis(15));
+    }
+}
+
+#[tokio::test]
+async fn counts_mock_server_errors() {
+    let server = MockLLMServer::builder()
+        .error_rate(1.0)
+        .error_status(503)
+        .build()
+        .await
+        .unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    builder
+        .body("{}")
+        .duration(Duration::from_millis(300))
+        .concurrency(2)
+        .sse(true);
+
+    let result = BenchmarkRunner::run(builder.build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(result.success, 0);
+    assert!(result.errors > 0);
+}
+
+#[tokio::test]
+async fn counts_failures_toward_request_limit() {
+    let server = MockLLMServer::builder()
+        .error_rate(1.0)
+        .build()
+        .await
+        .unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    builder.body("{}").requests(5).concurrency(2).sse(true);
+
+    let run = BenchmarkRunner::run(builder.build().unwrap());
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("benchmark did not stop after 5 failed requests")
+        .unwrap();
+    assert_eq!(result.total, 5);
+    assert_eq!(result.errors, 5);
+    assert_eq!(result.success, 0);
+}
+
+#[tokio::test]
+async fn rejects_zero_concurrency() {
+    let mut config = BenchmarkConfig::new("http://127.0.0.1:1/v1/chat/completions");
+    config.concurrency = 0;
+    config.iterations = Some(5);
+
+    let err = BenchmarkRunner::run(config).await.unwrap_err();
+    assert!(err.to_string().contains("concurrency"));
+}
+
+#[tokio::test]
+async fn ignores_failures_during_ramp_up() {
+    let transport = MockLLMServer::builder().error_rate(1.0).transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .concurrency(2)
+        .ramp_up(Duration::from_millis(200))
+        .duration(Duration::from_millis(100))
+        .think_time(Duration::from_millis(10), Duration::ZERO);
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone())
+        .await
+        .unwrap();
+    assert!(result.errors > 0);
+    // 爬坡期间 worker 0 已发出的请求不计入
+    assert!(result.errors < transport.received_bodies().len());
+}
+
+#[tokio::test]
+async fn discards_warmup_requests() {
+    let transport = MockLLMServer::builder().transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .requests(4)
+        .concurrency(1)
+        .warmup(3)
+        .sse(true);
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.total, 4);
+    assert_eq!(result.success, 4);
+    assert!(transport.received_bodies().len() >= 7);
+}
+
+#[tokio::test]
+async fn finishes_warmup_against_failing_server() {
+    let transport = MockLLMServer::builder().error_rate(1.0).transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder.body("{}").requests(4).concurrency(1).warmup(3);
+
+    let run = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone());
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("warm-up never finished")
+        .unwrap();
+    assert_eq!(result.errors, 4);
+    assert!(transport.received_bodies().len() >= 7);
+}
+
+#[tokio::test]
+async fn retries_failed_requests() {
+    let transport = MockLLMServer::builder().error_rate(1.0).transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .iterations(3)
+        .concurrency(1)
+        .retry(2, Duration::from_millis(1));
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone())
+        .await
+        .unwrap();
+    // 每个请求首次发送加 2 次重试，重试后仍失败的只计一次
+    assert_eq!(result.errors, 3);
+    assert_eq!(transport.received_bodies().len(), 9);
+}
+
+#[tokio::test]
+async fn aborts_after_max_errors() {
+    let transport = MockLLMServer::builder().error_rate(1.0).transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.duration = Some(Duration::from_secs(30));
+    config.concurrency = 1;
+    config.max_errors = Some(3);
+
+    let run = BenchmarkRunner::run_with_transport(config, transport);
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("benchmark did not abort after 3 errors")
+        .unwrap();
+    assert!(result.aborted);
+    assert!(result.errors >= 3);
+    assert_eq!(result.success, 0);
+}
+
+#[tokio::test]
+async fn circuit_breaker_pauses_workers() {
+    let transport = MockLLMServer::builder().error_rate(1.0).transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.duration = Some(Duration::from_millis(500));
+    config.concurrency = 2;
+    config.circuit_breaker = true;
+
+    let run = BenchmarkRunner::run_with_transport(config, transport.clone());
+    let result = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("paused workers did not stop at the deadline")
+        .unwrap();
+    assert!(!result.aborted);
+    // 10 个样本后断开 5 秒，之后只有断开前已发出的请求
+    let sent = transport.received_bodies().len();
+    assert!((10..=12).contains(&sent), "{sent} requests sent");
+}
+
+#[tokio::test]
+async fn stops_reading_at_end_of_stream_event() {
+    for (format, tokens) in [(SseFormat::OpenAi, 2), (SseFormat::Anthropic, 1)] {
+        let server = MockLLMServer::builder()
+            .sse_format(format)
+            .chunk_count(tokens)
+            .unterminated(true)
+            .build()
+            .await
+            .unwrap();
+        let mut builder = BenchmarkBuilder::new(server.url());
+        builder
+            .body(r#"{"stream": true}"#)
+            .requests(2)
+            .concurrency(2)
+            .sse_format(format)
+            .timeout(Duration::from_secs(10));
+        let run = BenchmarkRunner::run(builder.build().unwrap());
+        let result = tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("request did not finish at the end-of-stream event")
+            .unwrap();
+        assert_eq!(result.success, 2);
+        assert!(result.results.iter().all(|r| r.tokens as usize == tokens));
+    }
+}
+
+/// 充当 HTTP 代理：记录每个请求的请求头部分，并返回一段 SSE 流。MockLLMServer 不保留
+/// 请求行和原始请求头，无法检查 absolute-form 请求行与 Proxy-Authorization，因此这里直接读写 TCP
+async fn spawn_proxy() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let recorded = heads.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let heads = heads.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let head = request.split("\r\n\r\n").next().unwrap_or_default();
+                heads.lock().unwrap().push(head.to_string());
+                let body = "data: {\"n\":1}\n\ndata: [DONE]\n\n";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+    (format!("http://{addr}"), recorded)
+}
+
+#[tokio::test]
+async fn sends_requests_through_proxy() {
+    let (proxy_url, heads) = spawn_proxy().await;
+    // .invalid 域名无法解析，只有经由代理才能成功
+    let mut config = BenchmarkConfig::new("http://llm.invalid/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.requests = Some(2);
+    config.concurrency = 1;
+    config.sse = true;
+    let mut proxy = ProxyConfig::new(proxy_url);
+    proxy.basic_auth = Some(("user".to_string(), "pass".to_string()));
+    config.proxy = Some(proxy);
+
+    let result = BenchmarkRunner::run(config).await.unwrap();
+    assert_eq!(result.success, 2);
+    let heads = heads.lock().unwrap();
+    assert_eq!(heads.len(), 2);
+    for head in heads.iter() {
+        // 经由代理时请求行为完整的 URL
+        assert!(
+            head.starts_with("POST http://llm.invalid/v1/chat/completions HTTP/1.1\r\n"),
+            "{head}"
+        );
+        assert!(
+            head.to_ascii_lowercase()
+                .contains("proxy-authorization: basic dxnlcjpwyxnz"),
+            "{head}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn rejects_invalid_proxy_url() {
+    let mut config = BenchmarkConfig::new("http://127.0.0.1:1/v1/chat/completions");
+    config.requests = Some(1);
+    config.proxy = Some(ProxyConfig::new("http://[::1"));
+
+    let run = BenchmarkRunner::run(config);
+    let err = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("invalid proxy was not rejected at startup")
+        .unwrap_err();
+    assert!(
+        err.to_string().to_ascii_lowercase().contains("proxy"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn rejects_https_url_list_entries() {
+    let mut config = BenchmarkConfig::new("http://127.0.0.1:1/v1/chat/completions");
+    config.urls = vec![
+        config.url.clone(),
+        "https://127.0.0.1:2/v1/chat/completions".to_string(),
+    ];
+    config.requests = Some(1);
+
+    let err = BenchmarkRunner::run(config).await.unwrap_err().to_string();
+    assert!(err.contains("https URLs are not supported"), "{err}");
+    assert!(
+        err.contains("https://127.0.0.1:2/v1/chat/completions"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn finished_workers_release_rate_slots() {
+    let transport = MockLLMServer::builder().transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder.body("{}").iterations(1).concurrency(4).sse(true);
+    let mut config = builder.build().unwrap();
+    config.rate = Some(10.0);
+
+    let start = std::time::Instant::now();
+    let result = BenchmarkRunner::run_with_transport(config, transport)
+        .await
+        .unwrap();
+    assert_eq!(result.success, 4);
+    // 4 个请求占用 4 个时间片（0、100、200、300ms），发满的 worker 退出时不再预约
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(600), "{elapsed:?}");
+}
+
+#[tokio::test]
+async fn rejects_tiny_rate() {
+    let mut config = BenchmarkConfig::new("http://127.0.0.1:1/v1/chat/completions");
+    config.requests = Some(1);
+    config.rate = Some(1e-30);
+
+    let err = BenchmarkRunner::run(config).await.unwrap_err();
+    assert!(
+        err.to_string().contains("rate must be a positive number"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn fails_before_sending_when_metrics_port_is_busy() {
+    let busy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let transport = MockLLMServer::builder().transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .duration(Duration::from_secs(30))
+        .sse(true);
+    let mut config = builder.build().unwrap();
+    config.metrics_port = Some(busy.local_addr().unwrap().port());
+
+    let run = BenchmarkRunner::run_with_transport(config, transport.clone());
+    let err = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("benchmark kept running after the metrics port failed")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("--metrics-port 127.0.0.1:"), "{err}");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(transport.received_bodies().is_empty());
+}
+
+#[tokio::test]
+async fn stops_at_duration() {
+    let transport = MockLLMServer::builder()
+        .chunk_delay(Duration::from_millis(5))
+        .transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .duration(Duration::from_millis(300))
+        .concurrency(2)
+        .sse(true);
+
+    let start = std::time::Instant::now();
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport)
+        .await
+        .unwrap();
+    let elapsed = start.elapsed();
+    // 到时后只等待已发出的请求完成，不再发起新请求（留出 worker 看到停止信号前的余量）
+    assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+    assert!(result.success > 2);
+    assert!(
+        result
+            .results
+            .iter()
+            .all(|r| r.start_ts - result.started_at < 0.4)
+    );
+}
+
+#[tokio::test]
+async fn limits_request_rate() {
+    let transport = MockLLMServer::builder().transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.duration = Some(Duration::from_millis(500));
+    config.concurrency = 4;
+    config.sse = true;
+    config.rate = Some(20.0);
+
+    let result = BenchmarkRunner::run_with_transport(config, transport.clone())
+        .await
+        .unwrap();
+    // 每 50ms 一个时间片，与并发数无关
+    let sent = transport.received_bodies().len();
+    assert!((8..=11).contains(&sent), "{sent} requests sent");
+    assert_eq!(result.errors, 0);
+}
+
+#[tokio::test]
+async fn waits_think_time_between_requests() {
+    let transport = MockLLMServer::builder().transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder
+        .body("{}")
+        .duration(Duration::from_millis(450))
+        .concurrency(1)
+        .sse(true)
+        .think_time(Duration::from_millis(100), Duration::ZERO);
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone())
+        .await
+        .unwrap();
+    let sent = transport.received_bodies().len();
+    assert!((4..=5).contains(&sent), "{sent} requests sent");
+    let mut starts: Vec<f64> = result.results.iter().map(|r| r.start_ts).collect();
+    starts.sort_by(f64::total_cmp);
+    assert!(starts.windows(2).all(|w| w[1] - w[0] >= 0.1), "{starts:?}");
+}
+
+#[tokio::test]
+async fn runs_each_load_step() {
+    let server = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(10))
+        .build()
+        .await
+        .unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    builder.body("{}").requests(1).sse(true);
+    let steps = StepLoad {
+        start: 1,
+        step: 2,
+        max: 5,
+        duration: Duration::from_millis(150),
+    };
+
+    let mut reported = Vec::new();
+    let results = BenchmarkRunner::run_steps(builder.build().unwrap(), steps, |step| {
+        reported.push(step.concurrency)
+    })
+    .await
+    .unwrap();
+    assert_eq!(reported, [1, 3, 5]);
+    let levels: Vec<usize> = results.iter().map(|s| s.concurrency).collect();
+    assert_eq!(levels, [1, 3, 5]);
+    for step in &results {
+        // 每步按时长运行，不受 requests 限制
+        assert!(step.result.success > 1, "{} requests", step.result.success);
+        let workers = step
+            .result
+            .results
+            .iter()
+            .map(|r| r.worker_id)
+            .max()
+            .unwrap();
+        assert_eq!(workers + 1, step.concurrency);
+    }
+}
+
+#[tokio::test]
+async fn finds_breakpoint_concurrency() {
+    // 超过 3 个并发请求时服务端返回 500，出现失败即视为超标
+    let server = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(30))
+        .max_concurrency(3)
+        .build()
+        .await
+        .unwrap();
+    let mut builder = BenchmarkBuilder::new(server.url());
+    // --iterations 优先于每步的请求数：每个 worker 发满即退出，上一次探测不会留下仍在处理的请求
+    builder.body("{}").iterations(2).sse(true);
+    let search = Breakpoint {
+        latency_target: Duration::from_secs(1),
+        requests: 6,
+        iterations: 4,
+    };
+
+    let mut probes = Vec::new();
+    let result = BenchmarkRunner::find_breakpoint(builder.build().unwrap(), search, |step, ok| {
+        probes.push((step.concurrency, ok))
+    })
+    .await
+    .unwrap();
+    // 翻倍到 4 时超标，再在 2 与 4 之间二分
+    assert_eq!(probes, [(1, true), (2, true), (4, false), (3, true)]);
+    assert_eq!(result.max_concurrency, Some(3));
+    assert!(result.requests_per_sec.is_some());
+    assert_eq!(result.steps.len(), 4);
+}
+
+#[tokio::test]
+async fn adds_workers_only_during_spike() {
+    let transport = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(20))
+        .transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.duration = Some(Duration::from_millis(600));
+    config.concurrency = 4;
+    config.sse = true;
+    config.spike = Some(Spike {
+        baseline_concurrency: 1,
+        delay: Duration::from_millis(200),
+        duration: Duration::from_millis(200),
+    });
+
+    let result = BenchmarkRunner::run_with_transport(config, transport)
+        .await
+        .unwrap();
+    let phases: Vec<&str> = result.phases.iter().map(|p| p.phase.as_str()).collect();
+    assert_eq!(phases, ["pre-spike", "spike", "post-spike"]);
+    let (pre, spike, post) = (&result.phases[0], &result.phases[1], &result.phases[2]);
+    assert!(
+        spike.requests_per_sec > pre.requests_per_sec * 2.0,
+        "pre {:.1} rps, spike {:.1} rps",
+        pre.requests_per_sec,
+        spike.requests_per_sec
+    );
+    assert!(post.requests_per_sec < spike.requests_per_sec / 2.0);
+    // 突发阶段之外只有基础并发的 worker 发起请求
+    let spike_end = result.started_at + 0.4;
+    assert!(
+        result
+            .results
+            .iter()
+            .filter(|r| r.start_ts < result.started_at + 0.2 || r.start_ts > spike_end + 0.05)
+            .all(|r| r.worker_id == 0)
+    );
+}
+
+#[tokio::test]
+async fn records_soak_windows() {
+    let transport = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(10))
+        .transport();
+    let output = TempFile::new("soak.jsonl");
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.duration = Some(Duration::from_millis(450));
+    config.concurrency = 2;
+    config.sse = true;
+    config.soak = Some(Soak {
+        output: Some(output.path().to_path_buf()),
+        interval: Duration::from_millis(100),
+    });
+
+    let result = BenchmarkRunner::run_with_transport(config, transport)
+        .await
+        .unwrap();
+    let soak = result.soak.expect("no soak summary");
+    assert!((3..=5).contains(&soak.windows), "{} windows", soak.windows);
+    assert!(soak.first_p99_ttft_ms.is_some() && soak.last_p99_ttft_ms.is_some());
+    let lines = std::fs::read_to_string(output.path()).unwrap();
+    let windows: Vec<serde_json::Value> = lines
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(windows.len(), soak.windows);
+    assert!(windows.iter().all(|w| w["success"].as_u64() > Some(0)));
+}
+
+#[tokio::test]
+async fn sends_fixed_iterations_per_worker() {
+    let transport = MockLLMServer::builder()
+        .ttft_delay(Duration::from_millis(5))
+        .transport();
+    let mut builder = BenchmarkBuilder::new("http://mock/v1/chat/completions");
+    builder.body("{}").iterations(3).concurrency(2).sse(true);
+
+    let result = BenchmarkRunner::run_with_transport(builder.build().unwrap(), transport.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.total, 6);
+    assert_eq!(result.success, 6);
+    // 发满后 worker 退出，不会多发请求
+    assert_eq!(transport.received_bodies().len(), 6);
+    for worker in 0..2 {
+        let sent = result.results.iter().filter(|r| r.worker_id == worker);
+        assert_eq!(sent.count(), 3);
+    }
+}
+
+#[tokio::test]
+async fn renders_body_template_per_request() {
+    let transport = MockLLMServer::builder().transport();
+    let vars = [("MODEL".to_string(), "m-{{WORKER_ID}}".to_string())];
+    let template = r#"{"id": {{REQUEST_ID}}, "model": "{{MODEL}}"}"#;
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.body_template = Some(BodyTemplate::parse(template, &vars).unwrap());
+    config.iterations = Some(2);
+    config.concurrency = 2;
+    config.sse = true;
+
+    let result = BenchmarkRunner::run_with_transport(config, transport.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.success, 4);
+    let bodies: Vec<serde_json::Value> = transport
+        .received_bodies()
+        .iter()
+        .map(|body| serde_json::from_str(body).unwrap())
+        .collect();
+    let mut ids: Vec<u64> = bodies.iter().map(|b| b["id"].as_u64().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, [0, 1, 2, 3]);
+    for model in ["m-0", "m-1"] {
+        assert_eq!(bodies.iter().filter(|b| b["model"] == model).count(), 2);
+    }
+}
+
+/// 非流式 chat completions 响应
+const COMPLETION: &str = r#"{"id":"cmpl-1","model":"mock","usage":{"completion_tokens":12}}"#;
+
+#[tokio::test]
+async fn extracts_response_fields() {
+    let transport = MockLLMServer::builder().body(COMPLETION).transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.iterations = Some(3);
+    config.concurrency = 1;
+    config.extract = ["$.usage.completion_tokens", "$.model", "$.missing"]
+        .map(|path| JsonPath::parse(path).unwrap())
+        .to_vec();
+
+    let result = BenchmarkRunner::run_with_transport(config, transport)
+        .await
+        .unwrap();
+    assert_eq!(result.success, 3);
+    let [tokens, model, missing] = &result.extractions[..] else {
+        panic!("{:?}", result.extractions);
+    };
+    let numeric = tokens.numeric.as_ref().unwrap();
+    assert_eq!((tokens.found, numeric.mean), (3, 12.0));
+    assert_eq!(model.top_values, [("mock".to_string(), 3)]);
+    assert_eq!((missing.found, missing.missing), (0, 3));
+    assert!(
+        result
+            .results
+            .iter()
+            .all(|r| r.extracted[0] == Some(serde_json::json!(12)))
+    );
+}
+
+#[tokio::test]
+async fn counts_assertion_failures_without_failing_requests() {
+    let transport = MockLLMServer::builder().body(COMPLETION).transport();
+    let assertion = |path: &str, expected: &str| (JsonPath::parse(path).unwrap(), expected.into());
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.iterations = Some(2);
+    config.concurrency = 1;
+    config.expected_status = Some(200);
+    config.field_assertions = vec![
+        assertion("$.model", "mock"),
+        assertion("$.usage.completion_tokens", "12"),
+    ];
+
+    let result = BenchmarkRunner::run_with_transport(config.clone(), transport.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.assertion_failures, Some(0));
+
+    for (status, field) in [
+        (Some(201), assertion("$.model", "mock")),
+        (None, assertion("$.usage.completion_tokens", "13")),
+        (None, assertion("$.missing", "x")),
+    ] {
+        config.expected_status = status;
+        config.field_assertions = vec![field];
+        let result = BenchmarkRunner::run_with_transport(config.clone(), transport.clone())
+            .await
+            .unwrap();
+        // 未通过检查的请求仍计为成功
+        assert_eq!(result.success, 2);
+        assert_eq!(result.assertion_failures, Some(2));
+        assert!(result.results.iter().all(|r| r.assertion_failed));
+    }
+}
+
+#[tokio::test]
+async fn truncates_responses_at_max_body_size() {
+    // 流不结束，只有达到上限时读取才会停止
+    let transport = MockLLMServer::builder()
+        .chunk_count(5)
+        .unterminated(true)
+        .transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.sse = true;
+    config.iterations = Some(2);
+    config.concurrency = 1;
+    config.max_body_size = Some(1);
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        BenchmarkRunner::run_with_transport(config, transport),
+    )
+    .await
+    .expect("truncated responses should not wait for the rest of the stream")
+    .unwrap();
+    assert_eq!(result.success, 2);
+    assert_eq!(result.truncated_responses, Some(2));
+    for r in &result.results {
+        assert!(r.truncated);
+        assert_eq!(r.chunks, 1);
+    }
+}
+
+#[tokio::test]
+async fn sends_unique_request_ids() {
+    let transport = MockLLMServer::builder().transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.sse = true;
+    config.iterations = Some(3);
+    config.concurrency = 2;
+    config.request_id_header = Some(HeaderName::from_static("x-request-id"));
+
+    let result = BenchmarkRunner::run_with_transport(config, transport.clone())
+        .await
+        .unwrap();
+    let sent: HashSet<String> = transport
+        .received_requests()
+        .iter()
+        .map(|req| req.headers["x-request-id"].to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(sent.len(), 6);
+    // 结果中记录的 ID 与发送的请求头一一对应
+    let recorded: HashSet<String> = result
+        .results
+        .iter()
+        .map(|r| r.request_id.clone().unwrap())
+        .collect();
+    assert_eq!(recorded, sent);
+}
+
+#[tokio::test]
+async fn injects_traceparent_per_request() {
+    let transport = MockLLMServer::builder().transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec!["{}".to_string()];
+    config.sse = true;
+    config.iterations = Some(4);
+    config.concurrency = 1;
+    config.trace_context = true;
+
+    let result = BenchmarkRunner::run_with_transport(config, transport.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.success, 4);
+    let mut trace_ids = HashSet::new();
+    let mut span_ids = HashSet::new();
+    for req in transport.received_requests() {
+        let value = req.headers["traceparent"].to_str().unwrap();
+        let parts: Vec<&str> = value.split('-').collect();
+        assert_eq!(parts.len(), 4, "{value}");
+        assert_eq!((parts[0], parts[3]), ("00", "01"));
+        assert_eq!((parts[1].len(), parts[2].len()), (32, 16));
+        assert!(
+            parts[1..3]
+                .iter()
+                .all(|p| p.bytes().all(|b| b.is_ascii_hexdigit()))
+        );
+        trace_ids.insert(parts[1].to_string());
+        span_ids.insert(parts[2].to_string());
+    }
+    // 同一次压测共用一个 trace ID，每个请求的 span ID 不同
+    assert_eq!(trace_ids.len(), 1);
+    assert_eq!(span_ids.len(), 4);
+}
+
+#[tokio::test]
+async fn records_requests_as_har() {
+    let har = TempFile::new("record.har");
+    let transport = MockLLMServer::builder().body(COMPLETION).transport();
+    let mut config = BenchmarkConfig::new("http://mock/v1/chat/completions");
+    config.bodies = vec![r#"{"n":1}"#.to_string(), r#"{"n":2}"#.to_string()];
+    config.iterations = Some(2);
+    config.concurrency = 1;
+    config.record_har = Some(har.path().to_path_buf());
+
+    let result = BenchmarkRunner::run_with_transport(config, transport)
+        .await
+        .unwrap();
+    assert_eq!(result.success, 2);
+    let text = std::fs::read_to_string(har.path()).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&text).unwrap();
+    let entries = log["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    for entry in entries {
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(entry["request"]["url"], "http://mock/v1/chat/completions");
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["content"]["text"], COMPLETION);
+    }
+    // 录制的文件可以再作为 --har-file 回放
+    let bodies = load_har_bodies(har.path(), "/v1/chat/completions").unwrap();
+    assert_eq!(bodies, [r#"{"n":1}"#, r#"{"n":2}"#]);
+}
+
+#[tokio::test]
+async fn splits_requests_by_url_weight() {
+    let transport = MockLLMServer::builder().transport();
+    let (a, b) = ("http://mock/a", "http://mock/b");
+    let mut config = BenchmarkConfig::new(a);
+    config.urls = vec![a.to_string(), b.to_string()];
+    config.url_weights = vec![3, 1];
+    config.bodies = vec!["{}".to_string()];
+    config.sse = true;
+    config.iterations = Some(8);
+    config.concurrency = 1;
+
+    let result = BenchmarkRunner::run_with_transport(config, transport.clone())
+        .await
+        .unwrap();
+    let sent: Vec<String> = transport
+        .received_requests()
+        .into_iter()
+        .map(|req| req.url)
+        .collect();
+    // 平滑加权轮询：3:1 的权重交错分配，而不是连续发往同一个 URL
+    assert_eq!(sent, [a, a, b, a, a, a, b, a]);
+    let per_url: Vec<(&str, usize)> = result
+        .urls
+        .iter()
+        .map(|u| (u.url.as_str(), u.requests))
+        .collect();
+    assert_eq!(per_url, [(a, 6), (b, 2)]);
+}